@@ -8,10 +8,11 @@ use criterion::{
     black_box, criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput,
 };
 
-use refpool::{Pool, PoolDefault, PoolRef};
+use refpool::{Pool, PoolBox, PoolDefault, PoolRef};
 
 const SIZES: &[usize] = &[1024, 2048, 4096, 8192, 16384, 32768, 65536, 131_072];
 
+#[allow(dead_code)]
 struct BigLumpOfUsize([usize; 1024]);
 
 impl Default for BigLumpOfUsize {
@@ -20,6 +21,9 @@ impl Default for BigLumpOfUsize {
     }
 }
 
+#[cfg(not(feature = "default_impl"))]
+impl refpool::PoolDefaultImpl for BigLumpOfUsize {}
+
 pub fn alloc<A: PoolDefault, P: Default>(name: &str, c: &mut Criterion) {
     let mut group = c.benchmark_group(name);
     for size in SIZES {
@@ -91,6 +95,36 @@ pub fn realloc<A: PoolDefault, P: Default>(name: &str, c: &mut Criterion) {
     group.finish();
 }
 
+pub fn churn(c: &mut Criterion) {
+    let mut group = c.benchmark_group("churn/usize");
+    for size in SIZES {
+        group.throughput(Throughput::Elements(*size as u64));
+        group.bench_with_input(BenchmarkId::new("PoolRef::new", size), size, |b, &size| {
+            b.iter_batched_ref(
+                || Pool::<usize>::new(1),
+                |pool| {
+                    for i in 0..size {
+                        black_box(PoolRef::new(pool, i));
+                    }
+                },
+                BatchSize::SmallInput,
+            );
+        });
+        group.bench_with_input(BenchmarkId::new("PoolBox::new", size), size, |b, &size| {
+            b.iter_batched_ref(
+                || Pool::<usize>::new(1),
+                |pool| {
+                    for i in 0..size {
+                        black_box(PoolBox::new(pool, i));
+                    }
+                },
+                BatchSize::SmallInput,
+            );
+        });
+    }
+    group.finish();
+}
+
 fn alloc_usize(c: &mut Criterion) {
     alloc::<usize, Rc<usize>>("alloc/usize", c)
 }
@@ -112,6 +146,7 @@ criterion_group!(
     alloc_usize,
     realloc_usize,
     alloc_1024x_usize,
-    realloc_1024x_usize
+    realloc_1024x_usize,
+    churn
 );
 criterion_main!(refpool);
@@ -0,0 +1,95 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Building a tiny persistent vector out of pooled `imbl` chunks.
+//!
+//! `imbl::Vector` allocates its nodes itself and has no way to take a `Pool`,
+//! so this example builds a stripped down vector of its own in the same shape:
+//! leaf nodes are `Chunk`s shared between versions of the structure through
+//! reference counted pointers, and updating a version copies only the chunks
+//! it touches. Allocating those chunks from a `Pool` means the copies reuse
+//! memory freed by discarded versions instead of going back to the system
+//! allocator.
+//!
+//! Run with `cargo run --example imbl_chunks --features imbl-compat`.
+
+use imbl_sized_chunks::Chunk;
+use refpool::{Pool, PoolRef};
+
+const CHUNK_SIZE: usize = 64;
+
+type Leaf = Chunk<usize, CHUNK_SIZE>;
+
+#[derive(Clone)]
+struct PooledVector {
+    leaves: Vec<PoolRef<Leaf>>,
+}
+
+impl PooledVector {
+    fn new() -> Self {
+        PooledVector { leaves: Vec::new() }
+    }
+
+    fn push_back(&mut self, pool: &Pool<Leaf>, value: usize) {
+        match self.leaves.last_mut() {
+            Some(leaf) if !leaf.is_full() => PoolRef::make_mut(pool, leaf).push_back(value),
+            _ => {
+                let mut leaf = PoolRef::default(pool);
+                PoolRef::make_mut(pool, &mut leaf).push_back(value);
+                self.leaves.push(leaf);
+            }
+        }
+    }
+
+    fn set(&mut self, pool: &Pool<Leaf>, index: usize, value: usize) {
+        let leaf = &mut self.leaves[index / CHUNK_SIZE];
+        PoolRef::make_mut(pool, leaf)[index % CHUNK_SIZE] = value;
+    }
+
+    fn get(&self, index: usize) -> Option<&usize> {
+        self.leaves
+            .get(index / CHUNK_SIZE)
+            .and_then(|leaf| leaf.get(index % CHUNK_SIZE))
+    }
+
+    fn len(&self) -> usize {
+        self.leaves.iter().map(|leaf| leaf.len()).sum()
+    }
+}
+
+fn main() {
+    let pool: Pool<Leaf> = Pool::new(1024).filled();
+
+    let mut original = PooledVector::new();
+    for i in 0..CHUNK_SIZE * 16 {
+        original.push_back(&pool, i);
+    }
+    assert_eq!(CHUNK_SIZE * 16, original.len());
+
+    // Cloning the vector only bumps the reference counts of its leaves.
+    let mut updated = original.clone();
+    updated.set(&pool, 0, 1337);
+    assert_eq!(Some(&0), original.get(0));
+    assert_eq!(Some(&1337), updated.get(0));
+
+    // Only the modified leaf was copied, so the two versions share the rest.
+    let shared = original
+        .leaves
+        .iter()
+        .zip(updated.leaves.iter())
+        .filter(|(left, right)| PoolRef::ptr_eq(left, right))
+        .count();
+    assert_eq!(15, shared);
+
+    // When a version is dropped, its unshared leaves go back into the pool.
+    let before = pool.get_pool_size();
+    drop(updated);
+    assert_eq!(before + 1, pool.get_pool_size());
+
+    println!(
+        "{} leaves in use, {} chunks waiting in the pool",
+        original.leaves.len(),
+        pool.get_pool_size()
+    );
+}
@@ -0,0 +1,51 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+/// The number of allocations over which the adaptive policy observes demand
+/// before resizing the pool.
+pub(crate) const ADAPTIVE_WINDOW: usize = 1024;
+
+/// Demand tracking for pools which resize themselves.
+#[derive(Debug)]
+pub(crate) struct Adaptive {
+    min_size: usize,
+    max_size: usize,
+    allocations: usize,
+    outstanding: usize,
+    high_water: usize,
+}
+
+impl Adaptive {
+    pub(crate) fn new(min_size: usize, max_size: usize) -> Self {
+        Adaptive {
+            min_size,
+            max_size: max_size.max(min_size),
+            allocations: 0,
+            outstanding: 0,
+            high_water: 0,
+        }
+    }
+
+    /// Record an allocation, returning a new size for the pool if the
+    /// observation window has ended.
+    pub(crate) fn allocated(&mut self) -> Option<usize> {
+        self.outstanding += 1;
+        self.high_water = self.high_water.max(self.outstanding);
+        self.allocations += 1;
+        if self.allocations < ADAPTIVE_WINDOW {
+            return None;
+        }
+        // At most `high_water` values could have come back to the pool at
+        // once during the last window, so that's how much it needs to hold.
+        let size = self.high_water.max(self.min_size).min(self.max_size);
+        self.allocations = 0;
+        self.high_water = self.outstanding;
+        Some(size)
+    }
+
+    /// Record that an allocation was freed.
+    pub(crate) fn freed(&mut self) {
+        self.outstanding = self.outstanding.saturating_sub(1);
+    }
+}
@@ -0,0 +1,296 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::borrow::{Borrow, BorrowMut};
+use std::cmp::Ordering;
+use std::fmt::{Debug, Display, Error, Formatter};
+use std::hash::{Hash, Hasher};
+use std::ops::{Deref, DerefMut};
+
+use crate::byte_pool::{Align, Alignment};
+use crate::Pool;
+#[cfg(not(feature = "default_impl"))]
+use crate::{PoolClone, PoolDefault};
+#[cfg(not(feature = "default_impl"))]
+use std::mem::MaybeUninit;
+
+/// A value of `A` aligned to at least `ALIGN` bytes.
+///
+/// Because a pool's chunks are laid out with the value first, a
+/// `Pool<Aligned<A, ALIGN>>` hands out chunks which all start on an `ALIGN`
+/// byte boundary, and whose size is rounded up to a multiple of `ALIGN`, so no
+/// two chunks ever share an `ALIGN` sized block of memory.
+///
+/// `Aligned` dereferences to its value, and implements
+/// [`PoolDefault`][PoolDefault] and [`PoolClone`][PoolClone] when its value
+/// does.
+///
+/// # Examples
+///
+/// ```rust
+/// # use refpool::{Aligned, Pool, PoolRef};
+/// let pool: Pool<Aligned<[f32; 4], 64>> = Pool::new(16);
+/// let vector = PoolRef::new(&pool, Aligned::new([1.0, 2.0, 3.0, 4.0]));
+/// assert_eq!(0, vector.as_ptr() as usize % 64);
+/// assert_eq!(10.0, vector.iter().sum::<f32>());
+/// ```
+///
+/// [PoolDefault]: trait.PoolDefault.html
+/// [PoolClone]: trait.PoolClone.html
+#[repr(C)]
+pub struct Aligned<A, const ALIGN: usize>
+where
+    Align<ALIGN>: Alignment,
+{
+    _align: [<Align<ALIGN> as Alignment>::Archetype; 0],
+    value: A,
+}
+
+/// A value of `A` aligned to the size of a cache line.
+///
+/// Values in a [`CacheAlignedPool`][CacheAlignedPool] never share a cache
+/// line with one another, so threads reading and writing neighbouring values
+/// don't slow each other down with false sharing.
+///
+/// This is 128 bytes on `x86_64`, `aarch64` and `powerpc64`, where the CPU
+/// tends to fetch cache lines in pairs, and 64 bytes everywhere else.
+///
+/// [CacheAlignedPool]: type.CacheAlignedPool.html
+#[cfg(any(
+    target_arch = "x86_64",
+    target_arch = "aarch64",
+    target_arch = "powerpc64"
+))]
+pub type CacheAligned<A> = Aligned<A, 128>;
+
+/// A value of `A` aligned to the size of a cache line.
+///
+/// Values in a [`CacheAlignedPool`][CacheAlignedPool] never share a cache
+/// line with one another, so threads reading and writing neighbouring values
+/// don't slow each other down with false sharing.
+///
+/// This is 128 bytes on `x86_64`, `aarch64` and `powerpc64`, where the CPU
+/// tends to fetch cache lines in pairs, and 64 bytes everywhere else.
+///
+/// [CacheAlignedPool]: type.CacheAlignedPool.html
+#[cfg(not(any(
+    target_arch = "x86_64",
+    target_arch = "aarch64",
+    target_arch = "powerpc64"
+)))]
+pub type CacheAligned<A> = Aligned<A, 64>;
+
+/// A pool whose chunks are padded out to whole cache lines.
+///
+/// # Examples
+///
+/// ```rust
+/// # use refpool::{CacheAligned, CacheAlignedPool, PoolRef};
+/// # use std::sync::atomic::{AtomicUsize, Ordering};
+/// let pool: CacheAlignedPool<AtomicUsize> = CacheAlignedPool::new(16);
+/// let left = PoolRef::new(&pool, CacheAligned::new(AtomicUsize::new(0)));
+/// let right = PoolRef::new(&pool, CacheAligned::new(AtomicUsize::new(0)));
+/// left.fetch_add(1, Ordering::Relaxed);
+/// assert_eq!(1, left.load(Ordering::Relaxed));
+/// assert_eq!(0, right.load(Ordering::Relaxed));
+/// ```
+pub type CacheAlignedPool<A> = Pool<CacheAligned<A>>;
+
+impl<A, const ALIGN: usize> Aligned<A, ALIGN>
+where
+    Align<ALIGN>: Alignment,
+{
+    /// Wrap a value.
+    pub fn new(value: A) -> Self {
+        Aligned { _align: [], value }
+    }
+
+    /// Unwrap the value.
+    pub fn into_inner(this: Self) -> A {
+        this.value
+    }
+}
+
+impl<A, const ALIGN: usize> Default for Aligned<A, ALIGN>
+where
+    A: Default,
+    Align<ALIGN>: Alignment,
+{
+    fn default() -> Self {
+        Self::new(A::default())
+    }
+}
+
+impl<A, const ALIGN: usize> Clone for Aligned<A, ALIGN>
+where
+    A: Clone,
+    Align<ALIGN>: Alignment,
+{
+    fn clone(&self) -> Self {
+        Self::new(self.value.clone())
+    }
+}
+
+impl<A, const ALIGN: usize> Copy for Aligned<A, ALIGN>
+where
+    A: Copy,
+    Align<ALIGN>: Alignment,
+{
+}
+
+#[cfg(not(feature = "default_impl"))]
+impl<A, const ALIGN: usize> PoolDefault for Aligned<A, ALIGN>
+where
+    A: PoolDefault,
+    Align<ALIGN>: Alignment,
+{
+    unsafe fn default_uninit(target: &mut MaybeUninit<Self>) {
+        let value = std::ptr::addr_of_mut!((*target.as_mut_ptr()).value);
+        A::default_uninit(&mut *value.cast::<MaybeUninit<A>>());
+    }
+}
+
+#[cfg(not(feature = "default_impl"))]
+impl<A, const ALIGN: usize> PoolClone for Aligned<A, ALIGN>
+where
+    A: PoolClone,
+    Align<ALIGN>: Alignment,
+{
+    unsafe fn clone_uninit(&self, target: &mut MaybeUninit<Self>) {
+        let value = std::ptr::addr_of_mut!((*target.as_mut_ptr()).value);
+        self.value
+            .clone_uninit(&mut *value.cast::<MaybeUninit<A>>());
+    }
+}
+
+impl<A, const ALIGN: usize> From<A> for Aligned<A, ALIGN>
+where
+    Align<ALIGN>: Alignment,
+{
+    fn from(value: A) -> Self {
+        Self::new(value)
+    }
+}
+
+impl<A, const ALIGN: usize> Deref for Aligned<A, ALIGN>
+where
+    Align<ALIGN>: Alignment,
+{
+    type Target = A;
+    fn deref(&self) -> &Self::Target {
+        &self.value
+    }
+}
+
+impl<A, const ALIGN: usize> DerefMut for Aligned<A, ALIGN>
+where
+    Align<ALIGN>: Alignment,
+{
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.value
+    }
+}
+
+impl<A, const ALIGN: usize> AsRef<A> for Aligned<A, ALIGN>
+where
+    Align<ALIGN>: Alignment,
+{
+    fn as_ref(&self) -> &A {
+        &self.value
+    }
+}
+
+impl<A, const ALIGN: usize> AsMut<A> for Aligned<A, ALIGN>
+where
+    Align<ALIGN>: Alignment,
+{
+    fn as_mut(&mut self) -> &mut A {
+        &mut self.value
+    }
+}
+
+impl<A, const ALIGN: usize> Borrow<A> for Aligned<A, ALIGN>
+where
+    Align<ALIGN>: Alignment,
+{
+    fn borrow(&self) -> &A {
+        &self.value
+    }
+}
+
+impl<A, const ALIGN: usize> BorrowMut<A> for Aligned<A, ALIGN>
+where
+    Align<ALIGN>: Alignment,
+{
+    fn borrow_mut(&mut self) -> &mut A {
+        &mut self.value
+    }
+}
+
+impl<A, const ALIGN: usize> PartialEq for Aligned<A, ALIGN>
+where
+    A: PartialEq,
+    Align<ALIGN>: Alignment,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.value == other.value
+    }
+}
+
+impl<A, const ALIGN: usize> Eq for Aligned<A, ALIGN>
+where
+    A: Eq,
+    Align<ALIGN>: Alignment,
+{
+}
+
+impl<A, const ALIGN: usize> PartialOrd for Aligned<A, ALIGN>
+where
+    A: PartialOrd,
+    Align<ALIGN>: Alignment,
+{
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.value.partial_cmp(&other.value)
+    }
+}
+
+impl<A, const ALIGN: usize> Ord for Aligned<A, ALIGN>
+where
+    A: Ord,
+    Align<ALIGN>: Alignment,
+{
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.value.cmp(&other.value)
+    }
+}
+
+impl<A, const ALIGN: usize> Hash for Aligned<A, ALIGN>
+where
+    A: Hash,
+    Align<ALIGN>: Alignment,
+{
+    fn hash<H: Hasher>(&self, hasher: &mut H) {
+        self.value.hash(hasher)
+    }
+}
+
+impl<A, const ALIGN: usize> Display for Aligned<A, ALIGN>
+where
+    A: Display,
+    Align<ALIGN>: Alignment,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        self.value.fmt(f)
+    }
+}
+
+impl<A, const ALIGN: usize> Debug for Aligned<A, ALIGN>
+where
+    A: Debug,
+    Align<ALIGN>: Alignment,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        self.value.fmt(f)
+    }
+}
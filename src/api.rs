@@ -0,0 +1,134 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::ops::{Deref, DerefMut};
+
+#[cfg(not(feature = "disable-pool"))]
+use crate::box_handle::PoolBox;
+#[cfg(not(feature = "disable-pool"))]
+use crate::pool::Pool;
+#[cfg(not(feature = "disable-pool"))]
+use crate::ref_handle::PoolRef;
+use crate::{PoolClone, PoolDefault};
+
+/// The common interface of [`Pool`][Pool] and
+/// [`fakepool::Pool`][fakepool::Pool].
+///
+/// The [`fakepool`][fakepool] module is code compatible with the real thing,
+/// but that only lets you choose between them by changing your imports. This
+/// trait lets you write code which is generic over whether it's pooling its
+/// allocations or not, and make that choice at the type level instead.
+///
+/// # Examples
+///
+/// ```rust
+/// # use refpool::{fakepool, Pool, PoolApi};
+/// fn build_list<P: PoolApi<usize>>(pool: &P) -> Vec<P::Ref> {
+///     (0..10).map(|i| pool.new_ref(i)).collect()
+/// }
+///
+/// let pooled = build_list(&Pool::new(16));
+/// let unpooled = build_list(&fakepool::Pool::new(16));
+/// assert_eq!(9, *pooled[9]);
+/// assert_eq!(9, *unpooled[9]);
+/// ```
+///
+/// [Pool]: struct.Pool.html
+/// [fakepool]: fakepool/index.html
+/// [fakepool::Pool]: fakepool/struct.Pool.html
+pub trait PoolApi<A>: Clone {
+    /// The reference counted handle type, [`PoolRef`][PoolRef] or its fake
+    /// equivalent.
+    ///
+    /// [PoolRef]: struct.PoolRef.html
+    type Ref: Deref<Target = A> + Clone;
+
+    /// The owning handle type, [`PoolBox`][PoolBox] or its fake equivalent.
+    ///
+    /// [PoolBox]: struct.PoolBox.html
+    type Box: DerefMut<Target = A>;
+
+    /// Construct a new pool with a given max size.
+    fn with_max_size(max_size: usize) -> Self;
+
+    /// Get the maximum size of the pool.
+    fn get_max_size(&self) -> usize;
+
+    /// Get the current size of the pool.
+    fn get_pool_size(&self) -> usize;
+
+    /// Wrap a value in a reference counted handle.
+    fn new_ref(&self, value: A) -> Self::Ref;
+
+    /// Construct a reference counted handle with a default value.
+    fn default_ref(&self) -> Self::Ref
+    where
+        A: PoolDefault;
+
+    /// Wrap a value in an owning handle.
+    fn new_box(&self, value: A) -> Self::Box;
+
+    /// Construct an owning handle with a default value.
+    fn default_box(&self) -> Self::Box
+    where
+        A: PoolDefault;
+
+    /// Get a mutable reference to the value inside a reference counted
+    /// handle, cloning it first if the handle isn't unique.
+    fn make_mut<'a>(&self, this: &'a mut Self::Ref) -> &'a mut A
+    where
+        A: PoolClone;
+}
+
+#[cfg(not(feature = "disable-pool"))]
+impl<A> PoolApi<A> for Pool<A> {
+    type Ref = PoolRef<A>;
+    type Box = PoolBox<A>;
+
+    fn with_max_size(max_size: usize) -> Self {
+        Pool::new(max_size)
+    }
+
+    fn get_max_size(&self) -> usize {
+        Pool::get_max_size(self)
+    }
+
+    fn get_pool_size(&self) -> usize {
+        Pool::get_pool_size(self)
+    }
+
+    #[cfg_attr(feature = "trace-alloc", track_caller)]
+    fn new_ref(&self, value: A) -> Self::Ref {
+        PoolRef::new(self, value)
+    }
+
+    #[cfg_attr(feature = "trace-alloc", track_caller)]
+    fn default_ref(&self) -> Self::Ref
+    where
+        A: PoolDefault,
+    {
+        PoolRef::default(self)
+    }
+
+    #[cfg_attr(feature = "trace-alloc", track_caller)]
+    fn new_box(&self, value: A) -> Self::Box {
+        PoolBox::new(self, value)
+    }
+
+    #[cfg_attr(feature = "trace-alloc", track_caller)]
+    fn default_box(&self) -> Self::Box
+    where
+        A: PoolDefault,
+    {
+        PoolBox::default(self)
+    }
+
+    #[cfg_attr(feature = "trace-alloc", track_caller)]
+    fn make_mut<'a>(&self, this: &'a mut Self::Ref) -> &'a mut A
+    where
+        A: PoolClone,
+    {
+        PoolRef::make_mut(self, this)
+    }
+}
@@ -0,0 +1,79 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use arbitrary::{Arbitrary, Result, Unstructured};
+
+use crate::box_handle::PoolBox;
+use crate::pool::Pool;
+use crate::ref_handle::PoolRef;
+
+/// The maximum size of the pools installed for generating arbitrary values,
+/// if the thread doesn't have one already.
+const FUZZ_POOL_SIZE: usize = 256;
+
+/// Get the current thread's pool for `A`, installing one for the fuzzer if
+/// there isn't one.
+fn fuzz_pool<A: 'static>() -> Pool<A> {
+    crate::thread_local::install(FUZZ_POOL_SIZE)
+}
+
+impl<'a, A> Arbitrary<'a> for PoolRef<A>
+where
+    A: Arbitrary<'a> + 'static,
+{
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        A::arbitrary(u).map(|value| PoolRef::new(&fuzz_pool(), value))
+    }
+
+    fn arbitrary_take_rest(u: Unstructured<'a>) -> Result<Self> {
+        A::arbitrary_take_rest(u).map(|value| PoolRef::new(&fuzz_pool(), value))
+    }
+
+    fn size_hint(depth: usize) -> (usize, Option<usize>) {
+        A::size_hint(depth)
+    }
+}
+
+impl<'a, A> Arbitrary<'a> for PoolBox<A>
+where
+    A: Arbitrary<'a> + 'static,
+{
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        A::arbitrary(u).map(|value| PoolBox::new(&fuzz_pool(), value))
+    }
+
+    fn arbitrary_take_rest(u: Unstructured<'a>) -> Result<Self> {
+        A::arbitrary_take_rest(u).map(|value| PoolBox::new(&fuzz_pool(), value))
+    }
+
+    fn size_hint(depth: usize) -> (usize, Option<usize>) {
+        A::size_hint(depth)
+    }
+}
+
+impl<'a, A> Arbitrary<'a> for crate::fakepool::PoolRef<A>
+where
+    A: Arbitrary<'a>,
+{
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        A::arbitrary(u).map(|value| Self::new(&crate::fakepool::Pool::new(0), value))
+    }
+
+    fn size_hint(depth: usize) -> (usize, Option<usize>) {
+        A::size_hint(depth)
+    }
+}
+
+impl<'a, A> Arbitrary<'a> for crate::fakepool::PoolBox<A>
+where
+    A: Arbitrary<'a>,
+{
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        A::arbitrary(u).map(|value| Self::new(&crate::fakepool::Pool::new(0), value))
+    }
+
+    fn size_hint(depth: usize) -> (usize, Option<usize>) {
+        A::size_hint(depth)
+    }
+}
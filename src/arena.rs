@@ -0,0 +1,195 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::cell::RefCell;
+use std::fmt::{Debug, Display, Error, Formatter};
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut};
+use std::ptr::NonNull;
+
+use crate::box_handle::PoolBox;
+use crate::pool::Pool;
+use crate::PoolDefault;
+
+/// An arena for short lived values allocated from a [`Pool`][Pool].
+///
+/// You get one of these from [`Pool::arena()`][Pool::arena]. Values allocated
+/// from an arena come back as [`ArenaBox`][ArenaBox]es, which borrow the
+/// arena rather than owning their memory, and they all stay alive until you
+/// [`reset()`][reset] the arena, which drops them all at once and gives their
+/// memory back to the pool. Because `reset()` needs a mutable reference to
+/// the arena, the borrow checker makes sure no `ArenaBox` outlives it.
+///
+/// This suits workloads like a game loop, which allocates a pile of scratch
+/// values every frame and throws them all away at the end of it: the arena
+/// saves you from keeping track of them, and the pool saves you from going
+/// back to the system allocator for them every frame.
+///
+/// # Examples
+///
+/// ```rust
+/// # use refpool::{Pool, PoolArena};
+/// let pool: Pool<Vec<u32>> = Pool::new(16);
+/// let mut arena = pool.arena();
+/// for frame in 0..3 {
+///     let mut visible = arena.alloc_default();
+///     let mut hidden = arena.alloc_default();
+///     visible.push(frame);
+///     hidden.push(frame + 1);
+///     assert_eq!(2, arena.len());
+///     arena.reset();
+///     assert_eq!(2, pool.get_pool_size());
+/// }
+/// ```
+///
+/// An `ArenaBox` can't outlive the frame it was allocated in:
+///
+/// ```compile_fail
+/// # use refpool::{Pool, PoolArena};
+/// let pool: Pool<usize> = Pool::new(16);
+/// let mut arena = pool.arena();
+/// let number = arena.alloc(1337);
+/// arena.reset();
+/// assert_eq!(1337, *number);
+/// ```
+///
+/// [Pool]: struct.Pool.html
+/// [Pool::arena]: struct.Pool.html#method.arena
+/// [ArenaBox]: struct.ArenaBox.html
+/// [reset]: #method.reset
+pub struct PoolArena<A> {
+    pool: Pool<A>,
+    values: RefCell<Vec<PoolBox<A>>>,
+}
+
+impl<A> PoolArena<A> {
+    pub(crate) fn new(pool: &Pool<A>) -> Self {
+        PoolArena {
+            pool: pool.clone(),
+            values: RefCell::new(Vec::new()),
+        }
+    }
+
+    /// Allocate a value which lives until the arena is reset.
+    #[cfg_attr(feature = "trace-alloc", track_caller)]
+    pub fn alloc(&self, value: A) -> ArenaBox<'_, A> {
+        self.keep(PoolBox::new(&self.pool, value))
+    }
+
+    /// Allocate a default value which lives until the arena is reset.
+    #[cfg_attr(feature = "trace-alloc", track_caller)]
+    pub fn alloc_default(&self) -> ArenaBox<'_, A>
+    where
+        A: PoolDefault,
+    {
+        self.keep(PoolBox::default(&self.pool))
+    }
+
+    fn keep(&self, mut value: PoolBox<A>) -> ArenaBox<'_, A> {
+        // The value stays put in its chunk when the `PoolBox` moves into the
+        // list, and the chunk isn't freed until `reset()`, which can't be
+        // called while there's an `ArenaBox` borrowing the arena.
+        let ptr = NonNull::from(&mut *value);
+        self.values.borrow_mut().push(value);
+        ArenaBox {
+            value: ptr,
+            frame: PhantomData,
+        }
+    }
+
+    /// Get the number of values allocated since the arena was last reset.
+    pub fn len(&self) -> usize {
+        self.values.borrow().len()
+    }
+
+    /// Test whether no values have been allocated since the arena was last
+    /// reset.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Get the pool the arena allocates from.
+    pub fn pool(&self) -> &Pool<A> {
+        &self.pool
+    }
+
+    /// Drop every value allocated from the arena, giving their memory back to
+    /// the pool.
+    pub fn reset(&mut self) {
+        self.values.get_mut().clear();
+    }
+}
+
+impl<A> Debug for PoolArena<A> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        f.debug_struct("PoolArena")
+            .field("pool", &self.pool)
+            .field("len", &self.len())
+            .finish()
+    }
+}
+
+/// A value allocated from a [`PoolArena`][PoolArena].
+///
+/// This dereferences to the value, which lives until its arena is
+/// [`reset()`][reset]. Dropping an `ArenaBox` doesn't drop the value, it only
+/// gives up your access to it.
+///
+/// [PoolArena]: struct.PoolArena.html
+/// [reset]: struct.PoolArena.html#method.reset
+pub struct ArenaBox<'frame, A> {
+    value: NonNull<A>,
+    frame: PhantomData<&'frame mut A>,
+}
+
+impl<'frame, A> ArenaBox<'frame, A> {
+    /// Turn the `ArenaBox` into a mutable reference to its value, which
+    /// lives as long as the frame.
+    pub fn leak(this: Self) -> &'frame mut A {
+        unsafe { &mut *this.value.as_ptr() }
+    }
+}
+
+impl<'frame, A> Deref for ArenaBox<'frame, A> {
+    type Target = A;
+    fn deref(&self) -> &Self::Target {
+        unsafe { self.value.as_ref() }
+    }
+}
+
+impl<'frame, A> DerefMut for ArenaBox<'frame, A> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        unsafe { self.value.as_mut() }
+    }
+}
+
+impl<'frame, A> AsRef<A> for ArenaBox<'frame, A> {
+    fn as_ref(&self) -> &A {
+        self.deref()
+    }
+}
+
+impl<'frame, A> AsMut<A> for ArenaBox<'frame, A> {
+    fn as_mut(&mut self) -> &mut A {
+        self.deref_mut()
+    }
+}
+
+impl<'frame, A> Display for ArenaBox<'frame, A>
+where
+    A: Display,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        (**self).fmt(f)
+    }
+}
+
+impl<'frame, A> Debug for ArenaBox<'frame, A>
+where
+    A: Debug,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        (**self).fmt(f)
+    }
+}
@@ -2,29 +2,41 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
+use crate::chunk_guard::ChunkGuard;
 use crate::pointer::Pointer;
 use crate::pool::Pool;
-use crate::refbox::assume_init;
-use crate::refbox::data_ptr;
+use crate::ref_handle::PoolRef;
+use crate::refbox::value_ptr;
 use crate::refbox::RefBox;
-use crate::PoolClone;
 use crate::{types::ElementPointer, PoolDefault};
+use crate::{AllocError, PoolClone};
 use std::borrow::Borrow;
 use std::borrow::BorrowMut;
 use std::cmp::Ordering;
+use std::fmt::Arguments;
 use std::fmt::Debug;
 use std::fmt::Display;
 use std::fmt::Error;
 use std::fmt::Formatter;
+use std::future::Future;
 use std::hash::Hash;
 use std::hash::Hasher;
+use std::io::{self, BufRead, IoSlice, IoSliceMut, Read, Seek, SeekFrom, Write};
+use std::iter::FusedIterator;
+use std::marker::PhantomData;
+#[cfg(feature = "fn_traits")]
+use std::marker::Tuple;
+use std::mem::{ManuallyDrop, MaybeUninit};
 use std::ops::DerefMut;
+use std::panic::{RefUnwindSafe, UnwindSafe};
 use std::ptr::NonNull;
+use std::task::{Context, Poll};
 use std::{ops::Deref, pin::Pin};
 
 /// A unique pointer to a pool allocated value of `A`.
 pub struct PoolBox<A> {
     pub(crate) handle: ElementPointer<A>,
+    pub(crate) _type: PhantomData<A>,
 }
 
 impl<A> PoolBox<A> {
@@ -46,16 +58,12 @@ impl<A> PoolBox<A> {
     ///
     /// [new]: #method.new
     /// [default_uninit]: trait.PoolDefault.html#tymethod.default_uninit
+    #[cfg_attr(feature = "trace-alloc", track_caller)]
     pub fn default(pool: &Pool<A>) -> Self
     where
         A: PoolDefault,
     {
-        let mut handle = pool.pop();
-        unsafe {
-            PoolDefault::default_uninit(data_ptr(&mut handle));
-            assume_init(handle)
-        }
-        .into_box()
+        unsafe { (*pool.pop_default_chunk().as_ptr()).new_box() }
     }
 
     /// Wrap a value in a `PoolBox`.
@@ -75,13 +83,269 @@ impl<A> PoolBox<A> {
     /// ```
     ///
     /// [default]: #method.default
+    #[cfg_attr(feature = "trace-alloc", track_caller)]
     pub fn new(pool: &Pool<A>, value: A) -> Self {
-        let mut handle = pool.pop();
+        let chunk = pool.pop_chunk();
         unsafe {
-            data_ptr(&mut handle).as_mut_ptr().write(value);
-            assume_init(handle)
+            value_ptr(chunk).write(value);
+            (*chunk.as_ptr()).new_box()
         }
-        .into_box()
+    }
+
+    /// Wrap a value in a `PoolBox`, using the current thread's pool for `A`.
+    ///
+    /// See [`Pool::thread_local()`][Pool::thread_local]. If there's no pool
+    /// for `A` on this thread, the value is allocated from the heap as usual.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use refpool::{Pool, PoolBox};
+    /// Pool::<usize>::thread_local(1024);
+    /// let number: PoolBox<usize> = PoolBox::new_tl(1337);
+    /// assert_eq!(1337, *number);
+    /// ```
+    ///
+    /// [Pool::thread_local]: struct.Pool.html#method.thread_local
+    #[cfg(feature = "thread-local")]
+    #[cfg_attr(feature = "trace-alloc", track_caller)]
+    pub fn new_tl(value: A) -> Self
+    where
+        A: 'static,
+    {
+        Self::new(&Pool::current(), value)
+    }
+
+    /// Construct a `PoolBox` with a default value, using the current thread's
+    /// pool for `A`.
+    ///
+    /// See [`Pool::thread_local()`][Pool::thread_local]. If there's no pool
+    /// for `A` on this thread, the value is allocated from the heap as usual.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use refpool::{Pool, PoolBox};
+    /// Pool::<usize>::thread_local(1024);
+    /// let zero: PoolBox<usize> = PoolBox::default_tl();
+    /// assert_eq!(0, *zero);
+    /// ```
+    ///
+    /// [Pool::thread_local]: struct.Pool.html#method.thread_local
+    #[cfg(feature = "thread-local")]
+    #[cfg_attr(feature = "trace-alloc", track_caller)]
+    pub fn default_tl() -> Self
+    where
+        A: PoolDefault + 'static,
+    {
+        Self::default(&Pool::current())
+    }
+
+    /// Construct a `PoolBox` with a newly initialised value of `A`, using only
+    /// memory from the pool.
+    ///
+    /// This works like [`PoolBox::default()`][default], except that it will never
+    /// fall back to the system allocator. If the pool is empty, you get
+    /// `None`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use refpool::{Pool, PoolBox};
+    /// let pool: Pool<usize> = Pool::new(1).filled();
+    /// let zero = PoolBox::try_default(&pool);
+    /// assert_eq!(Some(0), zero.as_deref().copied());
+    /// assert!(PoolBox::try_default(&pool).is_none());
+    /// ```
+    ///
+    /// [default]: #method.default
+    #[cfg_attr(feature = "trace-alloc", track_caller)]
+    pub fn try_default(pool: &Pool<A>) -> Option<Self>
+    where
+        A: PoolDefault,
+    {
+        let guard = ChunkGuard::from_chunk(pool.try_pop_chunk()?);
+        Some(unsafe {
+            RefBox::into_box(guard.init_in_place(|slot| PoolDefault::default_uninit(slot)))
+        })
+    }
+
+    /// Wrap a value in a `PoolBox`, using only memory from the pool.
+    ///
+    /// This works like [`PoolBox::new()`][new], except that it will never fall
+    /// back to the system allocator. If the pool is empty, you get your value
+    /// back in the `Err` case.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use refpool::{Pool, PoolBox};
+    /// let pool: Pool<usize> = Pool::new(1).filled();
+    /// let number = PoolBox::try_new(&pool, 1337).unwrap();
+    /// assert_eq!(1337, *number);
+    /// assert_eq!(Err(31337), PoolBox::try_new(&pool, 31337).map(|_| ()));
+    /// ```
+    ///
+    /// [new]: #method.new
+    #[cfg_attr(feature = "trace-alloc", track_caller)]
+    pub fn try_new(pool: &Pool<A>, value: A) -> Result<Self, A> {
+        let chunk = match pool.try_pop_chunk() {
+            Some(chunk) => chunk,
+            None => return Err(value),
+        };
+        unsafe {
+            value_ptr(chunk).write(value);
+            Ok(RefBox::into_box(chunk))
+        }
+    }
+
+    /// Wrap a value in a `PoolBox`, failing if memory can't be allocated.
+    ///
+    /// This works like [`PoolBox::new()`][new], using memory from the pool if
+    /// there is any and falling back to the system allocator otherwise, but
+    /// if the system allocator fails, you get an [`AllocError`][AllocError]
+    /// instead of an abort.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use refpool::{Pool, PoolBox};
+    /// let pool: Pool<usize> = Pool::new(256);
+    /// let number = PoolBox::try_alloc(&pool, 1337).expect("out of memory");
+    /// assert_eq!(1337, *number);
+    /// ```
+    ///
+    /// [new]: #method.new
+    /// [AllocError]: struct.AllocError.html
+    #[cfg_attr(feature = "trace-alloc", track_caller)]
+    pub fn try_alloc(pool: &Pool<A>, value: A) -> Result<Self, AllocError> {
+        let chunk = pool.try_pop_or_alloc_chunk()?;
+        unsafe {
+            value_ptr(chunk).write(value);
+            Ok(RefBox::into_box(chunk))
+        }
+    }
+
+    /// Construct a `PoolBox` with uninitialised contents.
+    ///
+    /// This lets you build a value in place in pool memory, without needing
+    /// a [`PoolDefault`][PoolDefault] implementation or constructing the
+    /// value on the stack first. Once you've initialised it, turn the
+    /// `PoolBox` into a `PoolBox<A>` with
+    /// [`PoolBox::assume_init()`][assume_init].
+    ///
+    /// # Panics
+    ///
+    /// This panics if the pool was built with
+    /// [`PoolBuilder::recycle()`][recycle], as its memory isn't empty.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use refpool::{Pool, PoolBox};
+    /// let pool: Pool<[usize; 1024]> = Pool::new(1);
+    /// let mut numbers = PoolBox::new_uninit(&pool);
+    /// let slots = numbers.as_mut_ptr() as *mut usize;
+    /// for index in 0..1024 {
+    ///     unsafe { slots.add(index).write(index) };
+    /// }
+    /// let numbers = unsafe { PoolBox::assume_init(numbers) };
+    /// assert_eq!(1023, numbers[1023]);
+    /// ```
+    ///
+    /// [PoolDefault]: trait.PoolDefault.html
+    /// [assume_init]: #method.assume_init
+    /// [recycle]: struct.PoolBuilder.html#method.recycle
+    #[cfg_attr(feature = "trace-alloc", track_caller)]
+    pub fn new_uninit(pool: &Pool<A>) -> PoolBox<MaybeUninit<A>> {
+        assert!(
+            !pool.is_recycling(),
+            "PoolBox::new_uninit: can't take uninitialised memory from a pool which recycles values"
+        );
+        unsafe { RefBox::into_box(pool.pop_chunk().cast::<RefBox<MaybeUninit<A>>>()) }
+    }
+
+    /// Construct a `PoolBox` with its memory zeroed.
+    ///
+    /// Like [`PoolBox::new_uninit()`][new_uninit], this gives you a
+    /// `PoolBox<MaybeUninit<A>>`, but with every byte set to zero, which is a
+    /// valid value for many plain data types. Use
+    /// [`PoolBox::assume_init()`][assume_init] once you've made sure it is.
+    ///
+    /// # Panics
+    ///
+    /// This panics if the pool was built with
+    /// [`PoolBuilder::recycle()`][recycle], as its memory isn't empty.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use refpool::{Pool, PoolBox};
+    /// let pool: Pool<[u32; 256]> = Pool::new(1);
+    /// let zeroes = PoolBox::new_zeroed(&pool);
+    /// let zeroes = unsafe { PoolBox::assume_init(zeroes) };
+    /// assert_eq!([0; 256], *zeroes);
+    /// ```
+    ///
+    /// [new_uninit]: #method.new_uninit
+    /// [assume_init]: #method.assume_init
+    /// [recycle]: struct.PoolBuilder.html#method.recycle
+    #[cfg_attr(feature = "trace-alloc", track_caller)]
+    pub fn new_zeroed(pool: &Pool<A>) -> PoolBox<MaybeUninit<A>> {
+        let mut this = Self::new_uninit(pool);
+        unsafe { this.as_mut_ptr().write_bytes(0, 1) };
+        this
+    }
+
+    /// Construct a `PoolBox` containing a zeroed value of a type for which
+    /// that's known to be valid.
+    ///
+    /// This is only available with the `bytemuck` feature flag.
+    ///
+    /// # Panics
+    ///
+    /// This panics if the pool was built with
+    /// [`PoolBuilder::recycle()`][recycle], as its memory isn't empty.
+    ///
+    /// [recycle]: struct.PoolBuilder.html#method.recycle
+    #[cfg(feature = "bytemuck")]
+    #[cfg_attr(feature = "trace-alloc", track_caller)]
+    pub fn zeroed(pool: &Pool<A>) -> Self
+    where
+        A: bytemuck::Zeroable,
+    {
+        unsafe { PoolBox::assume_init(Self::new_zeroed(pool)) }
+    }
+
+    /// Construct a `PoolBox` by initialising its value in place.
+    ///
+    /// The `init` function gets a reference to the uninitialised memory,
+    /// and must initialise it and return the reference to the initialised
+    /// value that [`MaybeUninit::write()`][write] gives back, which is how it
+    /// proves that the value has been fully constructed.
+    ///
+    /// # Panics
+    ///
+    /// This panics if `init` returns a reference to anything other than the
+    /// slot it was given. If `init` panics, the memory goes back to the pool,
+    /// but anything `init` had already written into it is leaked.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use refpool::{Pool, PoolBox};
+    /// let pool: Pool<[u64; 512]> = Pool::new(1);
+    /// let numbers = PoolBox::new_with(&pool, |slot| slot.write([1337; 512]));
+    /// assert_eq!(1337, numbers[511]);
+    /// ```
+    ///
+    /// [write]: https://doc.rust-lang.org/std/mem/union.MaybeUninit.html#method.write
+    #[cfg_attr(feature = "trace-alloc", track_caller)]
+    pub fn new_with<F>(pool: &Pool<A>, init: F) -> Self
+    where
+        F: FnOnce(&mut MaybeUninit<A>) -> &mut A,
+    {
+        unsafe { RefBox::into_box(pool.reserve().init_with(init)) }
     }
 
     /// Clone a value and return a new `PoolBox` to it.
@@ -102,16 +366,17 @@ impl<A> PoolBox<A> {
     ///
     /// [new]: #method.new
     /// [clone_uninit]: trait.PoolClone.html#tymethod.clone_uninit
+    #[cfg_attr(feature = "trace-alloc", track_caller)]
     pub fn clone_from(pool: &Pool<A>, value: &A) -> Self
     where
         A: PoolClone,
     {
-        let mut handle = pool.pop();
         unsafe {
-            value.clone_uninit(data_ptr(&mut handle));
-            assume_init(handle)
+            RefBox::into_box(
+                pool.reserve()
+                    .init_in_place(|slot| value.clone_uninit(slot)),
+            )
         }
-        .into_box()
     }
 
     /// Construct a [`Pin`][Pin]ned `PoolBox` with a default value.
@@ -126,6 +391,7 @@ impl<A> PoolBox<A> {
     /// ```
     ///
     /// [Pin]: https://doc.rust-lang.org/std/pin/struct.Pin.html
+    #[cfg_attr(feature = "trace-alloc", track_caller)]
     pub fn pin_default(pool: &Pool<A>) -> Pin<Self>
     where
         A: PoolDefault,
@@ -145,10 +411,77 @@ impl<A> PoolBox<A> {
     /// ```
     ///
     /// [Pin]: https://doc.rust-lang.org/std/pin/struct.Pin.html
+    #[cfg_attr(feature = "trace-alloc", track_caller)]
     pub fn pin(pool: &Pool<A>, value: A) -> Pin<Self> {
         unsafe { Pin::new_unchecked(Self::new(pool, value)) }
     }
 
+    /// Turn a `PoolBox` into a [`Pin`][Pin]ned `PoolBox`.
+    ///
+    /// This is safe because the value never moves out of its chunk of pool
+    /// memory while the `PoolBox` is alive. A pinned `PoolBox` containing a
+    /// [`Future`][Future] is itself a `Future`, even if the value isn't
+    /// [`Unpin`][Unpin], so this is how you poll an async block allocated from
+    /// a pool.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use refpool::{Pool, PoolBox};
+    /// # use std::future::Future;
+    /// fn spawn<F: Future<Output = ()>>(task: F) {}
+    ///
+    /// let pool = Pool::new(16);
+    /// let task = PoolBox::new(&pool, async {});
+    /// spawn(PoolBox::into_pin(task));
+    /// ```
+    ///
+    /// A pinned `PoolBox` works with the same [`Pin`][Pin] methods as a
+    /// pinned `Box`: [`Pin::as_mut()`][as_mut] reborrows it to get at the
+    /// value, and [`Pin::into_inner()`][into_inner] unpins it again if the
+    /// value is [`Unpin`][Unpin].
+    ///
+    /// ```rust
+    /// # use refpool::{Pool, PoolBox};
+    /// # use std::pin::Pin;
+    /// let pool = Pool::new(16);
+    /// let mut number = PoolBox::into_pin(PoolBox::new(&pool, 1));
+    /// *number.as_mut() += 1;
+    /// let number = Pin::into_inner(number);
+    /// assert_eq!(2, PoolBox::into_inner(number));
+    /// ```
+    ///
+    /// [Pin]: https://doc.rust-lang.org/std/pin/struct.Pin.html
+    /// [Future]: https://doc.rust-lang.org/std/future/trait.Future.html
+    /// [Unpin]: https://doc.rust-lang.org/std/marker/trait.Unpin.html
+    /// [as_mut]: https://doc.rust-lang.org/std/pin/struct.Pin.html#method.as_mut
+    /// [into_inner]: https://doc.rust-lang.org/std/pin/struct.Pin.html#method.into_inner
+    pub fn into_pin(this: Self) -> Pin<Self> {
+        unsafe { Pin::new_unchecked(this) }
+    }
+
+    /// Move the value out of a `PoolBox`.
+    ///
+    /// The memory the value occupied goes back to the system allocator
+    /// rather than to the pool, just like when you
+    /// [`PoolRef::try_unwrap()`][try_unwrap] a value.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use refpool::{Pool, PoolBox};
+    /// let pool: Pool<String> = Pool::new(1);
+    /// let text = PoolBox::new(&pool, "Hello".to_string());
+    /// assert_eq!("Hello", PoolBox::into_inner(text));
+    /// ```
+    ///
+    /// [try_unwrap]: struct.PoolRef.html#method.try_unwrap
+    pub fn into_inner(this: Self) -> A {
+        let handle = this.handle;
+        std::mem::forget(this);
+        unsafe { RefBox::into_value(handle) }
+    }
+
     /// Test two `PoolBox`es for pointer equality.
     ///
     /// # Examples
@@ -169,7 +502,8 @@ impl<A> PoolBox<A> {
     /// is by using `PoolBox::from_raw` to turn it back into a `PoolBox`, because
     /// the value is followed by `PoolBox` metadata which also needs to
     /// be dropped.
-    pub fn into_raw_non_null(b: PoolBox<A>) -> NonNull<A> {
+    pub fn into_raw_non_null(mut b: PoolBox<A>) -> NonNull<A> {
+        b.box_ref_mut().raw_acquired();
         let ptr = b.handle.cast();
         std::mem::forget(b);
         ptr
@@ -214,9 +548,50 @@ impl<A> PoolBox<A> {
     /// assert_eq!(31337, *ref2);
     /// ```
     pub unsafe fn from_raw(ptr: *mut A) -> Self {
-        Self {
+        let mut this = Self {
             handle: ElementPointer::wrap(ptr.cast()),
-        }
+            _type: PhantomData,
+        };
+        this.box_ref_mut().raw_released();
+        this
+    }
+
+    /// Get a raw pointer to the contents of a `PoolBox` without consuming it.
+    ///
+    /// With the `debug-aliasing` feature flag enabled, the pointer is
+    /// considered live until you call
+    /// [`PoolBox::release_raw()`][release_raw], and trying to get a mutable
+    /// reference to the contents through the `PoolBox` before then will
+    /// panic. Without the feature flag, no tracking takes place.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use refpool::{Pool, PoolBox};
+    /// let pool: Pool<usize> = Pool::new(1);
+    /// let mut value = PoolBox::new(&pool, 1);
+    /// let ptr = PoolBox::as_non_null(&mut value);
+    /// unsafe { *ptr.as_ptr() = 2 };
+    /// PoolBox::release_raw(&mut value);
+    /// *value += 1;
+    /// assert_eq!(3, *value);
+    /// ```
+    ///
+    /// [release_raw]: #method.release_raw
+    pub fn as_non_null(this: &mut Self) -> NonNull<A> {
+        this.box_ref_mut().raw_acquired();
+        this.handle.cast()
+    }
+
+    /// Declare that a pointer obtained through
+    /// [`PoolBox::as_non_null()`][as_non_null] is no longer in use.
+    ///
+    /// This only has an effect with the `debug-aliasing` feature flag enabled,
+    /// in which case it panics if there's no such pointer.
+    ///
+    /// [as_non_null]: #method.as_non_null
+    pub fn release_raw(this: &mut Self) {
+        this.box_ref_mut().raw_released();
     }
 
     fn box_ref(&self) -> &RefBox<A> {
@@ -228,13 +603,93 @@ impl<A> PoolBox<A> {
     }
 }
 
+impl<A> PoolBox<MaybeUninit<A>> {
+    /// Move a value into an uninitialised `PoolBox`, turning it into a
+    /// `PoolBox<A>`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use refpool::{Pool, PoolBox};
+    /// let pool: Pool<String> = Pool::new(1);
+    /// let name = PoolBox::write(PoolBox::new_uninit(&pool), String::from("Joe Bloggs"));
+    /// assert_eq!("Joe Bloggs", *name);
+    /// ```
+    pub fn write(mut this: Self, value: A) -> PoolBox<A> {
+        (*this).write(value);
+        unsafe { Self::assume_init(this) }
+    }
+
+    /// Convert a `PoolBox<MaybeUninit<A>>` into a `PoolBox<A>`.
+    ///
+    /// # Safety
+    ///
+    /// The contents of the `PoolBox` must have been fully initialised, as
+    /// with [`MaybeUninit::assume_init()`][assume_init].
+    ///
+    /// [assume_init]: https://doc.rust-lang.org/std/mem/union.MaybeUninit.html#method.assume_init
+    pub unsafe fn assume_init(this: Self) -> PoolBox<A> {
+        let ptr = ManuallyDrop::new(this).handle.get_ptr();
+        PoolBox {
+            handle: ElementPointer::wrap(ptr.cast()),
+            _type: PhantomData,
+        }
+    }
+}
+
+#[cfg(feature = "thread-local")]
+impl<A> Default for PoolBox<A>
+where
+    A: PoolDefault + 'static,
+{
+    /// Construct a default value using the current thread's pool for `A`.
+    ///
+    /// This is only available with the `thread-local` feature flag. See
+    /// [`PoolBox::default_tl()`][default_tl].
+    ///
+    /// [default_tl]: #method.default_tl
+    fn default() -> Self {
+        Self::default_tl()
+    }
+}
+
+#[cfg(feature = "thread-local")]
+impl<A> From<A> for PoolBox<A>
+where
+    A: 'static,
+{
+    /// Wrap a value using the current thread's pool for `A`.
+    ///
+    /// This is only available with the `thread-local` feature flag. See
+    /// [`PoolBox::new_tl()`][new_tl].
+    ///
+    /// [new_tl]: #method.new_tl
+    fn from(value: A) -> Self {
+        Self::new_tl(value)
+    }
+}
+
+impl<A> From<PoolBox<A>> for Pin<PoolBox<A>> {
+    /// Pin a `PoolBox`. See [`PoolBox::into_pin()`][into_pin].
+    ///
+    /// [into_pin]: struct.PoolBox.html#method.into_pin
+    fn from(value: PoolBox<A>) -> Self {
+        PoolBox::into_pin(value)
+    }
+}
+
 impl<A> Drop for PoolBox<A> {
     fn drop(&mut self) {
-        let handle = unsafe { Box::from_raw(self.handle.get_ptr()) };
-        handle.return_to_pool();
+        unsafe { RefBox::release(self.handle) };
     }
 }
 
+// Like `Box`, a `PoolBox` owns its value, so it's unwind safe if the value
+// is, and the value never moves, so the box itself is always `Unpin`.
+impl<A> UnwindSafe for PoolBox<A> where A: UnwindSafe {}
+impl<A> RefUnwindSafe for PoolBox<A> where A: RefUnwindSafe {}
+impl<A> Unpin for PoolBox<A> {}
+
 impl<A> Clone for PoolBox<A>
 where
     A: PoolClone,
@@ -257,13 +712,10 @@ where
     ///
     /// [new]: #method.new
     /// [clone_uninit]: trait.PoolClone.html#tymethod.clone_uninit
+    #[cfg_attr(feature = "trace-alloc", track_caller)]
     fn clone(&self) -> Self {
-        let mut handle = self.box_ref().pool.pop();
-        unsafe {
-            self.deref().clone_uninit(data_ptr(&mut handle));
-            assume_init(handle)
-        }
-        .into_box()
+        let guard = self.box_ref().pool.reserve();
+        unsafe { RefBox::into_box(guard.init_in_place(|slot| self.deref().clone_uninit(slot))) }
     }
 }
 
@@ -315,6 +767,24 @@ where
 
 impl<A> Eq for PoolBox<A> where A: Eq {}
 
+impl<A> PartialEq<A> for PoolBox<A>
+where
+    A: PartialEq,
+{
+    fn eq(&self, other: &A) -> bool {
+        (**self) == *other
+    }
+}
+
+impl<A> PartialEq<PoolRef<A>> for PoolBox<A>
+where
+    A: PartialEq,
+{
+    fn eq(&self, other: &PoolRef<A>) -> bool {
+        (**self) == (**other)
+    }
+}
+
 impl<A> PartialOrd for PoolBox<A>
 where
     A: PartialOrd,
@@ -365,3 +835,274 @@ impl<A> std::fmt::Pointer for PoolBox<A> {
         std::fmt::Pointer::fmt(&(&**self as *const A), f)
     }
 }
+
+impl<A> std::error::Error for PoolBox<A>
+where
+    A: std::error::Error,
+{
+    #[allow(deprecated)]
+    fn description(&self) -> &str {
+        (**self).description()
+    }
+
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        (**self).source()
+    }
+}
+
+impl<H> Hasher for PoolBox<H>
+where
+    H: Hasher,
+{
+    fn finish(&self) -> u64 {
+        (**self).finish()
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        (**self).write(bytes)
+    }
+
+    fn write_u8(&mut self, i: u8) {
+        (**self).write_u8(i)
+    }
+
+    fn write_u16(&mut self, i: u16) {
+        (**self).write_u16(i)
+    }
+
+    fn write_u32(&mut self, i: u32) {
+        (**self).write_u32(i)
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        (**self).write_u64(i)
+    }
+
+    fn write_u128(&mut self, i: u128) {
+        (**self).write_u128(i)
+    }
+
+    fn write_usize(&mut self, i: usize) {
+        (**self).write_usize(i)
+    }
+
+    fn write_i8(&mut self, i: i8) {
+        (**self).write_i8(i)
+    }
+
+    fn write_i16(&mut self, i: i16) {
+        (**self).write_i16(i)
+    }
+
+    fn write_i32(&mut self, i: i32) {
+        (**self).write_i32(i)
+    }
+
+    fn write_i64(&mut self, i: i64) {
+        (**self).write_i64(i)
+    }
+
+    fn write_i128(&mut self, i: i128) {
+        (**self).write_i128(i)
+    }
+
+    fn write_isize(&mut self, i: isize) {
+        (**self).write_isize(i)
+    }
+}
+
+impl<W> std::fmt::Write for PoolBox<W>
+where
+    W: std::fmt::Write,
+{
+    fn write_str(&mut self, s: &str) -> std::fmt::Result {
+        (**self).write_str(s)
+    }
+
+    fn write_char(&mut self, c: char) -> std::fmt::Result {
+        (**self).write_char(c)
+    }
+
+    fn write_fmt(&mut self, args: Arguments<'_>) -> std::fmt::Result {
+        (**self).write_fmt(args)
+    }
+}
+
+impl<R> Read for PoolBox<R>
+where
+    R: Read,
+{
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        (**self).read(buf)
+    }
+
+    fn read_vectored(&mut self, bufs: &mut [IoSliceMut<'_>]) -> io::Result<usize> {
+        (**self).read_vectored(bufs)
+    }
+
+    fn read_to_end(&mut self, buf: &mut Vec<u8>) -> io::Result<usize> {
+        (**self).read_to_end(buf)
+    }
+
+    fn read_to_string(&mut self, buf: &mut String) -> io::Result<usize> {
+        (**self).read_to_string(buf)
+    }
+
+    fn read_exact(&mut self, buf: &mut [u8]) -> io::Result<()> {
+        (**self).read_exact(buf)
+    }
+}
+
+impl<W> Write for PoolBox<W>
+where
+    W: Write,
+{
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        (**self).write(buf)
+    }
+
+    fn write_vectored(&mut self, bufs: &[IoSlice<'_>]) -> io::Result<usize> {
+        (**self).write_vectored(bufs)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        (**self).flush()
+    }
+
+    fn write_all(&mut self, buf: &[u8]) -> io::Result<()> {
+        (**self).write_all(buf)
+    }
+
+    fn write_fmt(&mut self, fmt: Arguments<'_>) -> io::Result<()> {
+        (**self).write_fmt(fmt)
+    }
+}
+
+impl<S> Seek for PoolBox<S>
+where
+    S: Seek,
+{
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        (**self).seek(pos)
+    }
+
+    fn stream_position(&mut self) -> io::Result<u64> {
+        (**self).stream_position()
+    }
+}
+
+impl<B> BufRead for PoolBox<B>
+where
+    B: BufRead,
+{
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        (**self).fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        (**self).consume(amt)
+    }
+
+    fn read_until(&mut self, byte: u8, buf: &mut Vec<u8>) -> io::Result<usize> {
+        (**self).read_until(byte, buf)
+    }
+
+    fn read_line(&mut self, buf: &mut String) -> io::Result<usize> {
+        (**self).read_line(buf)
+    }
+}
+
+#[cfg(feature = "fn_traits")]
+impl<Args, F> FnOnce<Args> for PoolBox<F>
+where
+    Args: Tuple,
+    F: FnOnce<Args>,
+{
+    type Output = F::Output;
+
+    extern "rust-call" fn call_once(self, args: Args) -> Self::Output {
+        let chunk = ManuallyDrop::new(self).handle;
+        let function = unsafe {
+            let function = std::ptr::read(&(*chunk.as_ptr()).value);
+            RefBox::return_uninit(chunk);
+            function
+        };
+        function.call_once(args)
+    }
+}
+
+#[cfg(feature = "fn_traits")]
+impl<Args, F> FnMut<Args> for PoolBox<F>
+where
+    Args: Tuple,
+    F: FnMut<Args>,
+{
+    extern "rust-call" fn call_mut(&mut self, args: Args) -> Self::Output {
+        (**self).call_mut(args)
+    }
+}
+
+#[cfg(feature = "fn_traits")]
+impl<Args, F> Fn<Args> for PoolBox<F>
+where
+    Args: Tuple,
+    F: Fn<Args>,
+{
+    extern "rust-call" fn call(&self, args: Args) -> Self::Output {
+        (**self).call(args)
+    }
+}
+
+impl<F> Future for PoolBox<F>
+where
+    F: Future + Unpin,
+{
+    type Output = F::Output;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        F::poll(Pin::new(&mut **self), cx)
+    }
+}
+
+impl<I> Iterator for PoolBox<I>
+where
+    I: Iterator,
+{
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        (**self).next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (**self).size_hint()
+    }
+
+    fn nth(&mut self, n: usize) -> Option<Self::Item> {
+        (**self).nth(n)
+    }
+}
+
+impl<I> DoubleEndedIterator for PoolBox<I>
+where
+    I: DoubleEndedIterator,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        (**self).next_back()
+    }
+
+    fn nth_back(&mut self, n: usize) -> Option<Self::Item> {
+        (**self).nth_back(n)
+    }
+}
+
+impl<I> ExactSizeIterator for PoolBox<I>
+where
+    I: ExactSizeIterator,
+{
+    fn len(&self) -> usize {
+        (**self).len()
+    }
+}
+
+impl<I> FusedIterator for PoolBox<I> where I: FusedIterator {}
@@ -0,0 +1,531 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::alloc::Layout;
+use std::fmt::{Debug, Error, Formatter};
+use std::marker::PhantomData;
+use std::time::Duration;
+
+use crate::group::PoolGroup;
+use crate::overflow::OverflowPolicy;
+use crate::pool::{Pool, PoolInner};
+use crate::recycle::{PoolRecycle, Recycler};
+use crate::refbox::RefBox;
+use crate::storage::{construct_storage, PoolStorage, StorageConstructor};
+
+/// A builder for a [`Pool`][Pool] with non-default configuration.
+///
+/// You get one of these from [`Pool::builder()`][Pool::builder].
+///
+/// # Examples
+///
+/// ```rust
+/// # use refpool::{Pool, PoolRef};
+/// let pool: Pool<usize> = Pool::builder()
+///     .max_size(1024)
+///     .prefill(true)
+///     .zero_on_return(true)
+///     .stats(true)
+///     .build();
+/// assert!(pool.is_full());
+/// let number = PoolRef::new(&pool, 1337);
+/// assert_eq!(1, pool.stats().unwrap().hits);
+/// ```
+///
+/// [Pool]: struct.Pool.html
+/// [Pool::builder]: struct.Pool.html#method.builder
+pub struct PoolBuilder<A> {
+    pub(crate) max_size: usize,
+    pub(crate) prefill: bool,
+    pub(crate) zero_on_return: bool,
+    pub(crate) stats: bool,
+    #[cfg(feature = "hdrhistogram")]
+    pub(crate) latency: bool,
+    pub(crate) live_count: bool,
+    pub(crate) overflow: OverflowPolicy,
+    pub(crate) group: Option<PoolGroup>,
+    pub(crate) adaptive: Option<usize>,
+    pub(crate) idle_timeout: Option<Duration>,
+    pub(crate) recycler: Option<Recycler<A>>,
+    pub(crate) storage: Option<StorageConstructor>,
+    pub(crate) inline_storage: Option<usize>,
+    pub(crate) alignment: Option<usize>,
+    pub(crate) bypass_below: Option<usize>,
+    pub(crate) discard_above: Option<usize>,
+    #[cfg(feature = "locked-memory")]
+    pub(crate) lock_memory: bool,
+    _type: PhantomData<A>,
+}
+
+impl<A> PoolBuilder<A> {
+    pub(crate) fn new() -> Self {
+        PoolBuilder {
+            max_size: 0,
+            prefill: false,
+            zero_on_return: false,
+            stats: false,
+            #[cfg(feature = "hdrhistogram")]
+            latency: false,
+            live_count: false,
+            overflow: OverflowPolicy::Discard,
+            group: None,
+            adaptive: None,
+            idle_timeout: None,
+            recycler: None,
+            storage: None,
+            inline_storage: None,
+            alignment: None,
+            bypass_below: None,
+            discard_above: None,
+            #[cfg(feature = "locked-memory")]
+            lock_memory: false,
+            _type: PhantomData,
+        }
+    }
+
+    /// Set the maximum size of the pool.
+    ///
+    /// This defaults to `0`, which, as with [`Pool::new(0)`][Pool::new], will
+    /// give you a null pool that never allocates anything and ignores all other
+    /// configuration options except [`alignment()`][alignment], so you'll
+    /// almost always want to set this.
+    ///
+    /// [Pool::new]: struct.Pool.html#method.new
+    /// [alignment]: #method.alignment
+    pub fn max_size(mut self, max_size: usize) -> Self {
+        self.max_size = max_size;
+        self
+    }
+
+    /// Set the maximum size of the pool in bytes.
+    ///
+    /// This sets the maximum size to the number of allocations which fit
+    /// within `bytes`, taking into account the pool's bookkeeping overhead for
+    /// each allocation, rounding down.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use refpool::Pool;
+    /// let pool: Pool<[u8; 1000]> = Pool::builder().max_bytes(1024 * 1024).build();
+    /// assert!(pool.get_max_size() > 1000);
+    /// assert!(pool.get_max_size() < 1048);
+    /// ```
+    pub fn max_bytes(self, bytes: usize) -> Self {
+        let chunk_size = self.chunk_layout().size();
+        self.max_size(bytes / chunk_size)
+    }
+
+    /// Don't pool chunks smaller than `bytes` at all.
+    ///
+    /// If the pool's chunks, including the pool's bookkeeping overhead, come
+    /// out smaller than `bytes`, you get a pool with a maximum size of `0`
+    /// instead, which always uses the system allocator, as if you'd never set
+    /// [`max_size()`][max_size]. This lets generic code build its pools the
+    /// same way for every type, and only pay for pooling the types where it
+    /// makes a difference.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use refpool::Pool;
+    /// let small: Pool<u8> = Pool::builder().max_size(16).bypass_below(64).build();
+    /// assert_eq!(0, small.get_max_size());
+    /// let large: Pool<[u8; 64]> = Pool::builder().max_size(16).bypass_below(64).build();
+    /// assert_eq!(16, large.get_max_size());
+    /// ```
+    ///
+    /// [max_size]: #method.max_size
+    pub fn bypass_below(mut self, bytes: usize) -> Self {
+        self.bypass_below = Some(bytes);
+        self
+    }
+
+    /// Never keep chunks larger than `bytes` in the pool.
+    ///
+    /// If the pool's chunks, including the pool's bookkeeping overhead, come
+    /// out larger than `bytes`, the pool refuses to hold on to any of them,
+    /// and values allocated from it always give their memory back to the
+    /// system allocator when they're dropped. Unlike with
+    /// [`bypass_below()`][bypass_below], you still get a pool, so statistics,
+    /// live counts and other bookkeeping keep working, but it won't prefill,
+    /// grow or adapt its size. This is for keeping large values from pinning
+    /// a lot of memory in a pool built by generic code.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use refpool::{Pool, PoolBox};
+    /// let pool: Pool<[u8; 4096]> = Pool::builder()
+    ///     .max_size(16)
+    ///     .discard_above(1024)
+    ///     .stats(true)
+    ///     .build();
+    /// drop(PoolBox::new(&pool, [0; 4096]));
+    /// assert_eq!(0, pool.get_pool_size());
+    /// assert_eq!(1, pool.stats().unwrap().discards);
+    /// ```
+    ///
+    /// [bypass_below]: #method.bypass_below
+    pub fn discard_above(mut self, bytes: usize) -> Self {
+        self.discard_above = Some(bytes);
+        self
+    }
+
+    /// Fill the pool with empty allocations when it's built.
+    ///
+    /// This is equivalent to calling [`Pool::fill()`][Pool::fill] on the newly
+    /// built pool. Defaults to `false`.
+    ///
+    /// [Pool::fill]: struct.Pool.html#method.fill
+    pub fn prefill(mut self, prefill: bool) -> Self {
+        self.prefill = prefill;
+        self
+    }
+
+    /// Overwrite the memory of values with zeroes when they're returned to
+    /// the pool.
+    ///
+    /// This is useful when the pool holds sensitive data which shouldn't
+    /// linger in memory after the value holding it has been dropped. Defaults
+    /// to `false`.
+    pub fn zero_on_return(mut self, zero_on_return: bool) -> Self {
+        self.zero_on_return = zero_on_return;
+        self
+    }
+
+    /// Lock the pool's memory into RAM, so that it's never written out to
+    /// swap.
+    ///
+    /// This is meant for pools holding secrets, like cryptographic keys,
+    /// which shouldn't end up on disk. Every chunk the pool allocates is
+    /// locked with `mlock` on Unix or `VirtualLock` on Windows, and is zeroed
+    /// before it's unlocked and handed back to the system allocator.
+    /// Combine this with [`zero_on_return()`][zero_on_return] to also wipe
+    /// values as they're returned to the pool.
+    ///
+    /// Locking works on whole pages, so each chunk is aligned to and padded
+    /// out to the page size, which costs at least a page of memory per value.
+    /// The operating system also limits how much memory a process can lock,
+    /// and an allocation the pool can't lock fails as if the system
+    /// allocator had run out of memory: constructors like
+    /// [`PoolRef::try_alloc()`][PoolRef::try_alloc] return an
+    /// [`AllocError`][AllocError], and the others abort. Values allocated
+    /// through a pool with a maximum size of `0` are still locked. Defaults to
+    /// `false`.
+    ///
+    /// This requires the `locked-memory` feature flag.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use refpool::{Pool, PoolBox};
+    /// let pool: Pool<[u8; 32]> = Pool::builder()
+    ///     .max_size(4)
+    ///     .lock_memory(true)
+    ///     .zero_on_return(true)
+    ///     .build();
+    /// if let Ok(key) = PoolBox::try_alloc(&pool, [0x55; 32]) {
+    ///     assert_eq!(0, &*key as *const [u8; 32] as usize % 4096);
+    /// }
+    /// ```
+    ///
+    /// [zero_on_return]: #method.zero_on_return
+    /// [PoolRef::try_alloc]: struct.PoolRef.html#method.try_alloc
+    /// [AllocError]: struct.AllocError.html
+    #[cfg(feature = "locked-memory")]
+    pub fn lock_memory(mut self, lock_memory: bool) -> Self {
+        self.lock_memory = lock_memory;
+        self
+    }
+
+    /// Collect usage statistics for the pool.
+    ///
+    /// These can be retrieved using [`Pool::stats()`][Pool::stats]. Defaults to
+    /// `false`.
+    ///
+    /// [Pool::stats]: struct.Pool.html#method.stats
+    pub fn stats(mut self, stats: bool) -> Self {
+        self.stats = stats;
+        self
+    }
+
+    /// Record histograms of how long allocating from and returning memory to
+    /// the pool takes.
+    ///
+    /// These can be retrieved using [`Pool::latency()`][Pool::latency].
+    /// Timing every operation isn't free, so this is best left off outside
+    /// of measurements. Like [`idle_timeout()`][idle_timeout], this needs a
+    /// clock, which `wasm32-unknown-unknown` doesn't have. Defaults to
+    /// `false`.
+    ///
+    /// This requires the `hdrhistogram` feature flag.
+    ///
+    /// [Pool::latency]: struct.Pool.html#method.latency
+    /// [idle_timeout]: #method.idle_timeout
+    #[cfg(feature = "hdrhistogram")]
+    pub fn latency(mut self, latency: bool) -> Self {
+        self.latency = latency;
+        self
+    }
+
+    /// Count the values allocated from the pool which are currently alive.
+    ///
+    /// The count can be retrieved using [`Pool::live_count()`][Pool::live_count].
+    /// Defaults to `false`.
+    ///
+    /// [Pool::live_count]: struct.Pool.html#method.live_count
+    pub fn live_count(mut self, live_count: bool) -> Self {
+        self.live_count = live_count;
+        self
+    }
+
+    /// Choose what the pool does with memory returned to it when it's full.
+    ///
+    /// Defaults to [`OverflowPolicy::Discard`][OverflowPolicy::Discard].
+    ///
+    /// [OverflowPolicy::Discard]: enum.OverflowPolicy.html#variant.Discard
+    pub fn overflow(mut self, policy: OverflowPolicy) -> Self {
+        self.overflow = policy;
+        self
+    }
+
+    /// Let the pool adjust its maximum size to the observed demand, up to
+    /// `cap`.
+    ///
+    /// An adaptive pool keeps track of the highest number of its values which
+    /// were alive at the same time over each period of 1024 allocations, and
+    /// at the end of each period it resizes itself to hold that many values,
+    /// releasing any memory it holds in excess of its new size. It will never
+    /// shrink below the size given to [`max_size()`][max_size], and never grow
+    /// beyond `cap`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use refpool::{Pool, PoolRef};
+    /// let pool: Pool<usize> = Pool::builder().max_size(16).adaptive(4096).build();
+    /// for _ in 0..4 {
+    ///     let refs: Vec<_> = (0..1000).map(|i| PoolRef::new(&pool, i)).collect();
+    /// }
+    /// assert_eq!(1000, pool.get_max_size());
+    /// for _ in 0..4096 {
+    ///     PoolRef::new(&pool, 0);
+    /// }
+    /// assert_eq!(16, pool.get_max_size());
+    /// assert_eq!(16, pool.get_pool_size());
+    /// ```
+    ///
+    /// [max_size]: #method.max_size
+    pub fn adaptive(mut self, cap: usize) -> Self {
+        self.adaptive = Some(cap);
+        self
+    }
+
+    /// Release memory which has sat unused in the pool for longer than
+    /// `timeout`.
+    ///
+    /// The pool checks for idle memory whenever a value is allocated from it
+    /// or returned to it, so there's no background thread involved, but it
+    /// also means an untouched pool will hold on to its memory until it's
+    /// used again. Once per timeout period, the pool releases the memory it
+    /// didn't need at any point during that period, so the memory returned
+    /// after a spike in demand is released at the end of the period the spike
+    /// happened in.
+    ///
+    /// This keeps a one-off spike in demand from pinning memory in the pool
+    /// forever.
+    ///
+    /// The pool keeps time using [`Instant`][Instant], which isn't available
+    /// on `wasm32-unknown-unknown`, so building a pool with an idle timeout
+    /// panics there.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use refpool::{Pool, PoolRef};
+    /// # use std::time::Duration;
+    /// # use std::thread::sleep;
+    /// let pool: Pool<usize> = Pool::builder()
+    ///     .max_size(8)
+    ///     .idle_timeout(Duration::from_millis(10))
+    ///     .build();
+    /// let spike: Vec<_> = (0..8).map(|i| PoolRef::new(&pool, i)).collect();
+    /// drop(spike);
+    /// assert_eq!(8, pool.get_pool_size());
+    /// sleep(Duration::from_millis(20));
+    /// PoolRef::new(&pool, 0);
+    /// assert_eq!(1, pool.get_pool_size());
+    /// ```
+    ///
+    /// [Instant]: https://doc.rust-lang.org/std/time/struct.Instant.html
+    pub fn idle_timeout(mut self, timeout: Duration) -> Self {
+        self.idle_timeout = Some(timeout);
+        self
+    }
+
+    /// Keep values returned to the pool alive instead of dropping them, and
+    /// reuse them for [`PoolRef::default()`][PoolRef::default] and
+    /// [`PoolBox::default()`][PoolBox::default].
+    ///
+    /// Values are reset using [`PoolRecycle::reset()`][reset] as they're
+    /// returned to the pool. Other constructors will still work, but they'll
+    /// have to drop the value they're reusing the memory of first, and
+    /// [`zero_on_return()`][zero_on_return] has no effect on a recycling pool.
+    ///
+    /// See [`PoolRecycle`][PoolRecycle].
+    ///
+    /// [PoolRecycle]: trait.PoolRecycle.html
+    /// [reset]: trait.PoolRecycle.html#tymethod.reset
+    /// [PoolRef::default]: struct.PoolRef.html#method.default
+    /// [PoolBox::default]: struct.PoolBox.html#method.default
+    /// [zero_on_return]: #method.zero_on_return
+    pub fn recycle(mut self) -> Self
+    where
+        A: PoolRecycle,
+    {
+        self.recycler = Some(Recycler::new());
+        self
+    }
+
+    /// Choose the strategy the pool uses for storing its free chunks of
+    /// memory.
+    ///
+    /// This defaults to [`VecStack`][VecStack]. The pool's type doesn't
+    /// depend on the storage, so pools with different storages can be used
+    /// interchangeably. See [`PoolStorage`][PoolStorage].
+    ///
+    /// [VecStack]: struct.VecStack.html
+    /// [PoolStorage]: trait.PoolStorage.html
+    pub fn storage<S>(mut self) -> Self
+    where
+        S: PoolStorage,
+    {
+        self.storage = Some(construct_storage::<S>);
+        self
+    }
+
+    /// Align the pool's chunks of memory to at least `align` bytes.
+    ///
+    /// Every value allocated from the pool will start on an `align` byte
+    /// boundary, whether its memory comes from the pool or from the system
+    /// allocator, and each chunk is padded out to a multiple of `align`
+    /// bytes. This is useful for values which benefit from a stricter
+    /// alignment than their type asks for, like buffers for SIMD
+    /// instructions. An alignment less strict than the type's own has no
+    /// effect.
+    ///
+    /// Unlike other options, the alignment is kept even when the pool's
+    /// maximum size is `0`, so you get a pool which always allocates from the
+    /// system allocator but still honours it. If you're also using
+    /// [`max_bytes()`][max_bytes], set the alignment first, so that the
+    /// padding is taken into account.
+    ///
+    /// # Panics
+    ///
+    /// This panics if `align` isn't a power of two.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use refpool::{Pool, PoolBox};
+    /// let pool: Pool<[f32; 4]> = Pool::builder().max_size(16).alignment(64).build();
+    /// let vector = PoolBox::new(&pool, [1.0, 2.0, 3.0, 4.0]);
+    /// assert_eq!(0, vector.as_ptr() as usize % 64);
+    /// assert_eq!(64, pool.alignment());
+    /// ```
+    ///
+    /// [max_bytes]: #method.max_bytes
+    pub fn alignment(mut self, align: usize) -> Self {
+        assert!(
+            align.is_power_of_two(),
+            "PoolBuilder::alignment: {} isn't a power of two",
+            align
+        );
+        self.alignment = Some(align);
+        self
+    }
+
+    /// The layout of the chunks of memory the pool will allocate.
+    pub(crate) fn chunk_layout(&self) -> Layout {
+        let layout = Layout::new::<RefBox<A>>();
+        #[cfg(feature = "locked-memory")]
+        let alignment = if self.lock_memory {
+            Some(self.alignment.unwrap_or(1).max(crate::locked::page_size()))
+        } else {
+            self.alignment
+        };
+        #[cfg(not(feature = "locked-memory"))]
+        let alignment = self.alignment;
+        match alignment {
+            Some(align) => layout
+                .align_to(align)
+                .expect("PoolBuilder::alignment: alignment too large")
+                .pad_to_align(),
+            None => layout,
+        }
+    }
+
+    /// Make the pool part of a [`PoolGroup`][PoolGroup], sharing the group's
+    /// retention budget with the other pools in it.
+    ///
+    /// [PoolGroup]: struct.PoolGroup.html
+    pub fn group(mut self, group: &PoolGroup) -> Self {
+        self.group = Some(group.clone());
+        self
+    }
+
+    /// Construct a [`Pool`][Pool] from this configuration.
+    ///
+    /// [Pool]: struct.Pool.html
+    pub fn build(mut self) -> Pool<A> {
+        let chunk_size = self.chunk_layout().size();
+        if matches!(self.bypass_below, Some(bytes) if chunk_size < bytes) {
+            self.max_size = 0;
+        }
+        if self.max_size == 0 && self.chunk_layout() == Layout::new::<RefBox<A>>() {
+            return Pool::new(0);
+        }
+        if matches!(self.discard_above, Some(bytes) if chunk_size > bytes) {
+            self.max_size = 0;
+            self.prefill = false;
+            self.overflow = OverflowPolicy::Discard;
+            self.adaptive = None;
+        }
+        let prefill = self.prefill;
+        let pool = PoolInner::allocate(self);
+        pool.join_group();
+        if prefill {
+            pool.fill();
+        }
+        pool
+    }
+}
+
+impl<A> Debug for PoolBuilder<A> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        let mut debug = f.debug_struct("PoolBuilder");
+        debug
+            .field("max_size", &self.max_size)
+            .field("prefill", &self.prefill)
+            .field("zero_on_return", &self.zero_on_return)
+            .field("stats", &self.stats)
+            .field("live_count", &self.live_count)
+            .field("overflow", &self.overflow)
+            .field("group", &self.group)
+            .field("adaptive", &self.adaptive)
+            .field("idle_timeout", &self.idle_timeout)
+            .field("recycle", &self.recycler.is_some())
+            .field("custom_storage", &self.storage.is_some())
+            .field("inline_storage", &self.inline_storage)
+            .field("alignment", &self.alignment)
+            .field("bypass_below", &self.bypass_below)
+            .field("discard_above", &self.discard_above);
+        #[cfg(feature = "hdrhistogram")]
+        debug.field("latency", &self.latency);
+        #[cfg(feature = "locked-memory")]
+        debug.field("lock_memory", &self.lock_memory);
+        debug.finish()
+    }
+}
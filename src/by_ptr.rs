@@ -0,0 +1,68 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+#[cfg(not(feature = "disable-pool"))]
+use std::hash::{Hash, Hasher};
+use std::ops::Deref;
+
+#[cfg(not(feature = "disable-pool"))]
+use crate::ref_handle::PoolRef;
+
+/// A wrapper for a [`PoolRef`][PoolRef] which compares and hashes it by the
+/// address of the value it points to, rather than by the value itself.
+///
+/// Two `ByPtr`s are equal only if they point to the same value, as with
+/// [`PoolRef::ptr_eq()`][PoolRef::ptr_eq], which makes them useful as keys in
+/// maps keyed by identity, whatever the value's own notion of equality is.
+///
+/// # Examples
+///
+/// ```rust
+/// # use refpool::{ByPtr, Pool, PoolRef};
+/// # use std::collections::HashMap;
+/// let pool: Pool<String> = Pool::new(16);
+/// let first = PoolRef::new(&pool, "node".to_string());
+/// let second = PoolRef::new(&pool, "node".to_string());
+/// let mut visits = HashMap::new();
+/// *visits.entry(ByPtr(first.clone())).or_insert(0) += 1;
+/// *visits.entry(ByPtr(second)).or_insert(0) += 1;
+/// *visits.entry(ByPtr(first.clone())).or_insert(0) += 1;
+/// assert_eq!(2, visits.len());
+/// assert_eq!(2, visits[&ByPtr(first)]);
+/// ```
+///
+/// [PoolRef]: struct.PoolRef.html
+/// [PoolRef::ptr_eq]: struct.PoolRef.html#method.ptr_eq
+#[derive(Clone, Debug)]
+pub struct ByPtr<P>(pub P);
+
+impl<P> Deref for ByPtr<P> {
+    type Target = P;
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<P> From<P> for ByPtr<P> {
+    fn from(pointer: P) -> Self {
+        ByPtr(pointer)
+    }
+}
+
+#[cfg(not(feature = "disable-pool"))]
+impl<A> PartialEq for ByPtr<PoolRef<A>> {
+    fn eq(&self, other: &Self) -> bool {
+        PoolRef::ptr_eq(&self.0, &other.0)
+    }
+}
+
+#[cfg(not(feature = "disable-pool"))]
+impl<A> Eq for ByPtr<PoolRef<A>> {}
+
+#[cfg(not(feature = "disable-pool"))]
+impl<A> Hash for ByPtr<PoolRef<A>> {
+    fn hash<H: Hasher>(&self, hasher: &mut H) {
+        PoolRef::ptr_hash(&self.0, hasher)
+    }
+}
@@ -0,0 +1,259 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::fmt::{Debug, Error, Formatter};
+use std::hash::{Hash, Hasher};
+use std::ops::{Deref, DerefMut};
+
+use crate::Pool;
+#[cfg(not(feature = "default_impl"))]
+use crate::{PoolClone, PoolDefault};
+#[cfg(not(feature = "default_impl"))]
+use std::mem::MaybeUninit;
+
+/// A pool of fixed size byte buffers.
+///
+/// Every buffer in a `BytePool<N, ALIGN>` is a [`ByteBuf`][ByteBuf] of `N`
+/// bytes, aligned to `ALIGN` bytes. This is useful for things like network
+/// packet buffers and serialisation scratch space, where the type of the
+/// payload varies but the size of the buffer doesn't.
+///
+/// # Examples
+///
+/// ```rust
+/// # use refpool::{BytePool, PoolBox};
+/// let pool: BytePool<1500, 8> = BytePool::new(64);
+/// let mut packet = PoolBox::default(&pool);
+/// packet[..4].copy_from_slice(&[0xde, 0xad, 0xbe, 0xef]);
+/// assert_eq!(0, packet.as_ptr() as usize % 8);
+/// ```
+///
+/// [ByteBuf]: struct.ByteBuf.html
+pub type BytePool<const N: usize, const ALIGN: usize> = Pool<ByteBuf<N, ALIGN>>;
+
+/// A type level alignment value.
+///
+/// [`Alignment`][Alignment] is implemented for `Align<ALIGN>` where `ALIGN` is
+/// a power of two between 1 and 4096.
+///
+/// [Alignment]: trait.Alignment.html
+#[derive(Clone, Copy, Debug)]
+pub struct Align<const ALIGN: usize>;
+
+/// A trait for alignments which a [`ByteBuf`][ByteBuf] or an
+/// [`Aligned`][Aligned] can have.
+///
+/// [ByteBuf]: struct.ByteBuf.html
+/// [Aligned]: struct.Aligned.html
+pub trait Alignment {
+    /// A zero sized type with the required alignment.
+    type Archetype: Copy + Eq + Hash;
+}
+
+macro_rules! alignments {
+    ($($name:ident = $align:literal;)*) => {
+        $(
+            #[doc(hidden)]
+            #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+            #[repr(align($align))]
+            pub struct $name;
+
+            impl Alignment for Align<$align> {
+                type Archetype = $name;
+            }
+        )*
+    };
+}
+
+alignments! {
+    Align1 = 1;
+    Align2 = 2;
+    Align4 = 4;
+    Align8 = 8;
+    Align16 = 16;
+    Align32 = 32;
+    Align64 = 64;
+    Align128 = 128;
+    Align256 = 256;
+    Align512 = 512;
+    Align1024 = 1024;
+    Align2048 = 2048;
+    Align4096 = 4096;
+}
+
+/// A buffer of `N` bytes aligned to `ALIGN` bytes.
+///
+/// This is the value type of a [`BytePool`][BytePool]. It dereferences to
+/// `[u8; N]`, and its default value is all zeroes.
+///
+/// With the `bytemuck` feature flag enabled, you can also get typed views into
+/// the buffer with [`view()`][view] and [`view_mut()`][view_mut].
+///
+/// [BytePool]: type.BytePool.html
+/// [view]: #method.view
+/// [view_mut]: #method.view_mut
+#[repr(C)]
+pub struct ByteBuf<const N: usize, const ALIGN: usize>
+where
+    Align<ALIGN>: Alignment,
+{
+    _align: [<Align<ALIGN> as Alignment>::Archetype; 0],
+    bytes: [u8; N],
+}
+
+impl<const N: usize, const ALIGN: usize> ByteBuf<N, ALIGN>
+where
+    Align<ALIGN>: Alignment,
+{
+    /// Construct a buffer from an array of bytes.
+    pub fn new(bytes: [u8; N]) -> Self {
+        ByteBuf { _align: [], bytes }
+    }
+
+    /// Get a reference to the buffer's contents as a `T`.
+    ///
+    /// Returns `None` if `T` is bigger than the buffer or needs a stricter
+    /// alignment than `ALIGN`. If `T` is smaller than the buffer, the view
+    /// covers the first `size_of::<T>()` bytes.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use refpool::{BytePool, PoolBox};
+    /// let pool: BytePool<16, 8> = BytePool::new(1);
+    /// let mut buf = PoolBox::default(&pool);
+    /// *buf.view_mut::<[u32; 2]>().unwrap() = [1, 2];
+    /// assert_eq!(Some(&[1, 2]), buf.view::<[u32; 2]>());
+    /// assert_eq!(None, buf.view::<[u64; 4]>());
+    /// assert_eq!(None, buf.view::<u128>());
+    /// ```
+    #[cfg(feature = "bytemuck")]
+    pub fn view<T: bytemuck::Pod>(&self) -> Option<&T> {
+        if std::mem::align_of::<T>() > ALIGN {
+            return None;
+        }
+        self.bytes
+            .get(..std::mem::size_of::<T>())
+            .and_then(|bytes| bytemuck::try_from_bytes(bytes).ok())
+    }
+
+    /// Get a mutable reference to the buffer's contents as a `T`.
+    ///
+    /// Returns `None` if `T` is bigger than the buffer or needs a stricter
+    /// alignment than `ALIGN`. If `T` is smaller than the buffer, the view
+    /// covers the first `size_of::<T>()` bytes.
+    #[cfg(feature = "bytemuck")]
+    pub fn view_mut<T: bytemuck::Pod>(&mut self) -> Option<&mut T> {
+        if std::mem::align_of::<T>() > ALIGN {
+            return None;
+        }
+        self.bytes
+            .get_mut(..std::mem::size_of::<T>())
+            .and_then(|bytes| bytemuck::try_from_bytes_mut(bytes).ok())
+    }
+}
+
+impl<const N: usize, const ALIGN: usize> Default for ByteBuf<N, ALIGN>
+where
+    Align<ALIGN>: Alignment,
+{
+    fn default() -> Self {
+        Self::new([0; N])
+    }
+}
+
+impl<const N: usize, const ALIGN: usize> Clone for ByteBuf<N, ALIGN>
+where
+    Align<ALIGN>: Alignment,
+{
+    fn clone(&self) -> Self {
+        Self::new(self.bytes)
+    }
+}
+
+#[cfg(not(feature = "default_impl"))]
+impl<const N: usize, const ALIGN: usize> PoolDefault for ByteBuf<N, ALIGN>
+where
+    Align<ALIGN>: Alignment,
+{
+    unsafe fn default_uninit(target: &mut MaybeUninit<Self>) {
+        target.as_mut_ptr().write_bytes(0, 1);
+    }
+}
+
+#[cfg(not(feature = "default_impl"))]
+impl<const N: usize, const ALIGN: usize> PoolClone for ByteBuf<N, ALIGN>
+where
+    Align<ALIGN>: Alignment,
+{
+    unsafe fn clone_uninit(&self, target: &mut MaybeUninit<Self>) {
+        target.as_mut_ptr().copy_from_nonoverlapping(self, 1);
+    }
+}
+
+impl<const N: usize, const ALIGN: usize> Deref for ByteBuf<N, ALIGN>
+where
+    Align<ALIGN>: Alignment,
+{
+    type Target = [u8; N];
+    fn deref(&self) -> &Self::Target {
+        &self.bytes
+    }
+}
+
+impl<const N: usize, const ALIGN: usize> DerefMut for ByteBuf<N, ALIGN>
+where
+    Align<ALIGN>: Alignment,
+{
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.bytes
+    }
+}
+
+impl<const N: usize, const ALIGN: usize> AsRef<[u8]> for ByteBuf<N, ALIGN>
+where
+    Align<ALIGN>: Alignment,
+{
+    fn as_ref(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+impl<const N: usize, const ALIGN: usize> AsMut<[u8]> for ByteBuf<N, ALIGN>
+where
+    Align<ALIGN>: Alignment,
+{
+    fn as_mut(&mut self) -> &mut [u8] {
+        &mut self.bytes
+    }
+}
+
+impl<const N: usize, const ALIGN: usize> PartialEq for ByteBuf<N, ALIGN>
+where
+    Align<ALIGN>: Alignment,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.bytes[..] == other.bytes[..]
+    }
+}
+
+impl<const N: usize, const ALIGN: usize> Eq for ByteBuf<N, ALIGN> where Align<ALIGN>: Alignment {}
+
+impl<const N: usize, const ALIGN: usize> Hash for ByteBuf<N, ALIGN>
+where
+    Align<ALIGN>: Alignment,
+{
+    fn hash<H: Hasher>(&self, hasher: &mut H) {
+        self.bytes[..].hash(hasher)
+    }
+}
+
+impl<const N: usize, const ALIGN: usize> Debug for ByteBuf<N, ALIGN>
+where
+    Align<ALIGN>: Alignment,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        f.debug_tuple("ByteBuf").field(&&self.bytes[..]).finish()
+    }
+}
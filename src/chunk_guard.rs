@@ -0,0 +1,160 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::fmt::{Debug, Error, Formatter};
+use std::mem::MaybeUninit;
+
+use crate::box_handle::PoolBox;
+use crate::pool::Pool;
+use crate::ref_handle::PoolRef;
+use crate::refbox::{value_ptr, RefBox};
+use crate::types::ElementPointer;
+use crate::PoolDefault;
+
+/// A chunk of pool memory reserved for a value which hasn't been constructed
+/// yet.
+///
+/// You get one of these from [`Pool::reserve()`][Pool::reserve]. You can turn
+/// it into a [`PoolRef`][PoolRef] or a [`PoolBox`][PoolBox] by providing a
+/// value, or just drop it, in which case the memory goes back to the pool.
+///
+/// # Examples
+///
+/// ```rust
+/// # use refpool::{Pool, PoolRef};
+/// let pool: Pool<usize> = Pool::new(1).filled();
+/// let chunk = pool.reserve();
+/// assert_eq!(0, pool.get_pool_size());
+/// let number = chunk.commit_ref(1337);
+/// assert_eq!(1337, *number);
+///
+/// let chunk = pool.reserve();
+/// drop(chunk);
+/// assert_eq!(1, pool.get_pool_size());
+/// ```
+///
+/// [Pool::reserve]: struct.Pool.html#method.reserve
+/// [PoolRef]: struct.PoolRef.html
+/// [PoolBox]: struct.PoolBox.html
+pub struct ChunkGuard<A> {
+    chunk: Option<ElementPointer<A>>,
+}
+
+impl<A> ChunkGuard<A> {
+    #[cfg_attr(feature = "trace-alloc", track_caller)]
+    pub(crate) fn new(pool: &Pool<A>) -> Self {
+        Self::from_chunk(pool.pop_chunk())
+    }
+
+    /// Guard a chunk which has already been taken from a pool.
+    pub(crate) fn from_chunk(chunk: ElementPointer<A>) -> Self {
+        ChunkGuard { chunk: Some(chunk) }
+    }
+
+    fn take(mut self) -> ElementPointer<A> {
+        self.chunk.take().unwrap()
+    }
+
+    fn slot(&mut self) -> &mut MaybeUninit<A> {
+        unsafe { &mut *value_ptr(self.chunk.unwrap()).cast::<MaybeUninit<A>>() }
+    }
+
+    fn write(self, value: A) -> ElementPointer<A> {
+        let chunk = self.take();
+        unsafe { value_ptr(chunk).write(value) };
+        chunk
+    }
+
+    /// Initialise the reserved memory with `init`, which must return a
+    /// reference to the value it wrote into the slot it was given.
+    ///
+    /// If `init` panics, the memory goes back to the pool when the guard is
+    /// dropped during unwinding.
+    pub(crate) fn init_with<F>(mut self, init: F) -> ElementPointer<A>
+    where
+        F: FnOnce(&mut MaybeUninit<A>) -> &mut A,
+    {
+        let slot = self.slot();
+        let expected = slot.as_ptr();
+        let written: *const A = init(slot);
+        assert!(
+            std::ptr::eq(written, expected),
+            "the initialiser must return a reference to the slot it was given"
+        );
+        self.take()
+    }
+
+    /// Initialise the reserved memory in place with `init`.
+    ///
+    /// If `init` panics, the memory goes back to the pool when the guard is
+    /// dropped during unwinding, rather than being freed with a layout which
+    /// might not be the pool's.
+    ///
+    /// # Safety
+    ///
+    /// `init` must fully initialise the slot it was given.
+    pub(crate) unsafe fn init_in_place<F>(mut self, init: F) -> ElementPointer<A>
+    where
+        F: FnOnce(&mut MaybeUninit<A>),
+    {
+        init(self.slot());
+        self.take()
+    }
+
+    /// Initialise the reserved memory with `init`, which may fail.
+    ///
+    /// If `init` fails, the memory goes back to the pool when the guard is
+    /// dropped.
+    ///
+    /// # Safety
+    ///
+    /// If `init` returns `Ok`, it must have fully initialised the slot it
+    /// was given.
+    pub(crate) unsafe fn try_init<E, F>(mut self, init: F) -> Result<ElementPointer<A>, E>
+    where
+        F: FnOnce(&mut MaybeUninit<A>) -> Result<(), E>,
+    {
+        init(self.slot())?;
+        Ok(self.take())
+    }
+
+    /// Construct a [`PoolRef`][PoolRef] in the reserved memory.
+    ///
+    /// [PoolRef]: struct.PoolRef.html
+    pub fn commit_ref(self, value: A) -> PoolRef<A> {
+        unsafe { RefBox::into_ref(self.write(value)) }
+    }
+
+    /// Construct a [`PoolBox`][PoolBox] in the reserved memory.
+    ///
+    /// [PoolBox]: struct.PoolBox.html
+    pub fn commit_box(self, value: A) -> PoolBox<A> {
+        unsafe { RefBox::into_box(self.write(value)) }
+    }
+
+    /// Construct a [`PoolRef`][PoolRef] with a default value in the reserved
+    /// memory.
+    ///
+    /// [PoolRef]: struct.PoolRef.html
+    pub fn commit_default(self) -> PoolRef<A>
+    where
+        A: PoolDefault,
+    {
+        unsafe { RefBox::into_ref(self.init_in_place(|slot| PoolDefault::default_uninit(slot))) }
+    }
+}
+
+impl<A> Drop for ChunkGuard<A> {
+    fn drop(&mut self) {
+        if let Some(chunk) = self.chunk.take() {
+            unsafe { RefBox::return_uninit(chunk) };
+        }
+    }
+}
+
+impl<A> Debug for ChunkGuard<A> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        write!(f, "ChunkGuard")
+    }
+}
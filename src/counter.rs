@@ -2,6 +2,16 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
+/// The type of the reference count in a pooled value's header.
+///
+/// With the `small-count` feature flag, this is a `u32`, which saves a word
+/// per value for types whose size leaves room for it next to the pool
+/// pointer, at the cost of limiting a value to `u32::MAX` references.
+#[cfg(not(feature = "small-count"))]
+pub(crate) type RefCount = usize;
+#[cfg(feature = "small-count")]
+pub(crate) type RefCount = u32;
+
 pub(crate) trait Counter: Default {
     fn inc(&mut self);
     fn dec(&mut self) -> usize;
@@ -26,3 +36,24 @@ impl Counter for usize {
         *self
     }
 }
+
+impl Counter for u32 {
+    #[inline(always)]
+    fn inc(&mut self) {
+        // Unlike a `usize`, a `u32` can plausibly be overflowed by leaking
+        // handles, and wrapping around to zero would free a live value.
+        *self = self.checked_add(1).expect("reference count overflow");
+    }
+
+    #[inline(always)]
+    fn dec(&mut self) -> usize {
+        let prev = *self;
+        *self -= 1;
+        prev as usize
+    }
+
+    #[inline(always)]
+    fn count(&self) -> usize {
+        *self as usize
+    }
+}
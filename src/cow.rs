@@ -0,0 +1,164 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::fmt::{Debug, Error, Formatter};
+use std::hash::{Hash, Hasher};
+use std::ops::Deref;
+
+use crate::box_handle::PoolBox;
+use crate::pool::Pool;
+use crate::ref_handle::PoolRef;
+use crate::PoolClone;
+
+/// A clone-on-write pointer to a pool allocated value of `A`.
+///
+/// This holds either a uniquely owned [`PoolBox`][PoolBox] or a shared
+/// [`PoolRef`][PoolRef], and only clones the value into fresh pool memory
+/// when you ask to mutate a shared value, much like
+/// [`PoolRef::make_mut()`][make_mut], but as a type you can keep in a
+/// struct field.
+///
+/// # Examples
+///
+/// ```rust
+/// # use refpool::{Pool, PoolCow, PoolRef};
+/// let pool: Pool<Vec<usize>> = Pool::new(16);
+/// let shared = PoolRef::new(&pool, vec![1, 2, 3]);
+/// let mut cow = PoolCow::from(shared.clone());
+/// assert!(cow.is_shared());
+/// cow.to_mut(&pool).push(4);
+/// assert!(cow.is_owned());
+/// assert_eq!(vec![1, 2, 3], *shared);
+/// assert_eq!(vec![1, 2, 3, 4], *cow);
+/// ```
+///
+/// [PoolBox]: struct.PoolBox.html
+/// [PoolRef]: struct.PoolRef.html
+/// [make_mut]: struct.PoolRef.html#method.make_mut
+pub enum PoolCow<A> {
+    /// A value owned by this `PoolCow`.
+    Owned(PoolBox<A>),
+    /// A value which may be shared with others.
+    Shared(PoolRef<A>),
+}
+
+impl<A> PoolCow<A> {
+    /// Test whether the value is owned.
+    pub fn is_owned(&self) -> bool {
+        matches!(self, PoolCow::Owned(_))
+    }
+
+    /// Test whether the value is shared.
+    pub fn is_shared(&self) -> bool {
+        matches!(self, PoolCow::Shared(_))
+    }
+
+    /// Get a mutable reference to the value.
+    ///
+    /// If the value is shared with other [`PoolRef`][PoolRef]s, it's cloned
+    /// into memory from `pool` first, and this `PoolCow` becomes the owner of
+    /// the clone. A [`PoolRef`][PoolRef] which is the only reference to its
+    /// value is mutated in place.
+    ///
+    /// [PoolRef]: struct.PoolRef.html
+    #[cfg_attr(feature = "trace-alloc", track_caller)]
+    pub fn to_mut(&mut self, pool: &Pool<A>) -> &mut A
+    where
+        A: PoolClone,
+    {
+        if let PoolCow::Shared(ref shared) = self {
+            if PoolRef::strong_count(shared) > 1 {
+                *self = PoolCow::Owned(PoolBox::clone_from(pool, shared));
+            }
+        }
+        match self {
+            PoolCow::Owned(owned) => owned,
+            PoolCow::Shared(shared) => PoolRef::get_mut(shared).unwrap(),
+        }
+    }
+
+    /// Turn the `PoolCow` into a [`PoolBox`][PoolBox], cloning the value into
+    /// memory from `pool` if it's shared.
+    ///
+    /// [PoolBox]: struct.PoolBox.html
+    #[cfg_attr(feature = "trace-alloc", track_caller)]
+    pub fn into_owned(self, pool: &Pool<A>) -> PoolBox<A>
+    where
+        A: PoolClone,
+    {
+        match self {
+            PoolCow::Owned(owned) => owned,
+            PoolCow::Shared(shared) => PoolBox::clone_from(pool, &shared),
+        }
+    }
+}
+
+impl<A> From<PoolBox<A>> for PoolCow<A> {
+    fn from(owned: PoolBox<A>) -> Self {
+        PoolCow::Owned(owned)
+    }
+}
+
+impl<A> From<PoolRef<A>> for PoolCow<A> {
+    fn from(shared: PoolRef<A>) -> Self {
+        PoolCow::Shared(shared)
+    }
+}
+
+impl<A> Clone for PoolCow<A>
+where
+    A: PoolClone,
+{
+    fn clone(&self) -> Self {
+        match self {
+            PoolCow::Owned(owned) => PoolCow::Owned(owned.clone()),
+            PoolCow::Shared(shared) => PoolCow::Shared(shared.clone()),
+        }
+    }
+}
+
+impl<A> Deref for PoolCow<A> {
+    type Target = A;
+    fn deref(&self) -> &Self::Target {
+        match self {
+            PoolCow::Owned(owned) => owned,
+            PoolCow::Shared(shared) => shared,
+        }
+    }
+}
+
+impl<A> AsRef<A> for PoolCow<A> {
+    fn as_ref(&self) -> &A {
+        self.deref()
+    }
+}
+
+impl<A> PartialEq for PoolCow<A>
+where
+    A: PartialEq,
+{
+    fn eq(&self, other: &Self) -> bool {
+        **self == **other
+    }
+}
+
+impl<A> Eq for PoolCow<A> where A: Eq {}
+
+impl<A> Hash for PoolCow<A>
+where
+    A: Hash,
+{
+    fn hash<H: Hasher>(&self, hasher: &mut H) {
+        (**self).hash(hasher)
+    }
+}
+
+impl<A> Debug for PoolCow<A>
+where
+    A: Debug,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        (**self).fmt(f)
+    }
+}
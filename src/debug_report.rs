@@ -0,0 +1,115 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+// With the `disable-pool` feature flag, there's no real pool to make use of
+// the crate internals here, only the fake pool which ignores them.
+#![cfg_attr(feature = "disable-pool", allow(dead_code))]
+
+use std::backtrace::{Backtrace, BacktraceStatus};
+use std::collections::HashMap;
+use std::fmt::{Display, Error, Formatter};
+
+/// The registry of live allocations kept by a pool.
+#[derive(Debug, Default)]
+pub(crate) struct LiveRegistry {
+    live: HashMap<usize, Backtrace>,
+}
+
+impl LiveRegistry {
+    pub(crate) fn allocated(&mut self, chunk: *const ()) {
+        self.live.insert(chunk as usize, Backtrace::capture());
+    }
+
+    pub(crate) fn freed(&mut self, chunk: *const ()) {
+        self.live.remove(&(chunk as usize));
+    }
+
+    pub(crate) fn report<A>(&self) -> DebugReport {
+        let mut allocations: Vec<LiveAllocation> = self
+            .live
+            .iter()
+            .map(|(address, backtrace)| LiveAllocation {
+                address: *address,
+                type_name: std::any::type_name::<A>(),
+                size: std::mem::size_of::<A>(),
+                backtrace: match backtrace.status() {
+                    BacktraceStatus::Captured => Some(backtrace.to_string()),
+                    _ => None,
+                },
+            })
+            .collect();
+        allocations.sort_by_key(|allocation| allocation.address);
+        DebugReport { allocations }
+    }
+}
+
+/// A value allocated from a pool which is still alive.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LiveAllocation {
+    /// The address of the allocation.
+    pub address: usize,
+    /// The name of the allocated type.
+    pub type_name: &'static str,
+    /// The size of the allocated type in bytes.
+    pub size: usize,
+    /// A backtrace of the allocation site, if backtraces were enabled when
+    /// the value was allocated.
+    ///
+    /// Backtraces are captured using
+    /// [`Backtrace::capture()`][Backtrace::capture], which means you need to
+    /// set the `RUST_BACKTRACE` or `RUST_LIB_BACKTRACE` environment variable
+    /// to get them.
+    ///
+    /// [Backtrace::capture]: https://doc.rust-lang.org/std/backtrace/struct.Backtrace.html#method.capture
+    pub backtrace: Option<String>,
+}
+
+/// A report of the values allocated from a pool which are still alive.
+///
+/// You get one of these from [`Pool::debug_report()`][debug_report]. Its
+/// [`Display`][Display] implementation prints a human readable listing of the
+/// allocations.
+///
+/// [debug_report]: struct.Pool.html#method.debug_report
+/// [Display]: https://doc.rust-lang.org/std/fmt/trait.Display.html
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DebugReport {
+    allocations: Vec<LiveAllocation>,
+}
+
+impl DebugReport {
+    /// Test whether there are no live allocations.
+    pub fn is_empty(&self) -> bool {
+        self.allocations.is_empty()
+    }
+
+    /// Get the number of live allocations.
+    pub fn len(&self) -> usize {
+        self.allocations.len()
+    }
+
+    /// Iterate over the live allocations, in order of address.
+    pub fn iter(&self) -> impl Iterator<Item = &LiveAllocation> {
+        self.allocations.iter()
+    }
+}
+
+impl Display for DebugReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        writeln!(f, "{} live allocations", self.allocations.len())?;
+        for allocation in &self.allocations {
+            writeln!(
+                f,
+                "{:#x}: {} ({} bytes)",
+                allocation.address, allocation.type_name, allocation.size
+            )?;
+            if let Some(ref backtrace) = allocation.backtrace {
+                for line in backtrace.lines() {
+                    writeln!(f, "    {}", line)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
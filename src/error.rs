@@ -0,0 +1,72 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::fmt::{Display, Error, Formatter};
+
+/// The error returned when the system allocator fails to provide memory.
+///
+/// # Examples
+///
+/// ```rust
+/// # use refpool::{AllocError, Pool};
+/// let pool: Pool<usize> = Pool::new(1024);
+/// assert_eq!(Ok(()), pool.try_fill());
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct AllocError;
+
+impl Display for AllocError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        write!(f, "memory allocation failed")
+    }
+}
+
+impl std::error::Error for AllocError {}
+
+/// The error returned when a fixed capacity buffer doesn't have room for more
+/// data.
+///
+/// # Examples
+///
+/// ```rust
+/// # use refpool::{CapacityError, PoolString, SlicePool};
+/// let pool: SlicePool<u8> = SlicePool::new(4, 1);
+/// let mut string = PoolString::new(&pool);
+/// assert_eq!(Err(CapacityError), string.try_push_str("hello"));
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct CapacityError;
+
+impl Display for CapacityError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        write!(f, "insufficient capacity")
+    }
+}
+
+impl std::error::Error for CapacityError {}
+
+/// The error returned when a pool can't be cast to a different type.
+///
+/// This happens when the two types don't have compatible sizes and
+/// alignments, or when the pool recycles values, in which case its memory
+/// isn't empty.
+///
+/// # Examples
+///
+#[cfg_attr(feature = "disable-pool", doc = "```ignore")]
+#[cfg_attr(not(feature = "disable-pool"), doc = "```rust")]
+/// # use refpool::{LayoutError, Pool};
+/// let pool: Pool<u64> = Pool::new(16);
+/// assert_eq!(Err(LayoutError), pool.try_cast::<u32>().map(|_| ()));
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct LayoutError;
+
+impl Display for LayoutError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        write!(f, "incompatible pool layout")
+    }
+}
+
+impl std::error::Error for LayoutError {}
@@ -16,15 +16,27 @@
 use std::marker::PhantomData;
 use std::{
     borrow::{Borrow, BorrowMut},
+    cell::RefCell,
     cmp::Ordering,
-    fmt::{Debug, Display, Error, Formatter},
+    fmt::{Arguments, Debug, Display, Error, Formatter},
+    future::Future,
     hash::{Hash, Hasher},
+    io::{self, BufRead, IoSlice, IoSliceMut, Read, Seek, SeekFrom, Write},
+    iter::FusedIterator,
+    mem::MaybeUninit,
     ops::{Deref, DerefMut},
     pin::Pin,
+    ptr::NonNull,
     rc::Rc,
+    task::{Context, Poll},
+    time::Duration,
 };
 
-use crate::{PoolClone, PoolDefault};
+use crate::metrics::PoolMetrics;
+use crate::{
+    AllocError, ByPtr, LayoutError, OverflowPolicy, PoolApi, PoolClone, PoolDefault, PoolGroup,
+    PoolRecycle, PoolStats, PoolStorage, PoolTryClone, SameLayout,
+};
 
 /// A fake `Pool` which is always empty.
 ///
@@ -41,6 +53,61 @@ impl<A> Pool<A> {
         Pool(PhantomData)
     }
 
+    pub fn with_byte_capacity(_bytes: usize) -> Self {
+        Pool(PhantomData)
+    }
+
+    pub fn fixed<const N: usize>() -> Self {
+        Pool(PhantomData)
+    }
+
+    pub fn with_alignment(_max_size: usize, _align: usize) -> Self {
+        Pool(PhantomData)
+    }
+
+    #[cfg(feature = "thread-local")]
+    pub fn thread_local(_max_size: usize) -> Self {
+        Pool(PhantomData)
+    }
+
+    #[cfg(feature = "thread-local")]
+    pub fn current() -> Self {
+        Pool(PhantomData)
+    }
+
+    pub fn builder() -> PoolBuilder<A> {
+        PoolBuilder(PhantomData)
+    }
+
+    pub fn into_send_token(self) -> Result<PoolSendToken<A>, Self> {
+        Ok(PoolSendToken(PhantomData))
+    }
+
+    #[inline(always)]
+    pub fn reserve(&self) -> ChunkGuard<A> {
+        ChunkGuard(PhantomData)
+    }
+
+    #[inline(always)]
+    pub fn checkout(&self) -> PoolGuard<A>
+    where
+        A: PoolDefault,
+    {
+        PoolGuard(PoolBox::default(self))
+    }
+
+    pub fn arena(&self) -> PoolArena<A> {
+        PoolArena(Pool(PhantomData), RefCell::new(Vec::new()))
+    }
+
+    pub fn speculate(&self) -> SpeculativePool<A> {
+        SpeculativePool(Pool(PhantomData))
+    }
+
+    pub fn alignment(&self) -> usize {
+        std::mem::align_of::<A>()
+    }
+
     pub fn get_max_size(&self) -> usize {
         0
     }
@@ -49,15 +116,114 @@ impl<A> Pool<A> {
         0
     }
 
+    pub fn retained_bytes(&self) -> usize {
+        0
+    }
+
+    pub fn max_retained_bytes(&self) -> usize {
+        0
+    }
+
+    pub fn live_count(&self) -> Option<usize> {
+        None
+    }
+
+    pub fn stats(&self) -> Option<PoolStats> {
+        None
+    }
+
+    #[cfg(feature = "debug-leaks")]
+    pub fn debug_report(&self) -> crate::DebugReport {
+        Default::default()
+    }
+
+    #[cfg(feature = "trace-alloc")]
+    pub fn alloc_sites(&self) -> Vec<crate::AllocSite> {
+        Vec::new()
+    }
+
+    #[cfg(feature = "debug-dump")]
+    pub fn debug_dump(&self) -> Vec<*const ()> {
+        Vec::new()
+    }
+
+    #[cfg(feature = "hdrhistogram")]
+    pub fn latency(&self) -> Option<crate::PoolLatency> {
+        None
+    }
+
+    pub fn metrics(&self) -> PoolMetrics {
+        PoolMetrics {
+            pool_size: 0,
+            max_size: 0,
+            retained_bytes: 0,
+            live: None,
+            stats: None,
+        }
+    }
+
+    #[inline(always)]
+    pub fn with_tmp<F, R>(&self, f: F) -> R
+    where
+        A: PoolDefault,
+        F: FnOnce(&mut A) -> R,
+    {
+        f(&mut A::default())
+    }
+
+    pub fn alloc_batch_default(&self, count: usize) -> Vec<PoolRef<A>>
+    where
+        A: PoolDefault,
+    {
+        (0..count).map(|_| PoolRef::default(self)).collect()
+    }
+
+    pub fn refs_from_iter<I>(&self, values: I) -> Vec<PoolRef<A>>
+    where
+        I: IntoIterator<Item = A>,
+    {
+        values
+            .into_iter()
+            .map(|value| PoolRef::new(self, value))
+            .collect()
+    }
+
     pub fn is_full(&self) -> bool {
         true
     }
 
     pub fn fill(&self) {}
 
+    pub fn try_fill(&self) -> Result<(), AllocError> {
+        Ok(())
+    }
+
+    pub fn filled(self) -> Self {
+        self
+    }
+
+    pub fn transfer_to<B>(&self, _other: &Pool<B>, _count: usize) -> usize {
+        0
+    }
+
     pub fn cast<B>(&self) -> Pool<B> {
         Pool(PhantomData)
     }
+
+    pub fn cast_into<B>(self) -> Pool<B> {
+        Pool(PhantomData)
+    }
+
+    pub fn try_cast<B>(&self) -> Result<Pool<B>, LayoutError> {
+        Ok(Pool(PhantomData))
+    }
+
+    pub fn cast_checked<B>(&self) -> Pool<B>
+    where
+        A: SameLayout<B>,
+    {
+        Pool(PhantomData)
+    }
 }
 
 impl<A> Clone for Pool<A> {
@@ -66,6 +232,60 @@ impl<A> Clone for Pool<A> {
     }
 }
 
+impl<A> PoolApi<A> for Pool<A> {
+    type Ref = PoolRef<A>;
+    type Box = PoolBox<A>;
+
+    #[inline(always)]
+    fn with_max_size(max_size: usize) -> Self {
+        Pool::new(max_size)
+    }
+
+    #[inline(always)]
+    fn get_max_size(&self) -> usize {
+        0
+    }
+
+    #[inline(always)]
+    fn get_pool_size(&self) -> usize {
+        0
+    }
+
+    #[inline(always)]
+    fn new_ref(&self, value: A) -> Self::Ref {
+        PoolRef::new(self, value)
+    }
+
+    #[inline(always)]
+    fn default_ref(&self) -> Self::Ref
+    where
+        A: PoolDefault,
+    {
+        PoolRef::default(self)
+    }
+
+    #[inline(always)]
+    fn new_box(&self, value: A) -> Self::Box {
+        PoolBox::new(self, value)
+    }
+
+    #[inline(always)]
+    fn default_box(&self) -> Self::Box
+    where
+        A: PoolDefault,
+    {
+        PoolBox::default(self)
+    }
+
+    #[inline(always)]
+    fn make_mut<'a>(&self, this: &'a mut Self::Ref) -> &'a mut A
+    where
+        A: PoolClone,
+    {
+        PoolRef::make_mut(self, this)
+    }
+}
+
 impl<A> Debug for Pool<A> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
         write!(f, "FakePool")
@@ -90,6 +310,21 @@ impl<A> PoolRef<A> {
         Self(Rc::new(value))
     }
 
+    #[cfg(feature = "thread-local")]
+    #[inline(always)]
+    pub fn new_tl(value: A) -> Self {
+        Self(Rc::new(value))
+    }
+
+    #[cfg(feature = "thread-local")]
+    #[inline(always)]
+    pub fn default_tl() -> Self
+    where
+        A: PoolDefault,
+    {
+        Self(Default::default())
+    }
+
     #[inline(always)]
     pub fn clone_from(_pool: &Pool<A>, value: &A) -> Self
     where
@@ -98,12 +333,32 @@ impl<A> PoolRef<A> {
         Self(Rc::new(value.clone()))
     }
 
+    /// A fake pool is always empty, so this always fails.
+    #[inline(always)]
+    pub fn try_default(_pool: &Pool<A>) -> Option<Self>
+    where
+        A: PoolDefault,
+    {
+        None
+    }
+
+    /// A fake pool is always empty, so this always fails.
+    #[inline(always)]
+    pub fn try_new(_pool: &Pool<A>, value: A) -> Result<Self, A> {
+        Err(value)
+    }
+
+    #[inline(always)]
+    pub fn try_alloc(_pool: &Pool<A>, value: A) -> Result<Self, AllocError> {
+        Ok(Self(Rc::new(value)))
+    }
+
     #[inline(always)]
     pub fn pin_default(_pool: &Pool<A>) -> Pin<Self>
     where
         A: PoolDefault,
     {
-        unsafe { Pin::new_unchecked(Self(Rc::new(A::default()))) }
+        unsafe { Pin::new_unchecked(Self(Default::default())) }
     }
 
     #[inline(always)]
@@ -127,6 +382,29 @@ impl<A> PoolRef<A> {
         Rc::make_mut(&mut this.0)
     }
 
+    #[inline(always)]
+    pub fn try_cloned(_pool: &Pool<A>, this: &Self) -> Result<Self, A::Error>
+    where
+        A: PoolTryClone,
+    {
+        let mut value = MaybeUninit::uninit();
+        unsafe {
+            this.deref().try_clone_uninit(&mut value)?;
+            Ok(Self(Rc::new(value.assume_init())))
+        }
+    }
+
+    #[inline(always)]
+    pub fn try_make_mut<'a>(pool: &Pool<A>, this: &'a mut Self) -> Result<&'a mut A, A::Error>
+    where
+        A: PoolTryClone,
+    {
+        if Rc::get_mut(&mut this.0).is_none() {
+            *this = Self::try_cloned(pool, this)?;
+        }
+        Ok(Rc::get_mut(&mut this.0).unwrap())
+    }
+
     #[inline(always)]
     pub fn get_mut(this: &mut Self) -> Option<&mut A> {
         Rc::get_mut(&mut this.0)
@@ -137,6 +415,15 @@ impl<A> PoolRef<A> {
         Rc::try_unwrap(this.0).map_err(Self)
     }
 
+    #[inline(always)]
+    pub fn migrate(this: Self, _pool: &Pool<A>) -> Result<Self, Self> {
+        if Rc::strong_count(&this.0) == 1 {
+            Ok(this)
+        } else {
+            Err(this)
+        }
+    }
+
     #[inline(always)]
     pub fn unwrap_or_clone(this: Self) -> A
     where
@@ -145,94 +432,165 @@ impl<A> PoolRef<A> {
         Self::try_unwrap(this).unwrap_or_else(|this| this.deref().clone())
     }
 
+    #[inline(always)]
+    pub fn swap(left: &mut Self, right: &mut Self) {
+        std::mem::swap(left, right)
+    }
+
+    #[inline(always)]
+    pub fn replace(this: &mut Self, other: Self) -> Self {
+        std::mem::replace(this, other)
+    }
+
+    #[inline(always)]
+    pub fn set(this: &mut Self, value: A) -> Self {
+        std::mem::replace(this, Self(Rc::new(value)))
+    }
+
     #[inline(always)]
     pub fn ptr_eq(left: &Self, right: &Self) -> bool {
         Rc::ptr_eq(&left.0, &right.0)
     }
 
+    #[inline(always)]
+    pub fn ptr_hash<H: Hasher>(this: &Self, hasher: &mut H) {
+        std::ptr::hash(Rc::as_ptr(&this.0), hasher)
+    }
+
     #[inline(always)]
     pub fn strong_count(this: &Self) -> usize {
         Rc::strong_count(&this.0)
     }
 
+    #[inline(always)]
+    pub fn new_with<F>(pool: &Pool<A>, init: F) -> Self
+    where
+        F: FnOnce(&mut MaybeUninit<A>) -> &mut A,
+    {
+        let mut slot = PoolRef::new_uninit(pool);
+        let expected = slot.as_ptr();
+        let written: *const A = init(PoolRef::get_mut(&mut slot).unwrap());
+        assert!(
+            std::ptr::eq(written, expected),
+            "the initialiser must return a reference to the slot it was given"
+        );
+        unsafe { PoolRef::assume_init(slot) }
+    }
+
+    #[inline(always)]
+    pub fn new_zeroed(_pool: &Pool<A>) -> PoolRef<MaybeUninit<A>> {
+        PoolRef(Rc::new(MaybeUninit::zeroed()))
+    }
+
+    #[cfg(feature = "bytemuck")]
+    #[inline(always)]
+    pub fn zeroed(pool: &Pool<A>) -> Self
+    where
+        A: bytemuck::Zeroable,
+    {
+        unsafe { PoolRef::assume_init(Self::new_zeroed(pool)) }
+    }
+
+    #[inline(always)]
+    pub fn new_uninit(_pool: &Pool<A>) -> PoolRef<MaybeUninit<A>> {
+        PoolRef(Rc::new(MaybeUninit::uninit()))
+    }
+
+    #[inline(always)]
+    pub fn borrow_ref(this: &Self) -> PoolRefBorrow<'_, A> {
+        PoolRefBorrow(this)
+    }
+
+    #[inline(always)]
+    pub fn map<T, F>(this: Self, f: F) -> PoolRefMap<A, T>
+    where
+        T: ?Sized,
+        F: FnOnce(&A) -> &T,
+    {
+        let value = NonNull::from(f(&this));
+        PoolRefMap { owner: this, value }
+    }
+
+    #[inline(always)]
+    pub fn drop_batch<I>(refs: I)
+    where
+        I: IntoIterator<Item = Self>,
+    {
+        refs.into_iter().for_each(drop)
+    }
+
+    #[inline(always)]
+    pub fn clone_slice(refs: &[Self]) -> Vec<Self> {
+        refs.to_vec()
+    }
+
     #[inline(always)]
     pub fn into_raw(this: PoolRef<A>) -> *const A {
         Rc::into_raw(this.0)
     }
 
     #[inline(always)]
-    pub unsafe fn from_raw(ptr: *mut A) -> Self {
+    pub unsafe fn from_raw(ptr: *const A) -> Self {
         Self(Rc::from_raw(ptr))
     }
 }
 
-impl<A> Clone for PoolRef<A> {
+/// A fake `PoolRefBorrow`, which is just a reference to a `PoolRef`.
+pub struct PoolRefBorrow<'a, A>(&'a PoolRef<A>);
+
+impl<'a, A> PoolRefBorrow<'a, A> {
     #[inline(always)]
-    fn clone(&self) -> Self {
-        PoolRef(self.0.clone())
+    pub fn to_owned(self) -> PoolRef<A> {
+        self.0.clone()
     }
-}
 
-impl<A> Deref for PoolRef<A> {
-    type Target = A;
     #[inline(always)]
-    fn deref(&self) -> &Self::Target {
+    pub fn get(self) -> &'a A {
         self.0.deref()
     }
-}
 
-impl<A> AsRef<A> for PoolRef<A> {
-    fn as_ref(&self) -> &A {
-        self.deref()
+    #[inline(always)]
+    pub fn ptr_eq(left: Self, right: Self) -> bool {
+        PoolRef::ptr_eq(left.0, right.0)
     }
-}
 
-impl<A> Borrow<A> for PoolRef<A> {
-    fn borrow(&self) -> &A {
-        self.deref()
+    #[inline(always)]
+    pub fn strong_count(this: Self) -> usize {
+        PoolRef::strong_count(this.0)
     }
 }
 
-impl<A> PartialEq for PoolRef<A>
-where
-    A: PartialEq,
-{
+impl<'a, A> Clone for PoolRefBorrow<'a, A> {
     #[inline(always)]
-    fn eq(&self, other: &Self) -> bool {
-        **self == **other
+    fn clone(&self) -> Self {
+        *self
     }
 }
 
-impl<A> Eq for PoolRef<A> where A: Eq {}
+impl<'a, A> Copy for PoolRefBorrow<'a, A> {}
 
-impl<A> PartialOrd for PoolRef<A>
-where
-    A: PartialOrd,
-{
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        (**self).partial_cmp(&**other)
+impl<'a, A> From<&'a PoolRef<A>> for PoolRefBorrow<'a, A> {
+    #[inline(always)]
+    fn from(pool_ref: &'a PoolRef<A>) -> Self {
+        PoolRefBorrow(pool_ref)
     }
 }
 
-impl<A> Ord for PoolRef<A>
-where
-    A: Ord,
-{
-    fn cmp(&self, other: &Self) -> Ordering {
-        (**self).cmp(&**other)
+impl<'a, A> Deref for PoolRefBorrow<'a, A> {
+    type Target = A;
+    #[inline(always)]
+    fn deref(&self) -> &Self::Target {
+        self.get()
     }
 }
 
-impl<A> Hash for PoolRef<A>
-where
-    A: Hash,
-{
-    fn hash<H: Hasher>(&self, hasher: &mut H) {
-        (**self).hash(hasher)
+impl<'a, A> AsRef<A> for PoolRefBorrow<'a, A> {
+    fn as_ref(&self) -> &A {
+        self.get()
     }
 }
 
-impl<A> Display for PoolRef<A>
+impl<'a, A> Display for PoolRefBorrow<'a, A>
 where
     A: Display,
 {
@@ -241,7 +599,220 @@ where
     }
 }
 
-impl<A> Debug for PoolRef<A>
+impl<'a, A> Debug for PoolRefBorrow<'a, A>
+where
+    A: Debug,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        (**self).fmt(f)
+    }
+}
+
+/// A fake `PoolRefMap`, which is a fake `PoolRef` and a pointer into it.
+pub struct PoolRefMap<A, T: ?Sized> {
+    owner: PoolRef<A>,
+    value: NonNull<T>,
+}
+
+impl<A, T: ?Sized> PoolRefMap<A, T> {
+    #[inline(always)]
+    pub fn map<U, F>(this: Self, f: F) -> PoolRefMap<A, U>
+    where
+        U: ?Sized,
+        F: FnOnce(&T) -> &U,
+    {
+        let value = NonNull::from(f(&this));
+        PoolRefMap {
+            owner: this.owner,
+            value,
+        }
+    }
+
+    #[inline(always)]
+    pub fn owner(this: &Self) -> &PoolRef<A> {
+        &this.owner
+    }
+
+    #[inline(always)]
+    pub fn into_owner(this: Self) -> PoolRef<A> {
+        this.owner
+    }
+}
+
+impl<A, T: ?Sized> Clone for PoolRefMap<A, T> {
+    #[inline(always)]
+    fn clone(&self) -> Self {
+        PoolRefMap {
+            owner: self.owner.clone(),
+            value: self.value,
+        }
+    }
+}
+
+impl<A, T: ?Sized> Deref for PoolRefMap<A, T> {
+    type Target = T;
+    #[inline(always)]
+    fn deref(&self) -> &Self::Target {
+        unsafe { self.value.as_ref() }
+    }
+}
+
+impl<A, T: ?Sized> AsRef<T> for PoolRefMap<A, T> {
+    fn as_ref(&self) -> &T {
+        self.deref()
+    }
+}
+
+impl<A, T: ?Sized> Borrow<T> for PoolRefMap<A, T> {
+    fn borrow(&self) -> &T {
+        self.deref()
+    }
+}
+
+impl<A, T> Display for PoolRefMap<A, T>
+where
+    T: Display + ?Sized,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        (**self).fmt(f)
+    }
+}
+
+impl<A, T> Debug for PoolRefMap<A, T>
+where
+    T: Debug + ?Sized,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        (**self).fmt(f)
+    }
+}
+
+impl<A> PoolRef<MaybeUninit<A>> {
+    #[inline(always)]
+    pub fn write(mut this: Self, value: A) -> PoolRef<A> {
+        match PoolRef::get_mut(&mut this) {
+            Some(slot) => {
+                slot.write(value);
+            }
+            None => panic!("PoolRef::write: can't initialise a shared reference"),
+        }
+        unsafe { Self::assume_init(this) }
+    }
+
+    #[inline(always)]
+    pub unsafe fn assume_init(this: Self) -> PoolRef<A> {
+        PoolRef(Rc::from_raw(Rc::into_raw(this.0).cast()))
+    }
+}
+
+impl<A> Clone for PoolRef<A> {
+    #[inline(always)]
+    fn clone(&self) -> Self {
+        PoolRef(self.0.clone())
+    }
+}
+
+impl<A> Deref for PoolRef<A> {
+    type Target = A;
+    #[inline(always)]
+    fn deref(&self) -> &Self::Target {
+        self.0.deref()
+    }
+}
+
+impl<A> AsRef<A> for PoolRef<A> {
+    fn as_ref(&self) -> &A {
+        self.deref()
+    }
+}
+
+impl<A> Borrow<A> for PoolRef<A> {
+    fn borrow(&self) -> &A {
+        self.deref()
+    }
+}
+
+impl<A> PartialEq for PoolRef<A>
+where
+    A: PartialEq,
+{
+    #[inline(always)]
+    fn eq(&self, other: &Self) -> bool {
+        **self == **other
+    }
+}
+
+impl<A> Eq for PoolRef<A> where A: Eq {}
+
+impl<A> PartialEq<A> for PoolRef<A>
+where
+    A: PartialEq,
+{
+    fn eq(&self, other: &A) -> bool {
+        (**self) == *other
+    }
+}
+
+impl<A> PartialEq<PoolBox<A>> for PoolRef<A>
+where
+    A: PartialEq,
+{
+    fn eq(&self, other: &PoolBox<A>) -> bool {
+        (**self) == (**other)
+    }
+}
+
+impl<A> PartialOrd for PoolRef<A>
+where
+    A: PartialOrd,
+{
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        (**self).partial_cmp(&**other)
+    }
+}
+
+impl<A> Ord for PoolRef<A>
+where
+    A: Ord,
+{
+    fn cmp(&self, other: &Self) -> Ordering {
+        (**self).cmp(&**other)
+    }
+}
+
+impl<A> Hash for PoolRef<A>
+where
+    A: Hash,
+{
+    fn hash<H: Hasher>(&self, hasher: &mut H) {
+        (**self).hash(hasher)
+    }
+}
+
+impl<A> PartialEq for ByPtr<PoolRef<A>> {
+    fn eq(&self, other: &Self) -> bool {
+        PoolRef::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl<A> Eq for ByPtr<PoolRef<A>> {}
+
+impl<A> Hash for ByPtr<PoolRef<A>> {
+    fn hash<H: Hasher>(&self, hasher: &mut H) {
+        PoolRef::ptr_hash(&self.0, hasher)
+    }
+}
+
+impl<A> Display for PoolRef<A>
+where
+    A: Display,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        (**self).fmt(f)
+    }
+}
+
+impl<A> Debug for PoolRef<A>
 where
     A: Debug,
 {
@@ -256,6 +827,20 @@ impl<A> std::fmt::Pointer for PoolRef<A> {
     }
 }
 
+impl<A> std::error::Error for PoolRef<A>
+where
+    A: std::error::Error,
+{
+    #[allow(deprecated)]
+    fn description(&self) -> &str {
+        (**self).description()
+    }
+
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        (**self).source()
+    }
+}
+
 /// A fake `PoolBox` which wraps a `Box`.
 pub struct PoolBox<A>(Box<A>);
 
@@ -265,7 +850,7 @@ impl<A> PoolBox<A> {
     where
         A: PoolDefault,
     {
-        Self(Box::new(A::default()))
+        Self(Box::default())
     }
 
     #[inline(always)]
@@ -273,6 +858,21 @@ impl<A> PoolBox<A> {
         Self(Box::new(value))
     }
 
+    #[cfg(feature = "thread-local")]
+    #[inline(always)]
+    pub fn new_tl(value: A) -> Self {
+        Self(Box::new(value))
+    }
+
+    #[cfg(feature = "thread-local")]
+    #[inline(always)]
+    pub fn default_tl() -> Self
+    where
+        A: PoolDefault,
+    {
+        Self(Default::default())
+    }
+
     #[inline(always)]
     pub fn clone_from(_pool: &Pool<A>, value: &A) -> Self
     where
@@ -281,12 +881,32 @@ impl<A> PoolBox<A> {
         Self(Box::new(value.clone()))
     }
 
+    /// A fake pool is always empty, so this always fails.
+    #[inline(always)]
+    pub fn try_default(_pool: &Pool<A>) -> Option<Self>
+    where
+        A: PoolDefault,
+    {
+        None
+    }
+
+    /// A fake pool is always empty, so this always fails.
+    #[inline(always)]
+    pub fn try_new(_pool: &Pool<A>, value: A) -> Result<Self, A> {
+        Err(value)
+    }
+
+    #[inline(always)]
+    pub fn try_alloc(_pool: &Pool<A>, value: A) -> Result<Self, AllocError> {
+        Ok(Self(Box::new(value)))
+    }
+
     #[inline(always)]
     pub fn pin_default(_pool: &Pool<A>) -> Pin<Self>
     where
         A: PoolDefault,
     {
-        unsafe { Pin::new_unchecked(Self(Box::new(A::default()))) }
+        unsafe { Pin::new_unchecked(Self(Box::default())) }
     }
 
     #[inline(always)]
@@ -294,11 +914,60 @@ impl<A> PoolBox<A> {
         unsafe { Pin::new_unchecked(Self(Box::new(value))) }
     }
 
+    #[inline(always)]
+    pub fn into_pin(this: Self) -> Pin<Self> {
+        unsafe { Pin::new_unchecked(this) }
+    }
+
+    #[inline(always)]
+    pub fn into_inner(this: Self) -> A {
+        *this.0
+    }
+
+    #[inline(always)]
+    pub fn new_with<F>(pool: &Pool<A>, init: F) -> Self
+    where
+        F: FnOnce(&mut MaybeUninit<A>) -> &mut A,
+    {
+        let mut slot = PoolBox::new_uninit(pool);
+        let expected = slot.as_ptr();
+        let written: *const A = init(&mut slot);
+        assert!(
+            std::ptr::eq(written, expected),
+            "the initialiser must return a reference to the slot it was given"
+        );
+        unsafe { PoolBox::assume_init(slot) }
+    }
+
+    #[inline(always)]
+    pub fn new_zeroed(_pool: &Pool<A>) -> PoolBox<MaybeUninit<A>> {
+        PoolBox(Box::new(MaybeUninit::zeroed()))
+    }
+
+    #[cfg(feature = "bytemuck")]
+    #[inline(always)]
+    pub fn zeroed(pool: &Pool<A>) -> Self
+    where
+        A: bytemuck::Zeroable,
+    {
+        unsafe { PoolBox::assume_init(Self::new_zeroed(pool)) }
+    }
+
+    #[inline(always)]
+    pub fn new_uninit(_pool: &Pool<A>) -> PoolBox<MaybeUninit<A>> {
+        PoolBox(Box::new(MaybeUninit::uninit()))
+    }
+
     #[inline(always)]
     pub fn ptr_eq(left: &Self, right: &Self) -> bool {
         std::ptr::eq(left.0.deref(), right.0.deref())
     }
 
+    #[inline(always)]
+    pub fn into_raw_non_null(this: Self) -> NonNull<A> {
+        unsafe { NonNull::new_unchecked(Box::into_raw(this.0)) }
+    }
+
     #[inline(always)]
     pub fn into_raw(this: Self) -> *mut A {
         Box::into_raw(this.0)
@@ -308,6 +977,44 @@ impl<A> PoolBox<A> {
     pub unsafe fn from_raw(ptr: *mut A) -> Self {
         Self(Box::from_raw(ptr))
     }
+
+    #[inline(always)]
+    pub fn as_non_null(this: &mut Self) -> NonNull<A> {
+        NonNull::from(this.0.deref_mut())
+    }
+
+    #[inline(always)]
+    pub fn release_raw(_this: &mut Self) {}
+}
+
+impl<A> PoolBox<MaybeUninit<A>> {
+    #[inline(always)]
+    pub fn write(mut this: Self, value: A) -> PoolBox<A> {
+        (*this).write(value);
+        unsafe { Self::assume_init(this) }
+    }
+
+    #[inline(always)]
+    pub unsafe fn assume_init(this: Self) -> PoolBox<A> {
+        PoolBox(Box::from_raw(Box::into_raw(this.0).cast()))
+    }
+}
+
+impl<A> Clone for PoolBox<A>
+where
+    A: PoolClone,
+{
+    #[inline(always)]
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<A> From<PoolBox<A>> for Pin<PoolBox<A>> {
+    #[inline(always)]
+    fn from(value: PoolBox<A>) -> Self {
+        PoolBox::into_pin(value)
+    }
 }
 
 impl<A> Deref for PoolBox<A> {
@@ -359,6 +1066,24 @@ where
 
 impl<A> Eq for PoolBox<A> where A: Eq {}
 
+impl<A> PartialEq<A> for PoolBox<A>
+where
+    A: PartialEq,
+{
+    fn eq(&self, other: &A) -> bool {
+        (**self) == *other
+    }
+}
+
+impl<A> PartialEq<PoolRef<A>> for PoolBox<A>
+where
+    A: PartialEq,
+{
+    fn eq(&self, other: &PoolRef<A>) -> bool {
+        (**self) == (**other)
+    }
+}
+
 impl<A> PartialOrd for PoolBox<A>
 where
     A: PartialOrd,
@@ -409,3 +1134,585 @@ impl<A> std::fmt::Pointer for PoolBox<A> {
         std::fmt::Pointer::fmt(&(&**self as *const A), f)
     }
 }
+
+impl<A> std::error::Error for PoolBox<A>
+where
+    A: std::error::Error,
+{
+    #[allow(deprecated)]
+    fn description(&self) -> &str {
+        (**self).description()
+    }
+
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        (**self).source()
+    }
+}
+
+impl<H> Hasher for PoolBox<H>
+where
+    H: Hasher,
+{
+    fn finish(&self) -> u64 {
+        (**self).finish()
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        (**self).write(bytes)
+    }
+
+    fn write_u8(&mut self, i: u8) {
+        (**self).write_u8(i)
+    }
+
+    fn write_u16(&mut self, i: u16) {
+        (**self).write_u16(i)
+    }
+
+    fn write_u32(&mut self, i: u32) {
+        (**self).write_u32(i)
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        (**self).write_u64(i)
+    }
+
+    fn write_u128(&mut self, i: u128) {
+        (**self).write_u128(i)
+    }
+
+    fn write_usize(&mut self, i: usize) {
+        (**self).write_usize(i)
+    }
+
+    fn write_i8(&mut self, i: i8) {
+        (**self).write_i8(i)
+    }
+
+    fn write_i16(&mut self, i: i16) {
+        (**self).write_i16(i)
+    }
+
+    fn write_i32(&mut self, i: i32) {
+        (**self).write_i32(i)
+    }
+
+    fn write_i64(&mut self, i: i64) {
+        (**self).write_i64(i)
+    }
+
+    fn write_i128(&mut self, i: i128) {
+        (**self).write_i128(i)
+    }
+
+    fn write_isize(&mut self, i: isize) {
+        (**self).write_isize(i)
+    }
+}
+
+impl<W> std::fmt::Write for PoolBox<W>
+where
+    W: std::fmt::Write,
+{
+    fn write_str(&mut self, s: &str) -> std::fmt::Result {
+        (**self).write_str(s)
+    }
+
+    fn write_char(&mut self, c: char) -> std::fmt::Result {
+        (**self).write_char(c)
+    }
+
+    fn write_fmt(&mut self, args: Arguments<'_>) -> std::fmt::Result {
+        (**self).write_fmt(args)
+    }
+}
+
+impl<R> Read for PoolBox<R>
+where
+    R: Read,
+{
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        (**self).read(buf)
+    }
+
+    fn read_vectored(&mut self, bufs: &mut [IoSliceMut<'_>]) -> io::Result<usize> {
+        (**self).read_vectored(bufs)
+    }
+
+    fn read_to_end(&mut self, buf: &mut Vec<u8>) -> io::Result<usize> {
+        (**self).read_to_end(buf)
+    }
+
+    fn read_to_string(&mut self, buf: &mut String) -> io::Result<usize> {
+        (**self).read_to_string(buf)
+    }
+
+    fn read_exact(&mut self, buf: &mut [u8]) -> io::Result<()> {
+        (**self).read_exact(buf)
+    }
+}
+
+impl<W> Write for PoolBox<W>
+where
+    W: Write,
+{
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        (**self).write(buf)
+    }
+
+    fn write_vectored(&mut self, bufs: &[IoSlice<'_>]) -> io::Result<usize> {
+        (**self).write_vectored(bufs)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        (**self).flush()
+    }
+
+    fn write_all(&mut self, buf: &[u8]) -> io::Result<()> {
+        (**self).write_all(buf)
+    }
+
+    fn write_fmt(&mut self, fmt: Arguments<'_>) -> io::Result<()> {
+        (**self).write_fmt(fmt)
+    }
+}
+
+impl<S> Seek for PoolBox<S>
+where
+    S: Seek,
+{
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        (**self).seek(pos)
+    }
+
+    fn stream_position(&mut self) -> io::Result<u64> {
+        (**self).stream_position()
+    }
+}
+
+impl<B> BufRead for PoolBox<B>
+where
+    B: BufRead,
+{
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        (**self).fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        (**self).consume(amt)
+    }
+
+    fn read_until(&mut self, byte: u8, buf: &mut Vec<u8>) -> io::Result<usize> {
+        (**self).read_until(byte, buf)
+    }
+
+    fn read_line(&mut self, buf: &mut String) -> io::Result<usize> {
+        (**self).read_line(buf)
+    }
+}
+
+#[cfg(feature = "fn_traits")]
+impl<Args, F> FnOnce<Args> for PoolBox<F>
+where
+    Args: std::marker::Tuple,
+    F: FnOnce<Args>,
+{
+    type Output = F::Output;
+
+    #[inline(always)]
+    extern "rust-call" fn call_once(self, args: Args) -> Self::Output {
+        (*self.0).call_once(args)
+    }
+}
+
+#[cfg(feature = "fn_traits")]
+impl<Args, F> FnMut<Args> for PoolBox<F>
+where
+    Args: std::marker::Tuple,
+    F: FnMut<Args>,
+{
+    #[inline(always)]
+    extern "rust-call" fn call_mut(&mut self, args: Args) -> Self::Output {
+        (**self).call_mut(args)
+    }
+}
+
+#[cfg(feature = "fn_traits")]
+impl<Args, F> Fn<Args> for PoolBox<F>
+where
+    Args: std::marker::Tuple,
+    F: Fn<Args>,
+{
+    #[inline(always)]
+    extern "rust-call" fn call(&self, args: Args) -> Self::Output {
+        (**self).call(args)
+    }
+}
+
+impl<F> Future for PoolBox<F>
+where
+    F: Future + Unpin,
+{
+    type Output = F::Output;
+
+    #[inline(always)]
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        F::poll(Pin::new(&mut **self), cx)
+    }
+}
+
+impl<I> Iterator for PoolBox<I>
+where
+    I: Iterator,
+{
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        (**self).next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (**self).size_hint()
+    }
+
+    fn nth(&mut self, n: usize) -> Option<Self::Item> {
+        (**self).nth(n)
+    }
+}
+
+impl<I> DoubleEndedIterator for PoolBox<I>
+where
+    I: DoubleEndedIterator,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        (**self).next_back()
+    }
+
+    fn nth_back(&mut self, n: usize) -> Option<Self::Item> {
+        (**self).nth_back(n)
+    }
+}
+
+impl<I> ExactSizeIterator for PoolBox<I>
+where
+    I: ExactSizeIterator,
+{
+    fn len(&self) -> usize {
+        (**self).len()
+    }
+}
+
+impl<I> FusedIterator for PoolBox<I> where I: FusedIterator {}
+
+/// A fake `PoolBuilder`, which ignores its configuration and builds a fake
+/// `Pool`.
+pub struct PoolBuilder<A>(PhantomData<A>);
+
+impl<A> PoolBuilder<A> {
+    pub fn max_size(self, _max_size: usize) -> Self {
+        self
+    }
+
+    pub fn max_bytes(self, _bytes: usize) -> Self {
+        self
+    }
+
+    pub fn bypass_below(self, _bytes: usize) -> Self {
+        self
+    }
+
+    pub fn discard_above(self, _bytes: usize) -> Self {
+        self
+    }
+
+    pub fn prefill(self, _prefill: bool) -> Self {
+        self
+    }
+
+    pub fn zero_on_return(self, _zero_on_return: bool) -> Self {
+        self
+    }
+
+    pub fn stats(self, _stats: bool) -> Self {
+        self
+    }
+
+    #[cfg(feature = "hdrhistogram")]
+    pub fn latency(self, _latency: bool) -> Self {
+        self
+    }
+
+    pub fn live_count(self, _live_count: bool) -> Self {
+        self
+    }
+
+    pub fn overflow(self, _policy: OverflowPolicy) -> Self {
+        self
+    }
+
+    pub fn adaptive(self, _cap: usize) -> Self {
+        self
+    }
+
+    pub fn idle_timeout(self, _timeout: Duration) -> Self {
+        self
+    }
+
+    pub fn recycle(self) -> Self
+    where
+        A: PoolRecycle,
+    {
+        self
+    }
+
+    pub fn storage<S>(self) -> Self
+    where
+        S: PoolStorage,
+    {
+        self
+    }
+
+    pub fn alignment(self, _align: usize) -> Self {
+        self
+    }
+
+    #[cfg(feature = "locked-memory")]
+    pub fn lock_memory(self, _lock_memory: bool) -> Self {
+        self
+    }
+
+    pub fn group(self, _group: &PoolGroup) -> Self {
+        self
+    }
+
+    pub fn build(self) -> Pool<A> {
+        Pool(PhantomData)
+    }
+}
+
+impl<A> Debug for PoolBuilder<A> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        write!(f, "FakePoolBuilder")
+    }
+}
+
+/// A fake `PoolSendToken`.
+pub struct PoolSendToken<A>(PhantomData<A>);
+
+unsafe impl<A> Send for PoolSendToken<A> {}
+
+impl<A> PoolSendToken<A> {
+    pub fn into_pool(self) -> Pool<A> {
+        Pool(PhantomData)
+    }
+}
+
+impl<A> Debug for PoolSendToken<A> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        write!(f, "FakePoolSendToken")
+    }
+}
+
+/// A fake `ChunkGuard`, which reserves nothing.
+pub struct ChunkGuard<A>(PhantomData<A>);
+
+impl<A> ChunkGuard<A> {
+    #[inline(always)]
+    pub fn commit_ref(self, value: A) -> PoolRef<A> {
+        PoolRef(Rc::new(value))
+    }
+
+    #[inline(always)]
+    pub fn commit_box(self, value: A) -> PoolBox<A> {
+        PoolBox(Box::new(value))
+    }
+
+    #[inline(always)]
+    pub fn commit_default(self) -> PoolRef<A>
+    where
+        A: PoolDefault,
+    {
+        PoolRef(Default::default())
+    }
+}
+
+impl<A> Debug for ChunkGuard<A> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        write!(f, "ChunkGuard")
+    }
+}
+
+/// A fake `PoolGuard`, wrapping a fake `PoolBox`.
+pub struct PoolGuard<A>(PoolBox<A>);
+
+impl<A> PoolGuard<A> {
+    #[inline(always)]
+    pub fn into_box(this: Self) -> PoolBox<A> {
+        this.0
+    }
+}
+
+impl<A> Deref for PoolGuard<A> {
+    type Target = A;
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<A> DerefMut for PoolGuard<A> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<A> AsRef<A> for PoolGuard<A> {
+    fn as_ref(&self) -> &A {
+        &self.0
+    }
+}
+
+impl<A> AsMut<A> for PoolGuard<A> {
+    fn as_mut(&mut self) -> &mut A {
+        &mut self.0
+    }
+}
+
+impl<A> Debug for PoolGuard<A>
+where
+    A: Debug,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        self.0.fmt(f)
+    }
+}
+
+/// A fake `PoolArena`, which keeps its values in `Box`es.
+pub struct PoolArena<A>(Pool<A>, RefCell<Vec<NonNull<A>>>);
+
+impl<A> PoolArena<A> {
+    #[inline(always)]
+    pub fn alloc(&self, value: A) -> ArenaBox<'_, A> {
+        self.keep(Box::new(value))
+    }
+
+    #[inline(always)]
+    pub fn alloc_default(&self) -> ArenaBox<'_, A>
+    where
+        A: PoolDefault,
+    {
+        self.keep(Box::default())
+    }
+
+    fn keep(&self, value: Box<A>) -> ArenaBox<'_, A> {
+        let ptr = unsafe { NonNull::new_unchecked(Box::into_raw(value)) };
+        self.1.borrow_mut().push(ptr);
+        ArenaBox(ptr, PhantomData)
+    }
+
+    pub fn len(&self) -> usize {
+        self.1.borrow().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn pool(&self) -> &Pool<A> {
+        &self.0
+    }
+
+    pub fn reset(&mut self) {
+        for ptr in self.1.get_mut().drain(..) {
+            drop(unsafe { Box::from_raw(ptr.as_ptr()) });
+        }
+    }
+}
+
+impl<A> Drop for PoolArena<A> {
+    fn drop(&mut self) {
+        self.reset();
+    }
+}
+
+impl<A> Debug for PoolArena<A> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        write!(f, "FakePoolArena[{}]", self.len())
+    }
+}
+
+/// A fake `ArenaBox`, pointing into a fake `PoolArena`.
+pub struct ArenaBox<'frame, A>(NonNull<A>, PhantomData<&'frame mut A>);
+
+impl<'frame, A> ArenaBox<'frame, A> {
+    #[inline(always)]
+    pub fn leak(this: Self) -> &'frame mut A {
+        unsafe { &mut *this.0.as_ptr() }
+    }
+}
+
+impl<'frame, A> Deref for ArenaBox<'frame, A> {
+    type Target = A;
+    #[inline(always)]
+    fn deref(&self) -> &Self::Target {
+        unsafe { self.0.as_ref() }
+    }
+}
+
+impl<'frame, A> DerefMut for ArenaBox<'frame, A> {
+    #[inline(always)]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        unsafe { self.0.as_mut() }
+    }
+}
+
+impl<'frame, A> AsRef<A> for ArenaBox<'frame, A> {
+    fn as_ref(&self) -> &A {
+        self.deref()
+    }
+}
+
+impl<'frame, A> AsMut<A> for ArenaBox<'frame, A> {
+    fn as_mut(&mut self) -> &mut A {
+        self.deref_mut()
+    }
+}
+
+impl<'frame, A> Display for ArenaBox<'frame, A>
+where
+    A: Display,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        (**self).fmt(f)
+    }
+}
+
+impl<'frame, A> Debug for ArenaBox<'frame, A>
+where
+    A: Debug,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        (**self).fmt(f)
+    }
+}
+
+/// A fake `SpeculativePool`, which can't roll anything back.
+pub struct SpeculativePool<A>(Pool<A>);
+
+impl<A> SpeculativePool<A> {
+    pub fn commit(self) {}
+
+    pub fn rollback(self) {}
+}
+
+impl<A> Deref for SpeculativePool<A> {
+    type Target = Pool<A>;
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<A> Debug for SpeculativePool<A> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        write!(f, "FakeSpeculativePool")
+    }
+}
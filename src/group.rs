@@ -0,0 +1,189 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+// With the `disable-pool` feature flag, there's no real pool to make use of
+// the crate internals here, only the fake pool which ignores them.
+#![cfg_attr(feature = "disable-pool", allow(dead_code))]
+
+use std::cell::RefCell;
+use std::fmt::{Debug, Error, Formatter};
+use std::rc::Rc;
+
+/// A function which releases up to the given number of bytes from a pool,
+/// returning the number of bytes actually released.
+pub(crate) type TrimFn = unsafe fn(*const (), usize) -> usize;
+
+struct Member {
+    pool: *const (),
+    trim: TrimFn,
+}
+
+struct GroupInner {
+    max_bytes: usize,
+    retained_bytes: usize,
+    trim_others: bool,
+    members: Vec<Member>,
+}
+
+/// A retention budget shared between several pools.
+///
+/// Pools join a group through
+/// [`PoolBuilder::group()`][PoolBuilder::group], which lets you bound the
+/// total amount of memory held by a set of pools, regardless of their types,
+/// rather than having to bound each pool individually.
+///
+/// When memory is returned to a pool in the group while the group is at its
+/// budget, the memory is either discarded, or, if the group was constructed
+/// with [`PoolGroup::trimming()`][trimming], the group releases memory held by
+/// the other pools in the group to make room, starting with the pools which
+/// joined the group first.
+///
+/// # Examples
+///
+/// ```rust
+/// # use refpool::{Pool, PoolGroup, PoolRef};
+/// let group = PoolGroup::new(4096);
+/// let small: Pool<[u8; 16]> = Pool::builder().max_size(1024).group(&group).build();
+/// let large: Pool<[u8; 1024]> = Pool::builder().max_size(1024).group(&group).build();
+/// small.fill();
+/// large.fill();
+/// assert!(group.retained_bytes() <= 4096);
+/// assert!(small.get_pool_size() < 1024);
+/// assert_eq!(0, large.get_pool_size());
+/// ```
+///
+/// [PoolBuilder::group]: struct.PoolBuilder.html#method.group
+/// [trimming]: #method.trimming
+#[derive(Clone)]
+pub struct PoolGroup {
+    inner: Rc<RefCell<GroupInner>>,
+}
+
+impl PoolGroup {
+    fn with_policy(max_bytes: usize, trim_others: bool) -> Self {
+        PoolGroup {
+            inner: Rc::new(RefCell::new(GroupInner {
+                max_bytes,
+                retained_bytes: 0,
+                trim_others,
+                members: Vec::new(),
+            })),
+        }
+    }
+
+    /// Construct a group with a budget of `max_bytes`, which discards memory
+    /// returned to its pools when it's at its budget.
+    pub fn new(max_bytes: usize) -> Self {
+        Self::with_policy(max_bytes, false)
+    }
+
+    /// Construct a group with a budget of `max_bytes`, which releases memory
+    /// held by its other pools to make room for memory returned to a pool
+    /// when it's at its budget.
+    ///
+    /// # Examples
+    ///
+    #[cfg_attr(feature = "disable-pool", doc = "```ignore")]
+    #[cfg_attr(not(feature = "disable-pool"), doc = "```rust")]
+    /// # use refpool::{Pool, PoolGroup, PoolRef};
+    /// let group = PoolGroup::trimming(1024);
+    /// let old: Pool<[u8; 64]> = Pool::builder().max_size(64).group(&group).build();
+    /// let new: Pool<[u8; 64]> = Pool::builder().max_size(64).group(&group).build();
+    /// old.fill();
+    /// let old_size = old.get_pool_size();
+    /// assert!(old_size > 0);
+    /// drop(PoolRef::new(&new, [0; 64]));
+    /// assert_eq!(1, new.get_pool_size());
+    /// assert_eq!(old_size - 1, old.get_pool_size());
+    /// ```
+    pub fn trimming(max_bytes: usize) -> Self {
+        Self::with_policy(max_bytes, true)
+    }
+
+    /// Get the maximum number of bytes the pools in the group may hold.
+    pub fn max_bytes(&self) -> usize {
+        self.inner.borrow().max_bytes
+    }
+
+    /// Get the number of bytes currently held by the pools in the group.
+    pub fn retained_bytes(&self) -> usize {
+        self.inner.borrow().retained_bytes
+    }
+
+    pub(crate) fn join(&self, pool: *const (), trim: TrimFn) {
+        self.inner.borrow_mut().members.push(Member { pool, trim });
+    }
+
+    pub(crate) fn leave(&self, pool: *const (), retained_bytes: usize) {
+        let mut inner = self.inner.borrow_mut();
+        inner.members.retain(|member| member.pool != pool);
+        inner.retained_bytes -= retained_bytes;
+    }
+
+    /// Try to make room for `bytes` more bytes in the group on behalf of
+    /// `pool`, and account for them if successful.
+    pub(crate) fn acquire(&self, pool: *const (), bytes: usize) -> bool {
+        let needed = {
+            let inner = self.inner.borrow();
+            (inner.retained_bytes + bytes).saturating_sub(inner.max_bytes)
+        };
+        if needed > 0 && self.inner.borrow().trim_others {
+            self.trim_others(pool, needed);
+        }
+        let mut inner = self.inner.borrow_mut();
+        if inner.retained_bytes + bytes > inner.max_bytes {
+            false
+        } else {
+            inner.retained_bytes += bytes;
+            true
+        }
+    }
+
+    /// Release up to `needed` bytes from the pools in the group other than
+    /// `pool`.
+    ///
+    /// Trimming a pool drops the values it's recycling, which may return
+    /// memory to other pools in the group and re-enter it, or even drop the
+    /// last handle to another pool, making it leave the group. So the group
+    /// isn't borrowed while a pool is being trimmed, and each pool is checked
+    /// for still being a member before it's trimmed.
+    fn trim_others(&self, pool: *const (), mut needed: usize) {
+        let candidates: Vec<(*const (), TrimFn)> = self
+            .inner
+            .borrow()
+            .members
+            .iter()
+            .filter(|member| member.pool != pool)
+            .map(|member| (member.pool, member.trim))
+            .collect();
+        for (candidate, trim) in candidates {
+            let is_member = self
+                .inner
+                .borrow()
+                .members
+                .iter()
+                .any(|member| member.pool == candidate);
+            if !is_member {
+                continue;
+            }
+            let freed = unsafe { trim(candidate, needed) };
+            self.inner.borrow_mut().retained_bytes -= freed;
+            needed = needed.saturating_sub(freed);
+            if needed == 0 {
+                break;
+            }
+        }
+    }
+
+    pub(crate) fn release(&self, bytes: usize) {
+        self.inner.borrow_mut().retained_bytes -= bytes;
+    }
+}
+
+impl Debug for PoolGroup {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        let inner = self.inner.borrow();
+        write!(f, "PoolGroup[{}/{}]", inner.retained_bytes, inner.max_bytes)
+    }
+}
@@ -0,0 +1,47 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::time::{Duration, Instant};
+
+/// Tracking of chunks which have sat unused in a pool.
+///
+/// The pool's size is observed every time a chunk is popped, and once per
+/// `timeout` period the smallest size seen during the period is reported as
+/// idle: those chunks weren't needed at any point during the period. Pushes
+/// only check whether the period has ended, and a period with no pops counts
+/// every chunk in the pool as idle, so the chunks returned after a spike in
+/// demand are released at the end of the period the spike happened in.
+#[derive(Debug)]
+pub(crate) struct IdleTrim {
+    timeout: Duration,
+    since: Instant,
+    low_water: usize,
+}
+
+impl IdleTrim {
+    pub(crate) fn new(timeout: Duration) -> Self {
+        IdleTrim {
+            timeout,
+            since: Instant::now(),
+            low_water: usize::MAX,
+        }
+    }
+
+    /// Observe the pool's current size after a chunk has been `taken` from
+    /// it or returned to it, returning the number of chunks which should be
+    /// released if a period has ended.
+    pub(crate) fn observe(&mut self, pool_size: usize, taken: bool) -> Option<usize> {
+        if taken {
+            self.low_water = self.low_water.min(pool_size);
+        }
+        let now = Instant::now();
+        if now.duration_since(self.since) < self.timeout {
+            return None;
+        }
+        let idle = self.low_water.min(pool_size);
+        self.since = now;
+        self.low_water = usize::MAX;
+        Some(idle)
+    }
+}
@@ -0,0 +1,51 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Adapters for the chunk types used by [`imbl`][imbl].
+//!
+//! `imbl`'s persistent data structures are built out of fixed size chunks from
+//! [`imbl-sized-chunks`][imbl-sized-chunks], and these are the types you'll
+//! want to allocate from a [`Pool`][Pool] when building pooled nodes for such
+//! a structure. This module provides [`PoolDefault`][PoolDefault] and
+//! [`PoolClone`][PoolClone] for them, through
+//! [`PoolDefaultImpl`][PoolDefaultImpl], so that they can be used with
+//! [`PoolRef::default()`][PoolRef::default] and
+//! [`PoolRef::make_mut()`][PoolRef::make_mut] without further ado.
+//!
+//! # Examples
+//!
+//! ```rust
+//! # use refpool::{Pool, PoolRef};
+//! use imbl_sized_chunks::Chunk;
+//!
+//! let pool: Pool<Chunk<usize, 64>> = Pool::new(16);
+//! let mut chunk = PoolRef::default(&pool);
+//! PoolRef::make_mut(&pool, &mut chunk).push_back(1337);
+//! let copy = chunk.clone();
+//! PoolRef::make_mut(&pool, &mut chunk).push_back(31337);
+//! assert_eq!(1, copy.len());
+//! assert_eq!(2, chunk.len());
+//! ```
+//!
+//! See `examples/imbl_chunks.rs` for a more complete example.
+//!
+//! [imbl]: https://docs.rs/imbl
+//! [imbl-sized-chunks]: https://docs.rs/imbl-sized-chunks
+//! [Pool]: ../struct.Pool.html
+//! [PoolRef::default]: ../struct.PoolRef.html#method.default
+//! [PoolRef::make_mut]: ../struct.PoolRef.html#method.make_mut
+//! [PoolDefault]: ../trait.PoolDefault.html
+//! [PoolClone]: ../trait.PoolClone.html
+//! [PoolDefaultImpl]: ../trait.PoolDefaultImpl.html
+
+#![cfg(not(feature = "default_impl"))]
+
+use imbl_sized_chunks::bitmap::{Bits, BitsImpl};
+use imbl_sized_chunks::{Chunk, SparseChunk};
+
+use crate::PoolDefaultImpl;
+
+impl<A, const N: usize> PoolDefaultImpl for Chunk<A, N> {}
+
+impl<A, const N: usize> PoolDefaultImpl for SparseChunk<A, N> where BitsImpl<N>: Bits {}
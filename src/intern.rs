@@ -0,0 +1,132 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::collections::HashSet;
+use std::fmt::{Debug, Error, Formatter};
+use std::hash::Hash;
+
+use crate::pool::Pool;
+use crate::ref_handle::PoolRef;
+
+/// An interner for pool allocated values.
+///
+/// Interning a value returns a [`PoolRef`][PoolRef] to an equal value if one
+/// has already been interned, and only allocates a new one otherwise, so that
+/// equal values end up sharing memory. This is also known as hash consing,
+/// and is particularly effective for deduplicating the nodes of persistent
+/// data structures.
+///
+/// The interner keeps a reference to every value it's interned. Values which
+/// are no longer referenced by anything but the interner are forgotten, and
+/// their memory returned to their pool, whenever the interner's table has
+/// doubled in size since the last time it checked, which keeps the table from
+/// growing without bounds while costing only amortised constant time per
+/// interned value. You can also do this at any time using
+/// [`purge()`][purge].
+///
+/// # Examples
+///
+/// ```rust
+/// # use refpool::{Pool, PoolIntern, PoolRef};
+/// let pool: Pool<String> = Pool::new(16);
+/// let mut interner = PoolIntern::new();
+/// let hello = interner.intern(&pool, "hello".to_string());
+/// let hello_again = interner.intern(&pool, "hello".to_string());
+/// assert!(PoolRef::ptr_eq(&hello, &hello_again));
+///
+/// drop(hello);
+/// drop(hello_again);
+/// assert_eq!(1, interner.purge());
+/// assert!(interner.is_empty());
+/// ```
+///
+/// [PoolRef]: struct.PoolRef.html
+/// [purge]: #method.purge
+pub struct PoolIntern<A> {
+    table: HashSet<PoolRef<A>>,
+    next_purge: usize,
+}
+
+/// The smallest table size at which the interner purges dead values.
+const MIN_PURGE_SIZE: usize = 64;
+
+impl<A> PoolIntern<A>
+where
+    A: Hash + Eq,
+{
+    /// Construct an empty interner.
+    pub fn new() -> Self {
+        PoolIntern {
+            table: HashSet::new(),
+            next_purge: MIN_PURGE_SIZE,
+        }
+    }
+
+    /// Intern a value.
+    ///
+    /// If an equal value has already been interned, this returns a reference
+    /// to it and drops `value`. Otherwise, `value` is moved into memory from
+    /// `pool` and remembered for later.
+    #[cfg_attr(feature = "trace-alloc", track_caller)]
+    pub fn intern(&mut self, pool: &Pool<A>, value: A) -> PoolRef<A> {
+        if let Some(existing) = self.table.get(&value) {
+            return existing.clone();
+        }
+        if self.table.len() >= self.next_purge {
+            self.purge();
+        }
+        let interned = PoolRef::new(pool, value);
+        self.table.insert(interned.clone());
+        interned
+    }
+
+    /// Look up an interned value equal to `value`.
+    pub fn get(&self, value: &A) -> Option<PoolRef<A>> {
+        self.table.get(value).cloned()
+    }
+
+    /// Get the number of interned values.
+    pub fn len(&self) -> usize {
+        self.table.len()
+    }
+
+    /// Test whether the interner is empty.
+    pub fn is_empty(&self) -> bool {
+        self.table.is_empty()
+    }
+
+    /// Forget every interned value which isn't referenced by anything other
+    /// than the interner, returning its memory to its pool.
+    ///
+    /// Returns the number of values forgotten.
+    pub fn purge(&mut self) -> usize {
+        let before = self.table.len();
+        self.table.retain(|value| PoolRef::strong_count(value) > 1);
+        self.next_purge = (self.table.len() * 2).max(MIN_PURGE_SIZE);
+        before - self.table.len()
+    }
+
+    /// Forget every interned value.
+    pub fn clear(&mut self) {
+        self.table.clear()
+    }
+}
+
+impl<A> Default for PoolIntern<A>
+where
+    A: Hash + Eq,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<A> Debug for PoolIntern<A>
+where
+    A: Debug,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        f.debug_set().entries(self.table.iter()).finish()
+    }
+}
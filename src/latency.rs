@@ -0,0 +1,89 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+// With the `disable-pool` feature flag, there's no real pool to make use of
+// the crate internals here, only the fake pool which ignores them.
+#![cfg_attr(feature = "disable-pool", allow(dead_code))]
+
+use std::convert::TryFrom;
+use std::time::Instant;
+
+use hdrhistogram::Histogram;
+
+/// Latency histograms for a [`Pool`][Pool], in nanoseconds.
+///
+/// Latencies are only recorded for pools constructed with
+/// [`PoolBuilder::latency(true)`][PoolBuilder::latency], and can be retrieved
+/// using [`Pool::latency()`][Pool::latency].
+///
+/// An allocation is timed from the moment it asks the pool for memory until
+/// it has a chunk with its header set up, which includes the trip to the
+/// system allocator for a miss, but not initialising the value. A return is
+/// timed from the moment the last handle to a value is dropped until its
+/// memory is back in the pool or with the system allocator, which includes
+/// dropping the value.
+///
+/// # Examples
+///
+#[cfg_attr(feature = "disable-pool", doc = "```ignore")]
+#[cfg_attr(not(feature = "disable-pool"), doc = "```rust")]
+/// # use refpool::{Pool, PoolRef};
+/// let pool: Pool<usize> = Pool::builder().max_size(16).latency(true).build();
+/// drop(PoolRef::new(&pool, 1));
+/// drop(PoolRef::new(&pool, 2));
+/// let latency = pool.latency().unwrap();
+/// assert_eq!(1, latency.misses.len());
+/// assert_eq!(1, latency.hits.len());
+/// assert_eq!(2, latency.returns.len());
+/// println!("p99 hit latency: {}ns", latency.hits.value_at_quantile(0.99));
+/// ```
+///
+/// [Pool]: struct.Pool.html
+/// [Pool::latency]: struct.Pool.html#method.latency
+/// [PoolBuilder::latency]: struct.PoolBuilder.html#method.latency
+#[derive(Clone, Debug)]
+pub struct PoolLatency {
+    /// The latencies of allocations which were served from memory in the
+    /// pool.
+    pub hits: Histogram<u64>,
+    /// The latencies of allocations which had to fall back to the system
+    /// allocator because the pool was empty.
+    pub misses: Histogram<u64>,
+    /// The latencies of returning values' memory, whether to the pool or to
+    /// the system allocator.
+    pub returns: Histogram<u64>,
+}
+
+impl PoolLatency {
+    pub(crate) fn new() -> Self {
+        PoolLatency {
+            hits: histogram(),
+            misses: histogram(),
+            returns: histogram(),
+        }
+    }
+
+    pub(crate) fn record_pop(&mut self, start: Instant, hit: bool) {
+        let histogram = if hit {
+            &mut self.hits
+        } else {
+            &mut self.misses
+        };
+        histogram.saturating_record(nanos_since(start));
+    }
+
+    pub(crate) fn record_return(&mut self, start: Instant) {
+        self.returns.saturating_record(nanos_since(start));
+    }
+}
+
+/// Construct an auto-resizing histogram with three significant figures of
+/// precision.
+fn histogram() -> Histogram<u64> {
+    Histogram::new(3).expect("three significant figures is a valid histogram precision")
+}
+
+fn nanos_since(start: Instant) -> u64 {
+    u64::try_from(start.elapsed().as_nanos()).unwrap_or(u64::MAX)
+}
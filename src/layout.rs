@@ -0,0 +1,60 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+/// A marker trait for types which can share a pool with type `B`.
+///
+/// Implementing `SameLayout<B>` for `A` lets you cast a `Pool<A>` to a
+/// `Pool<B>` with [`Pool::cast_checked()`][Pool::cast_checked], which checks
+/// the layouts at compile time instead of at runtime like
+/// [`Pool::cast()`][Pool::cast].
+///
+/// It's implemented for every type with itself, and for the pairs of
+/// primitive types of the same size, such as `u64` and `i64`.
+///
+/// # Safety
+///
+/// `A` and `B` must have the same size, and the alignment of `A` must be at
+/// least that of `B`.
+///
+/// # Examples
+///
+/// ```rust
+/// # use refpool::{Pool, PoolRef, SameLayout};
+/// struct Meters(f64);
+/// struct Feet(f64);
+/// unsafe impl SameLayout<Feet> for Meters {}
+///
+/// let meters: Pool<Meters> = Pool::new(16);
+/// let feet: Pool<Feet> = meters.cast_checked();
+/// let height = PoolRef::new(&feet, Feet(6.0));
+/// # assert_eq!(6.0, height.0);
+/// ```
+///
+/// [Pool::cast]: struct.Pool.html#method.cast
+/// [Pool::cast_checked]: struct.Pool.html#method.cast_checked
+pub unsafe trait SameLayout<B> {}
+
+unsafe impl<A> SameLayout<A> for A {}
+
+macro_rules! same_layout {
+    ($($a:ty, $b:ty;)*) => {
+        $(
+            unsafe impl SameLayout<$b> for $a {}
+            unsafe impl SameLayout<$a> for $b {}
+        )*
+    };
+}
+
+same_layout! {
+    u8, i8;
+    u16, i16;
+    u32, i32;
+    u64, i64;
+    u128, i128;
+    usize, isize;
+    u32, f32;
+    i32, f32;
+    u64, f64;
+    i64, f64;
+}
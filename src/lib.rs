@@ -22,12 +22,21 @@
 //! [`PoolClone`][PoolClone] for most primitive types and a good selection of
 //! `std`'s data types, and you can easily provide default implementations for
 //! your own types by implementing the marker trait
-//! [`PoolDefaultImpl`][PoolDefaultImpl]. You can also implement your own if you
-//! have data structures whose memory doesn't need to be fully intitialised at
-//! construction time, which can give you a slight performance boost. (This
+//! [`PoolDefaultImpl`][PoolDefaultImpl], or by listing them in the
+//! [`impl_pool_default!`][impl_pool_default] macro. You can also implement
+//! your own if you have data structures whose memory doesn't need to be fully
+//! intitialised at construction time, which can give you a slight performance
+//! boost. (This
 //! optimisation is why [`PoolDefault`][PoolDefault] and
 //! [`PoolClone`][PoolClone] exist as distinct traits, otherwise
-//! [`Default`][Default] and [`Clone`][Clone] would have sufficed.)
+//! [`Default`][Default] and [`Clone`][Clone] would have sufficed.) The
+//! [`pool_default_zeroed!`][pool_default_zeroed],
+//! [`pool_default_fields!`][pool_default_fields],
+//! [`pool_default_variant!`][pool_default_variant] and
+//! [`pool_clone_fields!`][pool_clone_fields] macros will write the unsafe
+//! parts of these implementations for you. (They aren't available with the
+//! `default_impl` feature flag, where your implementations would conflict
+//! with the blanket ones.)
 //!
 //! # Usage
 //!
@@ -53,19 +62,30 @@
 //!   * [`PoolBox`][PoolBox] and [`PoolRef`][PoolRef] do not implement
 //!     [`Default`][Default], because you need a
 //!     [`Pool`][Pool] argument to construct an instance. Use
-//!     [`PoolRef::default(pool)`][PoolRef::default].
+//!     [`PoolRef::default(pool)`][PoolRef::default]. The exception is with the
+//!     `thread-local` feature flag, where they implement [`Default`][Default]
+//!     and [`From`][From] using the current thread's pool.
 //!   * There's currently no equivalent to [`Weak`][Weak] for [`PoolRef`][PoolRef].
+//!   * Values of zero sized types still take up a chunk of pool memory. A
+//!     [`PoolRef`][PoolRef] needs somewhere to keep its reference count, just
+//!     like an [`Rc`][Rc] does, and both [`PoolRef`][PoolRef] and
+//!     [`PoolBox`][PoolBox] need to know which pool to go back to, so a
+//!     `Pool<()>` recycles these headers like any other pool would. If you
+//!     want zero sized values which don't allocate at all, use a
+//!     [`ThinPool`][ThinPool].
 //!   * Experimental APIs are not implemented.
 //!
 //! # Thread Safety
 //!
 //! [`Pool`][Pool] is strictly thread local, ie. it does not
 //! implement [`Sync`][Sync] and it will fail in appalling ways if you still
-//! somehow manage to access it from two different threads. There is no
-//! equivalent of [`Arc`][Arc] because adding thread safety to the pool turns
-//! out to degrade performance sufficiently that the pool is no longer providing
-//! a significant performance benefit even with the slowest system allocators
-//! you're likely to come across in the wild (by which I mean Windows).
+//! somehow manage to access it from two different threads. In debug builds,
+//! it'll at least panic if it's used from a thread other than the one which
+//! created it. There is no equivalent of [`Arc`][Arc] because adding thread
+//! safety to the pool turns out to degrade performance sufficiently that the
+//! pool is no longer providing a significant performance benefit even with the
+//! slowest system allocators you're likely to come across in the wild (by
+//! which I mean Windows).
 //!
 //! # Performance
 //!
@@ -85,7 +105,8 @@
 
 //! # Example
 //!
-//! ```rust
+#![cfg_attr(feature = "disable-pool", doc = "```ignore")]
+#![cfg_attr(not(feature = "disable-pool"), doc = "```rust")]
 //! # use refpool::{Pool, PoolRef};
 //! // Create a pool of `usize` with a max size of 1 (for argument's sake).
 //! let mut pool: Pool<usize> = Pool::new(1);
@@ -111,28 +132,199 @@
 //!
 //! # Feature Flags
 //!
-//! There's one feature flag available, `default_impl`, which requires a nightly
-//! rustc because it leans on the `min_specialization` language feature, which
-//! removes the `PoolDefaultImpl` trait and instead provides a `default`
-//! overridable implementation for `PoolClone` and `PoolDefault` for any type
-//! that implements `Clone` and `Default`. `PoolDefaultImpl` is an unfortunate
-//! hack to get around the current absence of specialisation in stable rustc.
+//! The `default_impl` feature flag requires a nightly rustc because it leans
+//! on the `min_specialization` language feature, which removes the
+//! `PoolDefaultImpl` trait and instead provides a `default` overridable
+//! implementation for `PoolClone` and `PoolDefault` for any type that
+//! implements `Clone` and `Default`. `PoolDefaultImpl` is an unfortunate hack
+//! to get around the current absence of specialisation in stable rustc.
+//!
+//! The `fn_traits` feature flag also requires a nightly rustc, and
+//! implements [`Fn`][Fn], [`FnMut`][FnMut] and [`FnOnce`][FnOnce] for
+//! [`PoolBox`][PoolBox] when its contents implement them, so that a boxed
+//! closure can be called directly, like a [`Box`][Box]ed one. Calling a
+//! `PoolBox<F: FnOnce>` moves the closure out and hands its memory back to
+//! the pool first.
+//!
+//! The `imbl-compat` feature flag provides `PoolDefault` and `PoolClone` for
+//! the chunk types from [`imbl-sized-chunks`][imbl-sized-chunks], which are the
+//! building blocks of [`imbl`][imbl]'s data structures. See the
+//! [`imbl_compat`][imbl_compat] module.
+//!
+//! The `rayon` feature flag provides
+//! [`ThreadLocalPools`][ThreadLocalPools], which gives each worker thread of a
+//! [`rayon`][rayon] thread pool its own pool, for data-parallel construction of
+//! pooled values.
+//!
+//! The `bytemuck` feature flag provides typed views into the buffers of a
+//! [`BytePool`][BytePool], through [`ByteBuf::view()`][ByteBuf::view] and
+//! [`ByteBuf::view_mut()`][ByteBuf::view_mut], for any type implementing
+//! [`bytemuck::Pod`][Pod]. It also adds
+//! [`PoolRef::zeroed()`][PoolRef::zeroed] and
+//! [`PoolBox::zeroed()`][PoolBox::zeroed], which construct zeroed values of
+//! any type implementing [`bytemuck::Zeroable`][Zeroable].
+//!
+//! The `debug-aliasing` feature flag keeps track of raw pointers handed out
+//! by [`PoolBox::into_raw()`][PoolBox::into_raw],
+//! [`PoolBox::as_non_null()`][PoolBox::as_non_null] and
+//! [`PoolRef::into_raw()`][PoolRef::into_raw], and panics if you ask for a
+//! mutable reference to a value while a raw pointer to it is still considered
+//! live. A pointer stops being live when it's passed to `from_raw()`, or, for
+//! [`PoolBox::as_non_null()`][PoolBox::as_non_null], to
+//! [`PoolBox::release_raw()`][PoolBox::release_raw]. This is meant for
+//! catching aliasing bugs in tests of code which passes pooled values through
+//! FFI or other unsafe code, and costs an extra word per value.
+//!
+//! The `debug-dump` feature flag adds
+//! [`Pool::debug_dump()`][Pool::debug_dump], which lists the addresses of the
+//! free memory in a pool, for checking whether a raw pointer points into
+//! memory which has gone back to the pool.
+//!
+//! The `debug-leaks` feature flag makes every pool keep a registry of the
+//! values allocated from it which are still alive, along with a backtrace of
+//! where they were allocated if `RUST_BACKTRACE` is set, which you can inspect
+//! using [`Pool::debug_report()`][Pool::debug_report]. This feature flag
+//! requires rustc 1.65 or later.
+//!
+//! The `trace-alloc` feature flag makes every pool count the allocations made
+//! from each location in your code, and how many of those had to fall back to
+//! the system allocator, which you can inspect using
+//! [`Pool::alloc_sites()`][Pool::alloc_sites] to find out which code paths
+//! are defeating the pool.
+//!
+//! The `thread-local` feature flag adds a per-thread registry of pools, one
+//! for each type, which you can set up with
+//! [`Pool::thread_local()`][Pool::thread_local], and constructors like
+//! [`PoolRef::new_tl()`][PoolRef::new_tl] and the [`pooled!`][pooled] macro,
+//! which allocate from the current thread's pool without needing a pool
+//! argument. It also implements [`Default`][Default] and [`From`][From] for
+//! [`PoolRef`][PoolRef] and [`PoolBox`][PoolBox] using the current thread's
+//! pool.
+//!
+//! The `serde` feature flag implements [`Serialize`][Serialize] for
+//! [`PoolRef`][PoolRef] and [`PoolBox`][PoolBox], and provides
+//! [`PoolSeed`][PoolSeed], a [`DeserializeSeed`][DeserializeSeed] which
+//! deserialises values into pooled memory.
+//!
+//! The `rkyv` feature flag adds
+//! [`PoolRef::from_archived()`][PoolRef::from_archived] and
+//! [`PoolBox::from_archived()`][PoolBox::from_archived], which deserialise
+//! archived [`rkyv`][rkyv] values directly into pooled memory, for loading
+//! large snapshots quickly.
+//!
+//! The `arbitrary` feature flag implements [`Arbitrary`][Arbitrary] for
+//! [`PoolRef`][PoolRef] and [`PoolBox`][PoolBox], so that fuzzing harnesses
+//! can generate structures containing pooled values. The values are
+//! allocated from the current thread's pool for their type, which is set up
+//! with a maximum size of 256 if there isn't one already.
+//!
+//! The `proptest` feature flag provides
+//! [`pool_ref_strategy()`][pool_ref_strategy], a [`proptest`][proptest]
+//! strategy which generates [`PoolRef`][PoolRef]s, sometimes sharing
+//! allocations between them, for property tests over pooled data
+//! structures.
+//!
+//! The `stable_deref_trait` feature flag implements
+//! [`StableDeref`][StableDeref] for [`PoolRef`][PoolRef] and
+//! [`PoolBox`][PoolBox], and [`CloneStableDeref`][CloneStableDeref] for
+//! [`PoolRef`][PoolRef], so that pooled values can own self-referential
+//! borrows through crates like `ouroboros`, `owning_ref` or `yoke`.
+//!
+//! The `small-count` feature flag stores the reference count of a pooled
+//! value in a `u32` rather than a `usize`, which saves a word per value when
+//! the value's size leaves room for the count before the next word boundary,
+//! as it does for any type of four bytes or less, at the cost of limiting the
+//! number of references to a value to `u32::MAX`, or to 65535 for a
+//! [`PoolRefCell`][PoolRefCell]. This matters mostly for workloads with
+//! millions of small pooled nodes.
+//!
+//! The `locked-memory` feature flag adds
+//! [`PoolBuilder::lock_memory()`][PoolBuilder::lock_memory], which locks a
+//! pool's memory into RAM with `mlock` on Unix or `VirtualLock` on Windows,
+//! so that secrets held in pooled values are never swapped out to disk. This
+//! feature flag requires rustc 1.65 or later.
+//!
+//! The `hdrhistogram` feature flag adds
+//! [`PoolBuilder::latency()`][PoolBuilder::latency], which makes a pool time
+//! every allocation and return, and collect the timings in
+//! [`hdrhistogram`][hdrhistogram] histograms, which you can get from
+//! [`Pool::latency()`][Pool::latency] to measure the tail latency the pool is
+//! saving you.
+//!
+//! The `sanitize` feature flag requires a nightly rustc, and poisons the
+//! memory of values returned to a pool while it sits in the pool, when the
+//! crate is built with AddressSanitizer (`-Zsanitizer=address`), so that
+//! AddressSanitizer reports code which uses a value after it's gone back to
+//! the pool. It does nothing for builds without AddressSanitizer.
+//!
+//! The `valgrind` feature flag does the same for Valgrind's Memcheck, marking
+//! the memory of values in a pool as inaccessible using Valgrind client
+//! requests, which do nothing when the program isn't running under Valgrind.
+//! It's only supported on `x86_64`, where it requires rustc 1.59 or later, and
+//! does nothing on other platforms.
+//!
+//! The `disable-pool` feature flag replaces [`Pool`][Pool],
+//! [`PoolRef`][PoolRef], [`PoolRefBorrow`][PoolRefBorrow] and
+//! [`PoolBox`][PoolBox], along with the builder, guards, arenas and other
+//! types you get from a pool, with the zero cost wrappers
+//! from the [`fakepool`][fakepool] module, which turns off pooling for every
+//! call site at once, for platforms where it doesn't pay off, or for
+//! comparing performance with and without pooling. The real pool isn't
+//! compiled at all with this feature flag, so types which only make sense
+//! with a real pool, like [`PoolCow`][PoolCow], [`PoolIntern`][PoolIntern]
+//! and [`PoolRefCell`][PoolRefCell], aren't available, and neither are the
+//! implementations of other crates' traits for the pool's types.
 //!
 //! [Pool]: struct.Pool.html
+//! [Pool::debug_report]: struct.Pool.html#method.debug_report
+//! [Pool::alloc_sites]: struct.Pool.html#method.alloc_sites
+//! [Pool::debug_dump]: struct.Pool.html#method.debug_dump
+//! [Pool::thread_local]: struct.Pool.html#method.thread_local
+//! [PoolRef::new_tl]: struct.PoolRef.html#method.new_tl
+//! [pooled]: macro.pooled.html
 //! [PoolBox]: struct.PoolBox.html
 //! [PoolBox::default]: struct.PoolBox.html#method.default
+//! [PoolBox::into_raw]: struct.PoolBox.html#method.into_raw
+//! [PoolBox::as_non_null]: struct.PoolBox.html#method.as_non_null
+//! [PoolBox::release_raw]: struct.PoolBox.html#method.release_raw
+//! [PoolRef::into_raw]: struct.PoolRef.html#method.into_raw
 //! [PoolRef]: struct.PoolRef.html
 //! [PoolRef::new]: struct.PoolRef.html#method.new
+//! [PoolRefBorrow]: struct.PoolRefBorrow.html
+//! [PoolRefCell]: struct.PoolRefCell.html
+//! [PoolCow]: struct.PoolCow.html
+//! [PoolIntern]: struct.PoolIntern.html
+//! [PoolBuilder::lock_memory]: struct.PoolBuilder.html#method.lock_memory
+//! [PoolBuilder::latency]: struct.PoolBuilder.html#method.latency
+//! [Pool::latency]: struct.Pool.html#method.latency
+//! [hdrhistogram]: https://docs.rs/hdrhistogram
 //! [PoolRef::default]: struct.PoolRef.html#method.default
 //! [PoolRef::make_mut]: struct.PoolRef.html#method.make_mut
+//! [BytePool]: type.BytePool.html
+//! [ByteBuf::view]: struct.ByteBuf.html#method.view
+//! [ByteBuf::view_mut]: struct.ByteBuf.html#method.view_mut
+//! [Pod]: https://docs.rs/bytemuck/latest/bytemuck/trait.Pod.html
+//! [Zeroable]: https://docs.rs/bytemuck/latest/bytemuck/trait.Zeroable.html
+//! [PoolRef::zeroed]: struct.PoolRef.html#method.zeroed
+//! [PoolBox::zeroed]: struct.PoolBox.html#method.zeroed
 //! [PoolDefault]: trait.PoolDefault.html
 //! [PoolClone]: trait.PoolClone.html
+//! [pool_default_zeroed]: macro.pool_default_zeroed.html
+//! [impl_pool_default]: macro.impl_pool_default.html
+//! [pool_default_fields]: macro.pool_default_fields.html
+//! [pool_default_variant]: macro.pool_default_variant.html
+//! [pool_clone_fields]: macro.pool_clone_fields.html
 //! [PoolDefaultImpl]: trait.PoolDefaultImpl.html
 //! [PoolSync]: struct.PoolSync.html
+//! [ThinPool]: struct.ThinPool.html
 //! [Box]: https://doc.rust-lang.org/stable/std/boxed/struct.Box.html
+//! [Fn]: https://doc.rust-lang.org/std/ops/trait.Fn.html
+//! [FnMut]: https://doc.rust-lang.org/std/ops/trait.FnMut.html
+//! [FnOnce]: https://doc.rust-lang.org/std/ops/trait.FnOnce.html
 //! [Box::from_raw]: https://doc.rust-lang.org/stable/std/boxed/struct.Box.html#method.from_raw
 //! [Box::into_raw]: https://doc.rust-lang.org/stable/std/boxed/struct.Box.html#method.into_raw
 //! [Default]: https://doc.rust-lang.org/std/default/trait.Default.html
+//! [From]: https://doc.rust-lang.org/std/convert/trait.From.html
 //! [Clone]: https://doc.rust-lang.org/std/clone/trait.Clone.html
 //! [Arc]: https://doc.rust-lang.org/std/sync/struct.Arc.html
 //! [Rc]: https://doc.rust-lang.org/std/rc/struct.Rc.html
@@ -141,6 +333,23 @@
 //! [Sized]: https://doc.rust-lang.org/std/marker/trait.Sized.html
 //! [Sync]: https://doc.rust-lang.org/std/marker/trait.Sync.html
 //! [Chunk]: https://docs.rs/sized-chunks/*/sized_chunks/sized_chunk/struct.Chunk.html
+//! [imbl]: https://docs.rs/imbl
+//! [imbl-sized-chunks]: https://docs.rs/imbl-sized-chunks
+//! [imbl_compat]: imbl_compat/index.html
+//! [fakepool]: fakepool/index.html
+//! [PoolSeed]: struct.PoolSeed.html
+//! [Serialize]: https://docs.rs/serde/1/serde/ser/trait.Serialize.html
+//! [DeserializeSeed]: https://docs.rs/serde/1/serde/de/trait.DeserializeSeed.html
+//! [rayon]: https://docs.rs/rayon
+//! [rkyv]: https://docs.rs/rkyv
+//! [Arbitrary]: https://docs.rs/arbitrary/1/arbitrary/trait.Arbitrary.html
+//! [proptest]: https://docs.rs/proptest
+//! [StableDeref]: https://docs.rs/stable_deref_trait/1/stable_deref_trait/trait.StableDeref.html
+//! [CloneStableDeref]: https://docs.rs/stable_deref_trait/1/stable_deref_trait/trait.CloneStableDeref.html
+//! [pool_ref_strategy]: fn.pool_ref_strategy.html
+//! [PoolRef::from_archived]: struct.PoolRef.html#method.from_archived
+//! [PoolBox::from_archived]: struct.PoolBox.html#method.from_archived
+//! [ThreadLocalPools]: struct.ThreadLocalPools.html
 
 #![forbid(rust_2018_idioms)]
 #![deny(nonstandard_style)]
@@ -151,23 +360,176 @@
     missing_doc_code_examples
 )]
 #![cfg_attr(feature = "default_impl", feature(min_specialization))]
+#![cfg_attr(
+    feature = "fn_traits",
+    feature(fn_traits, unboxed_closures, tuple_trait)
+)]
+#![cfg_attr(
+    all(feature = "sanitize", not(feature = "disable-pool")),
+    feature(cfg_sanitize)
+)]
 
 use std::mem::MaybeUninit;
 
+#[cfg(not(feature = "disable-pool"))]
+mod adaptive;
+mod aligned;
+mod api;
+#[cfg(all(feature = "arbitrary", not(feature = "disable-pool")))]
+mod arbitrary_impl;
+#[cfg(not(feature = "disable-pool"))]
+mod arena;
+#[cfg(not(feature = "disable-pool"))]
 mod box_handle;
+#[cfg(not(feature = "disable-pool"))]
+mod builder;
+mod by_ptr;
+mod byte_pool;
+#[cfg(not(feature = "disable-pool"))]
+mod chunk_guard;
+#[cfg(not(feature = "disable-pool"))]
 mod counter;
+#[cfg(not(feature = "disable-pool"))]
+mod cow;
+// `debug-leaks` needs `std::backtrace`, so it's got a higher minimum rustc
+// version than the rest of the crate.
+#[cfg(feature = "debug-leaks")]
+#[clippy::msrv = "1.65"]
+mod debug_report;
+mod error;
+mod group;
+#[cfg(not(feature = "disable-pool"))]
+mod idle;
+#[cfg(not(feature = "disable-pool"))]
+mod intern;
+#[cfg(feature = "hdrhistogram")]
+mod latency;
+mod layout;
+#[cfg(all(feature = "locked-memory", not(feature = "disable-pool")))]
+mod locked;
+#[cfg(not(feature = "default_impl"))]
+mod macros;
+mod overflow;
+#[cfg(not(feature = "disable-pool"))]
 mod pointer;
+#[cfg(not(feature = "disable-pool"))]
 mod pool;
+#[cfg(not(feature = "disable-pool"))]
+mod pool_guard;
+#[cfg(all(feature = "proptest", not(feature = "disable-pool")))]
+mod proptest_impl;
+mod recycle;
+#[cfg(not(feature = "disable-pool"))]
+mod ref_borrow;
+#[cfg(not(feature = "disable-pool"))]
+mod ref_cell;
+#[cfg(not(feature = "disable-pool"))]
 mod ref_handle;
+#[cfg(not(feature = "disable-pool"))]
+mod ref_map;
+#[cfg(not(feature = "disable-pool"))]
 mod refbox;
-mod stack;
+#[cfg(all(feature = "rkyv", not(feature = "disable-pool")))]
+mod rkyv_impl;
+#[cfg(all(feature = "sanitize", not(feature = "disable-pool")))]
+mod sanitize;
+#[cfg(not(feature = "disable-pool"))]
+mod send_token;
+#[cfg(all(feature = "serde", not(feature = "disable-pool")))]
+mod serde_impl;
+mod slice;
+#[cfg(not(feature = "disable-pool"))]
+mod speculate;
+#[cfg(all(feature = "stable_deref_trait", not(feature = "disable-pool")))]
+mod stable_deref_impl;
+mod stats;
+mod storage;
+mod string;
+mod thin;
+#[cfg(all(
+    any(feature = "thread-local", feature = "arbitrary"),
+    not(feature = "disable-pool")
+))]
+mod thread_local;
+#[cfg(feature = "trace-alloc")]
+mod trace;
+#[cfg(not(feature = "disable-pool"))]
 mod types;
+#[cfg(all(feature = "valgrind", not(feature = "disable-pool")))]
+mod valgrind;
 
 pub mod fakepool;
+pub mod metrics;
 
+#[cfg(feature = "imbl-compat")]
+pub mod imbl_compat;
+
+#[cfg(all(feature = "rayon", not(feature = "disable-pool")))]
+mod thread_pools;
+#[cfg(all(feature = "rayon", not(feature = "disable-pool")))]
+pub use self::thread_pools::ThreadLocalPools;
+
+pub use self::aligned::{Aligned, CacheAligned, CacheAlignedPool};
+pub use self::api::PoolApi;
+#[cfg(not(feature = "disable-pool"))]
+pub use self::arena::{ArenaBox, PoolArena};
+#[cfg(not(feature = "disable-pool"))]
 pub use self::box_handle::PoolBox;
+#[cfg(not(feature = "disable-pool"))]
+pub use self::builder::PoolBuilder;
+pub use self::by_ptr::ByPtr;
+pub use self::byte_pool::{Align, Alignment, ByteBuf, BytePool};
+#[cfg(not(feature = "disable-pool"))]
+pub use self::chunk_guard::ChunkGuard;
+#[cfg(not(feature = "disable-pool"))]
+pub use self::cow::PoolCow;
+#[cfg(feature = "debug-leaks")]
+pub use self::debug_report::{DebugReport, LiveAllocation};
+pub use self::error::{AllocError, CapacityError, LayoutError};
+pub use self::group::PoolGroup;
+#[cfg(not(feature = "disable-pool"))]
+pub use self::intern::PoolIntern;
+#[cfg(feature = "hdrhistogram")]
+pub use self::latency::PoolLatency;
+pub use self::layout::SameLayout;
+#[cfg(not(feature = "default_impl"))]
+#[doc(hidden)]
+pub use self::macros::{clone_field_uninit, default_field_uninit};
+pub use self::overflow::OverflowPolicy;
+#[cfg(not(feature = "disable-pool"))]
 pub use self::pool::Pool;
+#[cfg(not(feature = "disable-pool"))]
+pub use self::pool_guard::PoolGuard;
+#[cfg(all(feature = "proptest", not(feature = "disable-pool")))]
+pub use self::proptest_impl::{pool_ref_strategy, PoolRefStrategy, PoolRefValueTree};
+pub use self::recycle::PoolRecycle;
+#[cfg(not(feature = "disable-pool"))]
+pub use self::ref_borrow::PoolRefBorrow;
+#[cfg(not(feature = "disable-pool"))]
+pub use self::ref_cell::{PoolRefCell, PoolRefCellRef, PoolRefCellRefMut};
+#[cfg(not(feature = "disable-pool"))]
 pub use self::ref_handle::PoolRef;
+#[cfg(not(feature = "disable-pool"))]
+pub use self::ref_map::PoolRefMap;
+#[cfg(not(feature = "disable-pool"))]
+pub use self::send_token::PoolSendToken;
+#[cfg(all(feature = "serde", not(feature = "disable-pool")))]
+pub use self::serde_impl::{PoolBoxSeed, PoolSeed};
+pub use self::slice::{PoolSlice, SlicePool};
+#[cfg(not(feature = "disable-pool"))]
+pub use self::speculate::SpeculativePool;
+pub use self::stats::PoolStats;
+pub use self::storage::{FixedStack, PoolStorage, RingBuffer, VecQueue, VecStack};
+pub use self::string::PoolString;
+pub use self::thin::{PoolBoxThin, ThinPool};
+#[cfg(feature = "trace-alloc")]
+pub use self::trace::AllocSite;
+
+#[cfg(feature = "disable-pool")]
+pub use self::fakepool::{
+    ArenaBox, ChunkGuard, Pool, PoolArena, PoolBox, PoolBuilder, PoolGuard, PoolRef, PoolRefBorrow,
+    PoolRefMap, PoolSendToken, SpeculativePool,
+};
 
 #[cfg(not(feature = "default_impl"))]
 mod std_types;
@@ -201,6 +563,33 @@ pub trait PoolClone: Clone {
     unsafe fn clone_uninit(&self, target: &mut MaybeUninit<Self>);
 }
 
+/// A trait for fallibly cloning a value into a `MaybeUninit<Self>`.
+///
+/// This is for types whose clones can fail, such as values holding an OS
+/// resource that has to be duplicated, where [`PoolClone`][PoolClone] would
+/// force you to panic. It's used by [`PoolRef::try_cloned()`][try_cloned]
+/// and [`PoolRef::try_make_mut()`][try_make_mut].
+///
+/// [PoolClone]: trait.PoolClone.html
+/// [try_cloned]: struct.PoolRef.html#method.try_cloned
+/// [try_make_mut]: struct.PoolRef.html#method.try_make_mut
+pub trait PoolTryClone: Sized {
+    /// The error produced when a clone fails.
+    type Error;
+
+    /// Attempt to clone an instance of `Self` into an uninitialised instance
+    /// of `Self`.
+    ///
+    /// # Safety
+    ///
+    /// You should assume that the object as passed to you contains
+    /// uninitialised memory. If you return `Ok`, you must leave it in a fully
+    /// initialised state, as expected by `MaybeUninit::assume_init()`. If you
+    /// return an error, it will be treated as uninitialised, so anything
+    /// you've written into it will not be dropped.
+    unsafe fn try_clone_uninit(&self, target: &mut MaybeUninit<Self>) -> Result<(), Self::Error>;
+}
+
 #[cfg(feature = "default_impl")]
 impl<A> PoolDefault for A
 where
@@ -221,9 +610,78 @@ where
     }
 }
 
-#[cfg(test)]
+/// Defaulting an `Option` only has to write its discriminant, or its niche
+/// if it has one, so we write `None` directly rather than going through
+/// `Default::default()`, which might copy the whole uninitialised payload.
+impl<A> PoolDefault for Option<A> {
+    unsafe fn default_uninit(target: &mut MaybeUninit<Self>) {
+        target.as_mut_ptr().write(None);
+    }
+}
+
+/// Handles own their values as far as the drop checker is concerned, so a
+/// value borrowing from something which goes out of scope before its handle
+/// is rejected:
+///
+/// ```compile_fail
+/// # use refpool::{Pool, PoolRef};
+/// # use std::cell::Cell;
+/// struct DropTest<'a>(&'a Cell<usize>);
+/// impl<'a> Drop for DropTest<'a> {
+///     fn drop(&mut self) {
+///         self.0.set(self.0.get() + 1);
+///     }
+/// }
+///
+/// let handle;
+/// let counter = Cell::new(0);
+/// let pool: Pool<DropTest<'_>> = Pool::new(1);
+/// handle = PoolRef::new(&pool, DropTest(&counter));
+/// ```
+///
+/// ```compile_fail
+/// # use refpool::{Pool, PoolBox};
+/// # use std::cell::Cell;
+/// struct DropTest<'a>(&'a Cell<usize>);
+/// impl<'a> Drop for DropTest<'a> {
+///     fn drop(&mut self) {
+///         self.0.set(self.0.get() + 1);
+///     }
+/// }
+///
+/// let handle;
+/// let counter = Cell::new(0);
+/// let pool: Pool<DropTest<'_>> = Pool::new(1);
+/// handle = PoolBox::new(&pool, DropTest(&counter));
+/// ```
+///
+/// Pools are invariant over their value type, because they keep a recycler
+/// which takes `&mut A`, and so are handles, which keep a reference to their
+/// pool, so neither a pool nor a handle for long lived values can pass for one
+/// for short lived values:
+///
+/// ```compile_fail
+/// # use refpool::Pool;
+/// fn shorten<'a>(pool: Pool<&'static str>) -> Pool<&'a str> {
+///     pool
+/// }
+/// ```
+///
+/// ```compile_fail
+/// # use refpool::PoolRef;
+/// fn shorten<'a>(handle: PoolRef<&'static str>) -> PoolRef<&'a str> {
+///     handle
+/// }
+/// ```
+#[cfg(all(doctest, not(feature = "disable-pool")))]
+pub struct DropCheck;
+
+#[cfg(all(test, not(feature = "disable-pool")))]
 mod test {
     use super::*;
+    use crate::box_handle::PoolBox;
+    use crate::pool::Pool;
+    use crate::ref_handle::PoolRef;
     use std::sync::atomic::{AtomicUsize, Ordering};
 
     struct DropTest<'a> {
@@ -243,6 +701,50 @@ mod test {
         }
     }
 
+    /// The system allocator, counting the allocations made on each thread, so
+    /// tests can tell how many allocations an operation makes.
+    struct CountingAlloc;
+
+    std::thread_local! {
+        static ALLOCATIONS: std::cell::Cell<usize> = std::cell::Cell::new(0);
+    }
+
+    unsafe impl std::alloc::GlobalAlloc for CountingAlloc {
+        unsafe fn alloc(&self, layout: std::alloc::Layout) -> *mut u8 {
+            let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+            std::alloc::System.alloc(layout)
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: std::alloc::Layout) {
+            std::alloc::System.dealloc(ptr, layout)
+        }
+    }
+
+    #[global_allocator]
+    static ALLOCATOR: CountingAlloc = CountingAlloc;
+
+    fn count_allocations<A>(f: impl FnOnce() -> A) -> (usize, A) {
+        let before = ALLOCATIONS.with(|count| count.get());
+        let result = f();
+        (ALLOCATIONS.with(|count| count.get()) - before, result)
+    }
+
+    #[test]
+    fn fixed_pool_is_a_single_allocation() {
+        let (allocations, pool) = count_allocations(Pool::<usize>::fixed::<64>);
+        assert_eq!(1, allocations);
+        let chunks = pool.refs_from_iter(0..64);
+        drop(chunks);
+        assert_eq!(64, pool.get_pool_size());
+        // debug-leaks captures a backtrace for every live value.
+        #[cfg(not(feature = "debug-leaks"))]
+        assert_eq!(0, count_allocations(|| PoolRef::new(&pool, 1)).0);
+        drop(pool);
+
+        let (allocations, _pool) = count_allocations(|| Pool::<usize>::new(64));
+        assert_eq!(2, allocations);
+    }
+
     fn fill_drop(pool_size: usize, alloc_size: usize) {
         let counter = AtomicUsize::new(0);
         let pool: Pool<DropTest<'_>> = Pool::new(pool_size);
@@ -327,6 +829,92 @@ mod test {
         assert_eq!(1337, unwrapped);
     }
 
+    #[test]
+    fn zero_on_return() {
+        let pool: Pool<usize> = Pool::builder().max_size(1).zero_on_return(true).build();
+        let ptr = PoolRef::into_raw(PoolRef::new(&pool, 0xdead_beef));
+        drop(unsafe { PoolRef::from_raw(ptr) });
+        assert_eq!(1, pool.get_pool_size());
+        // The chunk is still owned by the pool, so it's safe to peek into it.
+        assert_eq!(0, unsafe { *ptr });
+    }
+
+    #[test]
+    fn overflow_callback() {
+        let calls = std::rc::Rc::new(std::cell::Cell::new(0));
+        let counter = calls.clone();
+        let pool: Pool<usize> = Pool::builder()
+            .max_size(2)
+            .overflow(OverflowPolicy::Callback(Box::new(move |size| {
+                counter.set(counter.get() + 1);
+                size + 1
+            })))
+            .build();
+        let refs: Vec<_> = (0..5).map(|i| PoolRef::new(&pool, i)).collect();
+        drop(refs);
+        assert_eq!(3, calls.get());
+        assert_eq!(5, pool.get_max_size());
+        assert_eq!(5, pool.get_pool_size());
+    }
+
+    #[test]
+    fn reentrant_overflow_callback() {
+        let stash: std::rc::Rc<std::cell::RefCell<Vec<PoolRef<usize>>>> = Default::default();
+        let held = stash.clone();
+        let pool: Pool<usize> = Pool::builder()
+            .max_size(1)
+            .overflow(OverflowPolicy::Callback(Box::new(move |size| {
+                // Returning this to the full pool calls the policy again.
+                held.borrow_mut().clear();
+                size + 1
+            })))
+            .build();
+        let first = PoolRef::new(&pool, 1);
+        let second = PoolRef::new(&pool, 2);
+        stash.borrow_mut().push(PoolRef::new(&pool, 3));
+        drop(first);
+        drop(second);
+        assert!(stash.borrow().is_empty());
+        assert_eq!(2, pool.get_max_size());
+        assert_eq!(2, pool.get_pool_size());
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn thread_local_pools_catch_escaping_handles() {
+        // Escaping handles abort the process, so we have to run the escape in
+        // a child process, which is this test running itself again.
+        if std::env::var_os("REFPOOL_ESCAPE_HANDLES").is_some() {
+            let pools: ThreadLocalPools<usize> = ThreadLocalPools::new(16);
+            rayon::scope(|_| pools.with_pool(|pool| std::mem::forget(PoolRef::new(pool, 1))));
+            return;
+        }
+        let output = std::process::Command::new(std::env::current_exe().unwrap())
+            .args([
+                "--exact",
+                "test::thread_local_pools_catch_escaping_handles",
+                "--nocapture",
+            ])
+            .env("REFPOOL_ESCAPE_HANDLES", "1")
+            .output()
+            .unwrap();
+        assert!(!output.status.success());
+        assert!(String::from_utf8_lossy(&output.stderr).contains("must not outlive the closure"));
+    }
+
+    #[test]
+    fn dropped_chunk_guards_return_to_pool() {
+        let pool: Pool<Vec<usize>> = Pool::new(2);
+        let chunks: Vec<_> = (0..4).map(|_| pool.reserve()).collect();
+        assert_eq!(4, pool.handle_count() - 1);
+        drop(chunks);
+        assert_eq!(2, pool.get_pool_size());
+        assert_eq!(1, pool.handle_count());
+        let value = pool.reserve().commit_default();
+        assert!(value.is_empty());
+        assert_eq!(1, pool.get_pool_size());
+    }
+
     #[test]
     fn option_of_ref_size_equals_ref_size() {
         use std::mem::size_of;
@@ -336,4 +924,1210 @@ mod test {
         );
         assert_eq!(size_of::<Pool<usize>>(), size_of::<Option<Pool<usize>>>());
     }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    fn pool_used_from_another_thread() {
+        struct Smuggled(Pool<usize>);
+        unsafe impl Send for Smuggled {}
+
+        let pool: Pool<usize> = Pool::new(16);
+        let smuggled = Smuggled(pool.clone());
+        let result = std::thread::spawn(move || {
+            let smuggled = smuggled;
+            PoolRef::new(&smuggled.0, 1);
+        })
+        .join();
+        let message = result.unwrap_err();
+        assert!(message
+            .downcast_ref::<String>()
+            .unwrap()
+            .contains("pools must only be used from the thread that created them"));
+    }
+
+    #[test]
+    fn recycling_pool_drops_values_once() {
+        static DROPS: AtomicUsize = AtomicUsize::new(0);
+
+        #[derive(Default)]
+        struct Tracked(Vec<u8>);
+
+        impl Drop for Tracked {
+            fn drop(&mut self) {
+                DROPS.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        #[cfg(not(feature = "default_impl"))]
+        impl PoolDefaultImpl for Tracked {}
+
+        impl PoolRecycle for Tracked {
+            fn reset(&mut self) {
+                self.0.clear();
+            }
+        }
+
+        let pool: Pool<Tracked> = Pool::builder().max_size(4).recycle().build();
+        pool.fill();
+        let recycled = PoolRef::default(&pool);
+        let replaced = PoolRef::new(&pool, Tracked(vec![1]));
+        assert_eq!(1, DROPS.load(Ordering::Relaxed));
+        let guard = pool.reserve();
+        assert_eq!(2, DROPS.load(Ordering::Relaxed));
+        drop(guard);
+        drop(recycled);
+        drop(replaced);
+        assert_eq!(2, DROPS.load(Ordering::Relaxed));
+        assert_eq!(4, pool.get_pool_size());
+        drop(pool);
+        assert_eq!(6, DROPS.load(Ordering::Relaxed));
+    }
+
+    #[test]
+    fn strings_only_see_the_bytes_they_wrote() {
+        let pool: SlicePool<u8> = SlicePool::new(64, 1);
+        drop(PoolString::try_from_str(&pool, "hello world").unwrap());
+        let mut string = PoolString::new(&pool);
+        assert_eq!(0, pool.get_pool_size());
+        assert_eq!("", string.as_str());
+        assert_eq!(64, string.capacity());
+        string.push_str("hi");
+        let copy = string.clone();
+        assert_eq!("hi", copy.as_str());
+        assert_eq!(Some('i'), string.pop());
+        assert_eq!("hi", copy.as_str());
+    }
+
+    #[test]
+    fn idle_spike_is_trimmed_after_one_period() {
+        let pool: Pool<usize> = Pool::builder()
+            .max_size(64)
+            .idle_timeout(std::time::Duration::from_millis(50))
+            .build();
+        let spike = pool.refs_from_iter(0..64);
+        drop(spike);
+        assert_eq!(64, pool.get_pool_size());
+        std::thread::sleep(std::time::Duration::from_millis(60));
+        let value = PoolRef::new(&pool, 0);
+        assert_eq!(0, pool.get_pool_size());
+        drop(value);
+        assert_eq!(1, pool.get_pool_size());
+    }
+
+    #[test]
+    fn group_trims_recycled_values_holding_sibling_handles() {
+        // A recycled value which keeps hold of a value from another pool.
+        #[derive(Default)]
+        struct Holder(Option<PoolRef<u64>>);
+
+        #[cfg(not(feature = "default_impl"))]
+        impl PoolDefaultImpl for Holder {}
+
+        impl PoolRecycle for Holder {
+            fn reset(&mut self) {}
+        }
+
+        let holder_size = std::alloc::Layout::new::<crate::refbox::RefBox<Holder>>().size();
+        let group = PoolGroup::trimming(holder_size * 2);
+        let holders: Pool<Holder> = Pool::builder().max_size(2).recycle().group(&group).build();
+        let numbers: Pool<u64> = Pool::builder().max_size(2).group(&group).build();
+        let held: Vec<_> = (0..2)
+            .map(|number| {
+                let mut holder = PoolBox::default(&holders);
+                holder.0 = Some(PoolRef::new(&numbers, number));
+                holder
+            })
+            .collect();
+        drop(held);
+        assert_eq!(2, holders.get_pool_size());
+        assert_eq!(holder_size * 2, group.retained_bytes());
+        // Making room for this trims a holder, whose number goes back to its
+        // pool, which makes room for itself in the group while it's trimming.
+        drop(PoolRef::new(&numbers, 2));
+        assert!(holders.get_pool_size() < 2);
+        assert!(numbers.get_pool_size() > 0);
+        assert!(group.retained_bytes() <= group.max_bytes());
+    }
+
+    #[test]
+    fn interner_forgets_dead_values() {
+        let pool: Pool<usize> = Pool::new(1024);
+        let mut interner = PoolIntern::new();
+        let kept: Vec<_> = (0..10).map(|i| interner.intern(&pool, i)).collect();
+        for i in 10..10_000 {
+            interner.intern(&pool, i);
+            assert!(interner.len() <= 128);
+        }
+        for (i, value) in kept.iter().enumerate() {
+            assert!(PoolRef::ptr_eq(value, &interner.get(&i).unwrap()));
+        }
+    }
+
+    #[test]
+    fn transfer_between_recycling_pools() {
+        let vecs: Pool<Vec<u8>> = Pool::builder().max_size(4).recycle().build();
+        let strings: Pool<String> = Pool::builder().max_size(4).recycle().build();
+        let held: Vec<_> = (0..4).map(|_| PoolBox::default(&vecs)).collect();
+        drop(held);
+        assert_eq!(4, vecs.transfer_to(&strings, 8));
+        assert_eq!(0, vecs.get_pool_size());
+        assert_eq!(4, strings.get_pool_size());
+        let string = PoolBox::default(&strings);
+        assert!(string.is_empty());
+    }
+
+    #[test]
+    fn cross_type_equality() {
+        let pool: Pool<usize> = Pool::new(4);
+        let number = PoolRef::new(&pool, 42);
+        let boxed = PoolBox::new(&pool, 42);
+        assert_eq!(number, 42);
+        assert_eq!(boxed, 42);
+        assert_eq!(number, boxed);
+        assert_eq!(boxed, number);
+        assert_ne!(number, 43);
+        assert_ne!(PoolBox::new(&pool, 43), number);
+    }
+
+    #[test]
+    fn boxed_iterator() {
+        let pool: Pool<std::ops::Range<usize>> = Pool::new(1);
+        let mut numbers = PoolBox::new(&pool, 0..5);
+        assert_eq!(5, numbers.len());
+        assert_eq!(Some(4), numbers.next_back());
+        assert_eq!(vec![0, 1, 2, 3], numbers.collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn boxed_future() {
+        use std::future::Future;
+        use std::pin::Pin;
+        use std::task::{Context, Poll, RawWaker, RawWakerVTable, Waker};
+
+        fn noop_raw_waker() -> RawWaker {
+            fn clone(_: *const ()) -> RawWaker {
+                noop_raw_waker()
+            }
+            fn noop(_: *const ()) {}
+            static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, noop, noop, noop);
+            RawWaker::new(std::ptr::null(), &VTABLE)
+        }
+        let waker = unsafe { Waker::from_raw(noop_raw_waker()) };
+        let mut cx = Context::from_waker(&waker);
+
+        let pool = Pool::new(1);
+        let mut ready = PoolBox::new(&pool, std::future::ready(1337));
+        assert_eq!(Poll::Ready(1337), Pin::new(&mut ready).poll(&mut cx));
+
+        let pool = Pool::new(1);
+        let mut task = PoolBox::into_pin(PoolBox::new(&pool, async { 31337 }));
+        assert_eq!(Poll::Ready(31337), task.as_mut().poll(&mut cx));
+    }
+
+    #[test]
+    fn pinned_boxes() {
+        use std::marker::PhantomPinned;
+        use std::pin::Pin;
+
+        struct Node {
+            value: usize,
+            _pinned: PhantomPinned,
+        }
+
+        fn bump(node: Pin<&mut Node>) {
+            unsafe { node.get_unchecked_mut().value += 1 };
+        }
+
+        let pool: Pool<Node> = Pool::new(1);
+        let node = PoolBox::new(
+            &pool,
+            Node {
+                value: 1,
+                _pinned: PhantomPinned,
+            },
+        );
+        let address: *const Node = &*node;
+        let mut node: Pin<PoolBox<Node>> = node.into();
+        bump(node.as_mut());
+        bump(node.as_mut());
+        assert_eq!(3, node.value);
+        assert!(std::ptr::eq(address, &*node));
+
+        let pool: Pool<usize> = Pool::new(1);
+        let number = PoolBox::pin(&pool, 1337);
+        let number = Pin::into_inner(number);
+        assert_eq!(1337, PoolBox::into_inner(number));
+    }
+
+    #[test]
+    fn boxed_io() {
+        use std::io::{BufRead, Cursor, Read, Seek, SeekFrom, Write};
+        let pool: Pool<Cursor<Vec<u8>>> = Pool::new(1);
+        let mut buffer = PoolBox::new(&pool, Cursor::new(Vec::new()));
+        writeln!(buffer, "hello").unwrap();
+        buffer.write_all(b"world").unwrap();
+        buffer.seek(SeekFrom::Start(0)).unwrap();
+        let mut line = String::new();
+        buffer.read_line(&mut line).unwrap();
+        assert_eq!("hello\n", line);
+        let mut rest = String::new();
+        buffer.read_to_string(&mut rest).unwrap();
+        assert_eq!("world", rest);
+    }
+
+    #[test]
+    fn pooled_errors() {
+        use std::error::Error;
+        use std::fmt::{Display, Formatter};
+
+        #[derive(Debug)]
+        struct Outer(std::fmt::Error);
+        impl Display for Outer {
+            fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+                write!(f, "outer error")
+            }
+        }
+        impl Error for Outer {
+            fn source(&self) -> Option<&(dyn Error + 'static)> {
+                Some(&self.0)
+            }
+        }
+
+        let pool: Pool<Outer> = Pool::new(2);
+        let error = PoolRef::new(&pool, Outer(std::fmt::Error));
+        assert_eq!(
+            std::fmt::Error.to_string(),
+            error.source().unwrap().to_string()
+        );
+        let boxed: Box<dyn Error> = PoolBox::new(&pool, Outer(std::fmt::Error)).into();
+        assert_eq!("outer error", boxed.to_string());
+        assert!(boxed.source().is_some());
+    }
+
+    #[cfg(feature = "fn_traits")]
+    #[test]
+    fn boxed_closures() {
+        fn counter() -> impl FnMut(usize) -> usize {
+            let mut total = 0;
+            move |step| {
+                total += step;
+                total
+            }
+        }
+        fn take(words: String) -> impl FnOnce() -> String {
+            move || words
+        }
+
+        let pool = Pool::new(1);
+        let mut count = PoolBox::new(&pool, counter());
+        assert_eq!(2, count(2));
+        assert_eq!(5, count(3));
+
+        let pool = Pool::new(1);
+        let consume = PoolBox::new(&pool, take(String::from("hello")));
+        assert_eq!("hello", consume());
+        assert_eq!(1, pool.get_pool_size());
+    }
+
+    #[test]
+    fn boxed_hasher_and_writer() {
+        use std::collections::hash_map::DefaultHasher;
+        use std::fmt::Write;
+        use std::hash::{Hash, Hasher};
+
+        let pool: Pool<DefaultHasher> = Pool::new(1);
+        let mut hasher = PoolBox::new(&pool, DefaultHasher::new());
+        let mut expected = DefaultHasher::new();
+        (1337u64, "hello").hash(&mut hasher);
+        (1337u64, "hello").hash(&mut expected);
+        assert_eq!(expected.finish(), hasher.finish());
+
+        let pool: Pool<String> = Pool::new(1);
+        let mut text = PoolBox::default(&pool);
+        write!(text, "{}-{}", 1, 2).unwrap();
+        text.write_char('!').unwrap();
+        assert_eq!("1-2!", *text);
+    }
+
+    #[test]
+    fn handles_cross_unwind_boundaries() {
+        use std::cell::Cell;
+        use std::panic::{RefUnwindSafe, UnwindSafe};
+
+        fn assert_unwind_safe<T: UnwindSafe + RefUnwindSafe + Unpin>() {}
+        fn assert_unpin<T: Unpin>() {}
+        assert_unwind_safe::<Pool<usize>>();
+        assert_unwind_safe::<PoolRef<usize>>();
+        assert_unwind_safe::<PoolBox<usize>>();
+        assert_unwind_safe::<Pool<Cell<usize>>>();
+        assert_unpin::<PoolRef<std::marker::PhantomPinned>>();
+        assert_unpin::<PoolBox<std::marker::PhantomPinned>>();
+
+        let pool: Pool<usize> = Pool::new(1);
+        let number = PoolRef::new(&pool, 1337);
+        let result = std::panic::catch_unwind(|| {
+            assert_eq!(1337, *number);
+            panic!("unwinding");
+        });
+        assert!(result.is_err());
+        drop(number);
+        assert_eq!(1, pool.get_pool_size());
+    }
+
+    #[cfg(not(feature = "default_impl"))]
+    #[test]
+    fn impl_macros() {
+        #[derive(Clone, Default, PartialEq, Debug)]
+        struct Record {
+            name: String,
+            tags: Vec<u32>,
+            parent: Option<usize>,
+        }
+        pool_default_fields!(Record { name, tags, parent });
+        pool_clone_fields!(Record { name, tags, parent });
+
+        #[derive(Clone, Default, PartialEq, Debug)]
+        struct Pair(u64, [u8; 16]);
+        pool_default_zeroed!(Pair);
+
+        #[derive(Default, PartialEq, Debug)]
+        enum Slot {
+            #[default]
+            Empty,
+            Full(Box<usize>),
+        }
+        pool_default_variant!(Slot::Empty);
+
+        let pool: Pool<Record> = Pool::new(4);
+        let mut record = PoolRef::new(
+            &pool,
+            Record {
+                name: "foo".to_string(),
+                tags: vec![1, 2, 3],
+                parent: Some(5),
+            },
+        );
+        let original = record.clone();
+        PoolRef::make_mut(&pool, &mut record).tags.push(4);
+        assert_eq!(vec![1, 2, 3], original.tags);
+        assert_eq!(vec![1, 2, 3, 4], record.tags);
+        assert_eq!(original.name, record.name);
+        drop(record);
+        assert_eq!(Record::default(), *PoolBox::default(&pool));
+
+        let pool: Pool<Pair> = Pool::new(4);
+        drop(PoolBox::new(&pool, Pair(u64::MAX, [0xff; 16])));
+        assert_eq!(Pair::default(), *PoolBox::default(&pool));
+
+        let pool: Pool<Slot> = Pool::new(4);
+        drop(PoolBox::new(&pool, Slot::Full(Box::new(5))));
+        assert_eq!(Slot::Empty, *PoolBox::default(&pool));
+    }
+
+    #[test]
+    fn option_defaults_to_none() {
+        let pool: Pool<Option<[u8; 4096]>> = Pool::new(4);
+        drop(PoolBox::new(&pool, Some([0xff; 4096])));
+        assert_eq!(None, *PoolBox::default(&pool));
+
+        let pool: Pool<(Option<usize>, u8)> = Pool::new(4);
+        assert_eq!((None, 0), *PoolRef::default(&pool));
+        let mut pair = PoolRef::new(&pool, (Some(1), 2));
+        let other = pair.clone();
+        PoolRef::make_mut(&pool, &mut pair).0 = None;
+        assert_eq!((Some(1), 2), *other);
+    }
+
+    #[cfg(all(
+        feature = "small-count",
+        not(feature = "debug-aliasing"),
+        target_pointer_width = "64"
+    ))]
+    #[test]
+    fn small_count_shrinks_header() {
+        use crate::refbox::RefBox;
+        assert_eq!(16, std::mem::size_of::<RefBox<u32>>());
+        assert_eq!(16, std::mem::size_of::<RefBox<[u16; 2]>>());
+    }
+
+    #[cfg(feature = "small-count")]
+    #[test]
+    #[should_panic(expected = "reference count overflow")]
+    fn small_count_overflow() {
+        let pool: Pool<usize> = Pool::new(1);
+        let number = PoolRef::new(&pool, 1);
+        unsafe { (*number.handle.as_ptr()).count = u32::MAX };
+        std::mem::forget(number.clone());
+    }
+
+    #[test]
+    fn thin_boxes() {
+        let counter = AtomicUsize::new(0);
+        let pool: ThinPool<DropTest<'_>> = ThinPool::new(2);
+        assert_eq!(0, pool.get_pool_size());
+        let first = PoolBoxThin::new(&pool, DropTest::new(&counter));
+        let second = PoolBoxThin::new(&pool, DropTest::new(&counter));
+        let third = PoolBoxThin::new(&pool, DropTest::new(&counter));
+        assert_eq!(3, counter.load(Ordering::SeqCst));
+        PoolBoxThin::drop_into(first, &pool);
+        let value = PoolBoxThin::into_inner(second, &pool);
+        assert_eq!(2, counter.load(Ordering::SeqCst));
+        assert_eq!(2, pool.get_pool_size());
+        drop(third);
+        drop(value);
+        assert_eq!(0, counter.load(Ordering::SeqCst));
+        assert_eq!(2, pool.get_pool_size());
+
+        let pool: ThinPool<u8> = ThinPool::new(1);
+        PoolBoxThin::drop_into(PoolBoxThin::default(&pool), &pool);
+        assert_eq!(1, pool.get_pool_size());
+
+        let pool: ThinPool<()> = ThinPool::new(1);
+        pool.fill();
+        PoolBoxThin::drop_into(PoolBoxThin::new(&pool, ()), &pool);
+        drop(PoolBoxThin::new(&pool, ()));
+        assert_eq!(0, pool.get_pool_size());
+    }
+
+    #[test]
+    fn values_hold_one_pool_handle() {
+        let pool: Pool<usize> = Pool::new(1);
+        assert_eq!(1, pool.handle_count());
+        let first = PoolRef::new(&pool, 1);
+        let second = PoolBox::new(&pool, 2);
+        assert_eq!(3, pool.handle_count());
+        drop(first);
+        assert_eq!(2, pool.handle_count());
+        drop(second);
+        assert_eq!(1, pool.handle_count());
+        assert_eq!(1, pool.get_pool_size());
+
+        let pool: Pool<Vec<usize>> = Pool::builder().max_size(1).recycle().build();
+        let list = PoolRef::new(&pool, vec![1, 2, 3]);
+        assert_eq!(2, pool.handle_count());
+        drop(list);
+        assert_eq!(1, pool.handle_count());
+    }
+
+    #[test]
+    fn aligned_chunks() {
+        let pool: Pool<Aligned<u8, 64>> = Pool::new(2);
+        pool.fill();
+        let first = PoolRef::new(&pool, Aligned::new(1));
+        let second = PoolBox::default(&pool);
+        let first_addr = &**first as *const u8 as usize;
+        let second_addr = &**second as *const u8 as usize;
+        assert_eq!(0, first_addr % 64);
+        assert_eq!(0, second_addr % 64);
+        assert!(first_addr.max(second_addr) - first_addr.min(second_addr) >= 64);
+        assert_eq!(1, **first.clone());
+        assert_eq!(0, **second);
+
+        assert!(std::mem::align_of::<CacheAligned<u8>>() >= 64);
+        assert_eq!(
+            std::mem::size_of::<CacheAligned<u8>>(),
+            std::mem::align_of::<CacheAligned<u8>>()
+        );
+    }
+
+    #[test]
+    fn over_aligned_pools_on_every_allocation_path() {
+        let pool: Pool<u16> = Pool::with_alignment(4, 64);
+        let aligned = |value: &u16| value as *const u16 as usize % 64 == 0;
+        let values = vec![
+            PoolRef::try_alloc(&pool, 1).unwrap(),
+            PoolRef::cloned(&pool, &PoolRef::new(&pool, 2)),
+            PoolRef::new_with(&pool, |slot| slot.write(3)),
+            PoolRef::write(PoolRef::new_uninit(&pool), 4),
+        ];
+        assert!(values.iter().all(|value| aligned(value)));
+        PoolRef::drop_batch(values);
+        assert_eq!(4, pool.get_pool_size());
+        let value = PoolRef::try_new(&pool, 5).unwrap();
+        assert!(aligned(&value));
+        assert!(aligned(&PoolRef::try_default(&pool).unwrap()));
+        let other: Pool<u16> = Pool::with_alignment(1, 64);
+        let value = PoolRef::migrate(value, &other).unwrap();
+        assert!(aligned(&value));
+        assert_eq!(5, PoolRef::try_unwrap(value).unwrap());
+        let boxed = PoolBox::try_alloc(&pool, 6).unwrap();
+        assert!(aligned(&boxed) && aligned(&PoolBox::try_new(&pool, 7).unwrap()));
+        assert_eq!(6, PoolBox::into_inner(boxed));
+        let cell = PoolRefCell::new(&pool, 8);
+        assert!(aligned(&cell.borrow()));
+        drop(cell);
+        drop(pool.reserve());
+        assert_eq!(3, pool.get_pool_size());
+    }
+
+    #[test]
+    fn over_aligned_pools() {
+        let pool: Pool<u16> = Pool::with_alignment(2, 64);
+        assert_eq!(64, pool.alignment());
+        assert!(pool.retained_bytes() == 0 && pool.max_retained_bytes() == 128);
+        pool.fill();
+        let values: Vec<_> = (0..4).map(|i| PoolRef::new(&pool, i)).collect();
+        for value in &values {
+            assert_eq!(0, &**value as *const u16 as usize % 64);
+        }
+        drop(values);
+        assert_eq!(2, pool.get_pool_size());
+        let boxed = PoolBox::new(&pool, 1);
+        assert_eq!(0, &*boxed as *const u16 as usize % 64);
+        assert_eq!(1, PoolRef::try_unwrap(PoolRef::new(&pool, 1)).unwrap());
+
+        let signed: Pool<i16> = pool.cast();
+        assert_eq!(64, signed.alignment());
+        let number = PoolRef::new(&signed, -1);
+        assert_eq!(0, &*number as *const i16 as usize % 64);
+        let unaligned: Pool<u16> = Pool::new(2);
+        assert!(std::panic::catch_unwind(|| pool.transfer_to(&unaligned, 1)).is_err());
+
+        #[repr(align(64))]
+        struct Wide([u8; 64]);
+        let bytes: Pool<[u8; 64]> = Pool::new(1);
+        assert!(bytes.try_cast::<Wide>().is_err());
+        let bytes: Pool<[u8; 64]> = Pool::with_alignment(1, 64);
+        let wide = PoolRef::new(&bytes.cast::<Wide>(), Wide([1; 64]));
+        assert_eq!(1, wide.0[63]);
+
+        // A clone which panics hands its chunk back to the pool rather than
+        // freeing it with the wrong layout.
+        #[cfg(not(feature = "default_impl"))]
+        {
+            #[derive(Clone)]
+            struct Grumpy;
+            impl PoolClone for Grumpy {
+                unsafe fn clone_uninit(&self, _target: &mut MaybeUninit<Self>) {
+                    panic!("won't clone");
+                }
+            }
+            let pool: Pool<Grumpy> = Pool::with_alignment(1, 128);
+            let grumpy = PoolRef::new(&pool, Grumpy);
+            let result = std::panic::catch_unwind(|| PoolRef::cloned(&pool, &grumpy));
+            assert!(result.is_err());
+            assert_eq!(1, pool.get_pool_size());
+            assert_eq!(2, pool.handle_count());
+        }
+
+        let null: Pool<u8> = Pool::with_alignment(0, 32);
+        assert_eq!(32, null.alignment());
+        let byte = PoolBox::new(&null, 1);
+        assert_eq!(0, &*byte as *const u8 as usize % 32);
+    }
+
+    #[cfg(feature = "locked-memory")]
+    #[test]
+    fn locked_memory() {
+        let pool: Pool<[u8; 32]> = Pool::builder().max_size(1).lock_memory(true).build();
+        assert!(pool.alignment() >= 4096);
+        assert_eq!(0, pool.max_retained_bytes() % pool.alignment());
+        let key = match PoolBox::try_alloc(&pool, [0x55; 32]) {
+            Ok(key) => key,
+            // Not allowed to lock any memory.
+            Err(_) => return,
+        };
+        assert_eq!(0, &*key as *const [u8; 32] as usize % pool.alignment());
+        let other = PoolBox::try_alloc(&pool, [0xaa; 32]).unwrap();
+        drop(key);
+        drop(other);
+        assert_eq!(1, pool.get_pool_size());
+
+        let null: Pool<u8> = Pool::builder().lock_memory(true).build();
+        assert!(null.alignment() >= 4096);
+    }
+
+    #[cfg(feature = "locked-memory")]
+    #[test]
+    fn no_transfers_between_locked_and_unlocked_pools() {
+        let locked: Pool<[u8; 32]> = Pool::builder().max_size(2).lock_memory(true).build();
+        let unlocked: Pool<[u8; 32]> = Pool::with_alignment(2, locked.alignment());
+        assert_eq!(locked.chunk_layout(), unlocked.chunk_layout());
+        match PoolBox::try_alloc(&locked, [0x55; 32]) {
+            Ok(key) => drop(key),
+            // Not allowed to lock any memory.
+            Err(_) => return,
+        }
+        drop(PoolBox::new(&unlocked, [0xaa; 32]));
+        assert_eq!(0, locked.transfer_to(&unlocked, 1));
+        assert_eq!(0, unlocked.transfer_to(&locked, 1));
+        assert_eq!(1, locked.get_pool_size());
+        assert_eq!(1, unlocked.get_pool_size());
+    }
+
+    #[test]
+    fn zero_sized_values() {
+        static DROPS: AtomicUsize = AtomicUsize::new(0);
+
+        #[derive(Clone, Default)]
+        struct Marker;
+
+        impl Drop for Marker {
+            fn drop(&mut self) {
+                DROPS.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        #[cfg(not(feature = "default_impl"))]
+        impl PoolDefaultImpl for Marker {}
+
+        let pool: Pool<Marker> = Pool::new(2);
+        let mut first = PoolRef::new(&pool, Marker);
+        let second = first.clone();
+        assert!(PoolRef::ptr_eq(&first, &second));
+        assert_eq!(2, PoolRef::strong_count(&first));
+        PoolRef::make_mut(&pool, &mut first);
+        assert!(!PoolRef::ptr_eq(&first, &second));
+        assert_eq!(0, DROPS.load(Ordering::Relaxed));
+        drop(first);
+        drop(second);
+        assert_eq!(2, DROPS.load(Ordering::Relaxed));
+        assert_eq!(2, pool.get_pool_size());
+
+        let boxed = PoolBox::new(&pool, Marker);
+        let other = boxed.clone();
+        assert!(!PoolBox::ptr_eq(&boxed, &other));
+        assert_eq!(0, pool.get_pool_size());
+        drop(boxed);
+        drop(other);
+        assert_eq!(4, DROPS.load(Ordering::Relaxed));
+        assert_eq!(2, pool.get_pool_size());
+
+        let units: Pool<()> = Pool::new(1);
+        let unit = PoolRef::new(&units, ());
+        assert_eq!(Ok(()), PoolRef::try_unwrap(unit));
+        drop(PoolRef::new(&units, ()));
+        assert_eq!(1, units.get_pool_size());
+    }
+
+    #[test]
+    fn size_thresholds() {
+        fn build<A>() -> Pool<A> {
+            Pool::builder()
+                .max_size(4)
+                .prefill(true)
+                .bypass_below(48)
+                .discard_above(256)
+                .live_count(true)
+                .build()
+        }
+
+        let small: Pool<u8> = build();
+        assert_eq!(0, small.get_max_size());
+        assert_eq!(None, small.live_count());
+
+        let medium: Pool<[u8; 64]> = build();
+        assert_eq!(4, medium.get_max_size());
+        assert!(medium.is_full());
+
+        let large: Pool<[u8; 1024]> = build();
+        assert_eq!(0, large.get_max_size());
+        assert_eq!(0, large.get_pool_size());
+        let value = PoolBox::new(&large, [0; 1024]);
+        assert_eq!(Some(1), large.live_count());
+        drop(value);
+        assert_eq!(Some(0), large.live_count());
+        assert_eq!(0, large.get_pool_size());
+    }
+
+    #[cfg(feature = "hdrhistogram")]
+    #[test]
+    fn latency_histograms() {
+        let pool: Pool<usize> = Pool::builder().max_size(1).latency(true).build();
+        let first = PoolRef::new(&pool, 1);
+        let second = PoolBox::try_alloc(&pool, 2).unwrap();
+        drop(first);
+        drop(second);
+        let third = PoolRef::new(&pool, 3);
+        assert!(PoolRef::try_unwrap(third).is_ok());
+        let latency = pool.latency().unwrap();
+        assert_eq!(1, latency.hits.len());
+        assert_eq!(2, latency.misses.len());
+        assert_eq!(2, latency.returns.len());
+
+        let untimed: Pool<usize> = Pool::new(1);
+        drop(PoolRef::new(&untimed, 1));
+        assert!(untimed.latency().is_none());
+    }
+
+    #[cfg(not(feature = "default_impl"))]
+    #[test]
+    fn tuples_forward_to_elements() {
+        static DEFAULTS: AtomicUsize = AtomicUsize::new(0);
+        static CLONES: AtomicUsize = AtomicUsize::new(0);
+
+        #[derive(Clone, Default, PartialEq, Debug)]
+        struct Counted(usize);
+
+        impl PoolDefault for Counted {
+            unsafe fn default_uninit(target: &mut MaybeUninit<Self>) {
+                DEFAULTS.fetch_add(1, Ordering::Relaxed);
+                target.as_mut_ptr().write(Counted(0));
+            }
+        }
+
+        impl PoolClone for Counted {
+            unsafe fn clone_uninit(&self, target: &mut MaybeUninit<Self>) {
+                CLONES.fetch_add(1, Ordering::Relaxed);
+                target.as_mut_ptr().write(self.clone());
+            }
+        }
+
+        let pool: Pool<(Counted, usize)> = Pool::new(4);
+        let mut pair = PoolBox::default(&pool);
+        assert_eq!((Counted(0), 0), *pair);
+        assert_eq!(1, DEFAULTS.load(Ordering::Relaxed));
+        pair.1 = 5;
+        let copy = pair.clone();
+        assert_eq!((Counted(0), 5), *copy);
+        assert_eq!(1, CLONES.load(Ordering::Relaxed));
+
+        type Twelve = (u8, u8, u8, u8, u8, u8, u8, u8, u8, u8, u8, Counted);
+        let pool: Pool<Twelve> = Pool::new(1);
+        let twelve = PoolRef::default(&pool);
+        assert_eq!(Twelve::default(), *twelve);
+        assert_eq!(2, DEFAULTS.load(Ordering::Relaxed));
+    }
+
+    #[cfg(not(feature = "default_impl"))]
+    #[test]
+    fn interior_mutability_types() {
+        use std::cell::{Cell, RefCell};
+        use std::sync::{Mutex, RwLock};
+
+        let pool: Pool<Mutex<Vec<usize>>> = Pool::new(1);
+        let locked = PoolRef::default(&pool);
+        locked.lock().unwrap().push(1);
+        assert_eq!(vec![1], *locked.lock().unwrap());
+
+        let pool: Pool<RwLock<String>> = Pool::new(1);
+        let locked = PoolRef::default(&pool);
+        locked.write().unwrap().push('a');
+        assert_eq!("a", *locked.read().unwrap());
+
+        let pool: Pool<RefCell<Vec<usize>>> = Pool::new(1);
+        let cell = PoolRef::default(&pool);
+        cell.borrow_mut().push(1);
+        let copy = PoolBox::new(&pool, RefCell::default()).clone();
+        assert!(copy.borrow().is_empty());
+        assert_eq!(vec![1], *cell.borrow());
+
+        let pool: Pool<Cell<Option<usize>>> = Pool::new(1);
+        let cell = PoolBox::default(&pool);
+        cell.set(Some(1));
+        assert_eq!(Some(1), cell.clone().get());
+    }
+
+    #[cfg(not(feature = "default_impl"))]
+    #[test]
+    fn connection_state_types() {
+        use std::ffi::{CString, OsString};
+        use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+
+        let pool: Pool<(OsString, CString, Option<SocketAddr>)> = Pool::new(1);
+        let mut state = PoolBox::default(&pool);
+        assert_eq!((OsString::new(), CString::default(), None), *state);
+        state.2 = Some(SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 80));
+        assert_eq!(state.2, state.clone().2);
+
+        let pool: Pool<IpAddr> = Pool::new(1);
+        let address = PoolRef::new(&pool, IpAddr::V4(Ipv4Addr::LOCALHOST));
+        assert_eq!(*address, *PoolRef::cloned(&pool, &address));
+    }
+
+    #[cfg(feature = "debug-dump")]
+    #[test]
+    fn free_list_dump() {
+        fn dump<S: PoolStorage>() {
+            let pool: Pool<usize> = Pool::builder().max_size(4).storage::<S>().build();
+            let values: Vec<_> = (0..3)
+                .map(|i| PoolRef::into_raw(PoolRef::new(&pool, i)))
+                .collect();
+            assert!(pool.debug_dump().is_empty());
+            for value in &values[1..] {
+                drop(unsafe { PoolRef::from_raw(*value) });
+            }
+            let mut free = pool.debug_dump();
+            free.sort();
+            let mut expected: Vec<_> = values[1..].iter().map(|v| v.cast::<()>()).collect();
+            expected.sort();
+            assert_eq!(expected, free);
+            drop(unsafe { PoolRef::from_raw(values[0]) });
+            assert_eq!(3, pool.debug_dump().len());
+        }
+
+        dump::<VecStack>();
+        dump::<VecQueue>();
+        dump::<FixedStack<4>>();
+        dump::<RingBuffer>();
+        assert!(Pool::<usize>::new(0).debug_dump().is_empty());
+    }
+
+    #[test]
+    fn identity_keys() {
+        use std::collections::HashSet;
+
+        let pool: Pool<usize> = Pool::new(4);
+        let first = PoolRef::new(&pool, 1);
+        let twin = PoolRef::new(&pool, 1);
+        assert_eq!(first, twin);
+        assert_ne!(ByPtr(first.clone()), ByPtr(twin.clone()));
+        assert_eq!(ByPtr(first.clone()), ByPtr(first.clone()));
+        let set: HashSet<_> = vec![first.clone(), twin, first]
+            .into_iter()
+            .map(ByPtr::from)
+            .collect();
+        assert_eq!(2, set.len());
+    }
+
+    #[test]
+    fn mapped_refs() {
+        use crate::ref_map::PoolRefMap;
+
+        let counter = AtomicUsize::new(0);
+        let pool: Pool<(String, DropTest<'_>)> = Pool::new(1);
+        let pair = PoolRef::new(&pool, ("Hello Joe".to_string(), DropTest::new(&counter)));
+        let text = PoolRef::map(pair.clone(), |pair| &pair.0);
+        let greeting = PoolRefMap::map(text.clone(), |text| &text[..5]);
+        assert_eq!("Hello", &*greeting);
+        assert_eq!(3, PoolRef::strong_count(&pair));
+        assert!(PoolRef::ptr_eq(&pair, PoolRefMap::owner(&greeting)));
+
+        drop(pair);
+        drop(text);
+        assert_eq!(1, counter.load(Ordering::SeqCst));
+        assert_eq!("Hello", format!("{}", greeting));
+        let pair = PoolRefMap::into_owner(greeting);
+        assert_eq!("Hello Joe", pair.0);
+        drop(pair);
+        assert_eq!(0, counter.load(Ordering::SeqCst));
+        assert_eq!(1, pool.get_pool_size());
+    }
+
+    #[test]
+    fn clone_slices() {
+        let counter = AtomicUsize::new(0);
+        let pool: Pool<DropTest<'_>> = Pool::new(4);
+        let children: Vec<_> = (0..4)
+            .map(|_| PoolRef::new(&pool, DropTest::new(&counter)))
+            .collect();
+        let copied = PoolRef::clone_slice(&children);
+        assert!(children
+            .iter()
+            .zip(&copied)
+            .all(|(left, right)| PoolRef::ptr_eq(left, right)));
+        assert!(children
+            .iter()
+            .all(|child| PoolRef::strong_count(child) == 2));
+        assert!(PoolRef::clone_slice(&children[..0]).is_empty());
+
+        drop(children);
+        assert_eq!(4, counter.load(Ordering::SeqCst));
+        drop(copied);
+        assert_eq!(0, counter.load(Ordering::SeqCst));
+        assert_eq!(4, pool.get_pool_size());
+    }
+
+    #[test]
+    fn arena_frames() {
+        use crate::arena::ArenaBox;
+
+        let counter = AtomicUsize::new(0);
+        let pool: Pool<DropTest<'_>> = Pool::new(4);
+        let mut arena = pool.arena();
+        for _ in 0..3 {
+            arena.alloc(DropTest::new(&counter));
+            let second = ArenaBox::leak(arena.alloc(DropTest::new(&counter)));
+            assert!(std::ptr::eq(second.counter, &counter));
+            assert_eq!(2, counter.load(Ordering::SeqCst));
+            assert_eq!(2, arena.len());
+            arena.reset();
+            assert!(arena.is_empty());
+            assert_eq!(0, counter.load(Ordering::SeqCst));
+            assert_eq!(2, pool.get_pool_size());
+        }
+
+        arena.alloc(DropTest::new(&counter));
+        drop(arena);
+        assert_eq!(0, counter.load(Ordering::SeqCst));
+        assert_eq!(2, pool.get_pool_size());
+    }
+
+    #[test]
+    fn fallible_clones() {
+        struct Handle(Option<usize>);
+
+        impl PoolTryClone for Handle {
+            type Error = &'static str;
+            unsafe fn try_clone_uninit(
+                &self,
+                target: &mut MaybeUninit<Self>,
+            ) -> Result<(), Self::Error> {
+                let id = self.0.ok_or("handle is closed")?;
+                target.as_mut_ptr().write(Handle(Some(id + 1)));
+                Ok(())
+            }
+        }
+
+        let pool: Pool<Handle> = Pool::new(8);
+        let open = PoolRef::new(&pool, Handle(Some(1)));
+        let copy = PoolRef::try_cloned(&pool, &open).unwrap();
+        assert_eq!(Some(2), copy.0);
+
+        let closed = PoolRef::new(&pool, Handle(None));
+        let mut shared = closed.clone();
+        assert_eq!(
+            Err("handle is closed"),
+            PoolRef::try_cloned(&pool, &closed).map(|_| ())
+        );
+        assert!(PoolRef::try_make_mut(&pool, &mut shared).is_err());
+        assert!(PoolRef::ptr_eq(&closed, &shared));
+        assert_eq!(2, PoolRef::strong_count(&closed));
+        assert_eq!(1, pool.get_pool_size());
+
+        let mut unique = PoolRef::new(&pool, Handle(None));
+        PoolRef::try_make_mut(&pool, &mut unique).unwrap().0 = Some(5);
+        let mut shared = copy.clone();
+        PoolRef::try_make_mut(&pool, &mut shared).unwrap();
+        assert_eq!(Some(3), shared.0);
+        assert!(!PoolRef::ptr_eq(&copy, &shared));
+    }
+
+    #[test]
+    fn uninit_box_goes_back_to_pool() {
+        let pool: Pool<Vec<usize>> = Pool::new(1);
+        let empty = PoolBox::new_uninit(&pool);
+        drop(empty);
+        assert_eq!(1, pool.get_pool_size());
+        let mut numbers = PoolBox::new_uninit(&pool);
+        numbers.write(vec![1, 2, 3]);
+        let numbers = unsafe { PoolBox::assume_init(numbers) };
+        assert_eq!(vec![1, 2, 3], *numbers);
+        drop(numbers);
+        assert_eq!(1, pool.get_pool_size());
+    }
+
+    #[test]
+    #[should_panic(expected = "can't initialise a shared reference")]
+    fn write_shared_uninit_ref() {
+        let pool: Pool<usize> = Pool::new(1);
+        let slot = PoolRef::new_uninit(&pool);
+        let _other = slot.clone();
+        PoolRef::write(slot, 1);
+    }
+
+    #[test]
+    fn panicking_initialiser_returns_chunk() {
+        let pool: Pool<usize> = Pool::new(1);
+        let result = std::panic::catch_unwind(|| {
+            PoolRef::new_with(&pool, |_slot| -> &mut usize {
+                panic!("initialiser failed")
+            })
+        });
+        assert!(result.is_err());
+        assert_eq!(1, pool.get_pool_size());
+    }
+
+    #[test]
+    #[should_panic(expected = "must return a reference to the slot it was given")]
+    fn initialiser_returning_another_reference() {
+        let pool: Pool<usize> = Pool::new(1);
+        PoolBox::new_with(&pool, |slot| {
+            slot.write(1);
+            Box::leak(Box::new(2))
+        });
+    }
+
+    #[test]
+    #[should_panic(expected = "which recycles values")]
+    fn uninit_box_from_recycling_pool() {
+        let pool: Pool<Vec<usize>> = Pool::builder().max_size(1).recycle().build();
+        let _empty = PoolBox::new_uninit(&pool);
+    }
+
+    #[test]
+    fn metrics_only_count_live_values_when_asked() {
+        let stats: Pool<usize> = Pool::builder().max_size(16).stats(true).build();
+        let _value = PoolRef::new(&stats, 0);
+        assert_eq!(None, stats.metrics().live);
+        let counted: Pool<usize> = Pool::builder().max_size(16).live_count(true).build();
+        let value = PoolRef::new(&counted, 0);
+        assert_eq!(Some(1), counted.metrics().live);
+        drop(value);
+        assert_eq!(Some(0), counted.metrics().live);
+    }
+
+    #[test]
+    fn rollback_keeps_returns_of_earlier_values() {
+        let pool: Pool<usize> = Pool::builder().max_size(16).stats(true).build();
+        let before = PoolRef::new(&pool, 0);
+        let speculation = pool.speculate();
+        drop(before);
+        drop(PoolRef::new(&speculation, 1));
+        speculation.rollback();
+        let stats = pool.stats().unwrap();
+        assert_eq!(0, stats.hits);
+        assert_eq!(1, stats.misses);
+        assert_eq!(1, stats.returns);
+        assert_eq!(0, stats.discards);
+    }
+
+    #[test]
+    #[should_panic(expected = "1 values allocated during the speculation are still alive")]
+    fn rollback_with_live_values() {
+        let pool: Pool<usize> = Pool::new(16);
+        let before = PoolRef::new(&pool, 0);
+        let speculation = pool.speculate();
+        let _during = PoolRef::new(&speculation, 1);
+        drop(before);
+        speculation.rollback();
+    }
+
+    #[cfg(feature = "debug-aliasing")]
+    #[test]
+    #[should_panic(expected = "while a raw pointer to it is live")]
+    fn debug_aliasing_catches_mutation_through_live_pointer() {
+        let pool: Pool<usize> = Pool::new(1);
+        let mut value = PoolBox::new(&pool, 1);
+        let _ptr = PoolBox::as_non_null(&mut value);
+        *value = 2;
+    }
+
+    #[cfg(feature = "debug-aliasing")]
+    #[test]
+    #[should_panic(expected = "released more times than it was acquired")]
+    fn debug_aliasing_catches_double_from_raw() {
+        let pool: Pool<usize> = Pool::new(1);
+        let ptr = PoolBox::into_raw(PoolBox::new(&pool, 1));
+        let value = unsafe { PoolBox::from_raw(ptr) };
+        std::mem::forget(value);
+        let _value = unsafe { PoolBox::from_raw(ptr) };
+    }
+
+    #[cfg(feature = "arbitrary")]
+    #[test]
+    fn arbitrary_values_come_from_thread_pool() {
+        use arbitrary::{Arbitrary, Unstructured};
+        let mut data = Unstructured::new(&[1, 2, 3, 4]);
+        let values: (PoolRef<u16>, PoolBox<u16>) = Arbitrary::arbitrary(&mut data).unwrap();
+        let pool = crate::thread_local::install::<u16>(1);
+        assert_eq!(0, pool.get_pool_size());
+        drop(values);
+        assert_eq!(2, pool.get_pool_size());
+    }
+
+    #[cfg(feature = "proptest")]
+    #[test]
+    fn proptest_strategy_shares_values() {
+        use proptest::strategy::{Strategy, ValueTree};
+        let pool: Pool<usize> = Pool::new(16);
+        let strategy = pool_ref_strategy(&pool, 0..1000usize).sharing(1.0);
+        let mut runner = proptest::test_runner::TestRunner::default();
+        let first = strategy.new_tree(&mut runner).unwrap().current();
+        for _ in 0..16 {
+            let mut tree = strategy.new_tree(&mut runner).unwrap();
+            assert!(!tree.simplify());
+            assert!(PoolRef::ptr_eq(&first, &tree.current()));
+        }
+    }
+
+    /// Exercise the API shared by the real and the fake pool, so that the
+    /// build fails if `fakepool` falls out of step with the real thing.
+    macro_rules! api_parity {
+        ($name:ident, $($module:ident)::+) => {
+            #[test]
+            fn $name() {
+                use $($module)::+::{
+                    ChunkGuard, Pool, PoolBox, PoolBuilder, PoolGuard, PoolRef, PoolRefBorrow,
+                    PoolSendToken,
+                    SpeculativePool,
+                };
+
+                let _: fn(usize) -> Pool<usize> = Pool::new;
+                let _: fn(usize) -> Pool<usize> = Pool::with_byte_capacity;
+                let _: fn(usize, usize) -> Pool<usize> = Pool::with_alignment;
+                let _: fn(&Pool<usize>) -> usize = Pool::alignment;
+                let _: fn() -> PoolBuilder<usize> = Pool::builder;
+                let _: fn(Pool<usize>) -> Result<PoolSendToken<usize>, Pool<usize>> =
+                    Pool::into_send_token;
+                let _: fn(&Pool<usize>) -> ChunkGuard<usize> = Pool::reserve;
+                let _: fn(&Pool<usize>) -> PoolGuard<usize> = Pool::checkout;
+                let _: fn(&Pool<usize>) -> SpeculativePool<usize> = Pool::speculate;
+                let _: fn(&Pool<usize>) -> Option<PoolStats> = Pool::stats;
+                let _: fn(&Pool<usize>) -> Option<usize> = Pool::live_count;
+                let _: fn(&Pool<usize>) -> metrics::PoolMetrics = Pool::metrics;
+                let _: fn(&Pool<usize>) -> Result<(), AllocError> = Pool::try_fill;
+                let _: fn(Pool<usize>) -> Pool<usize> = Pool::filled;
+                let _: fn(&Pool<u64>) -> Result<Pool<i64>, LayoutError> = Pool::try_cast;
+                let _: fn(&Pool<u64>) -> Pool<i64> = Pool::cast_checked;
+                let _: fn(Pool<u64>) -> Pool<i64> = Pool::cast_into;
+                let _: fn(&Pool<u64>, &Pool<i64>, usize) -> usize = Pool::transfer_to;
+
+                let _: fn(&Pool<usize>, usize) -> PoolRef<usize> = PoolRef::new;
+                let _: fn(&Pool<usize>) -> Option<PoolRef<usize>> = PoolRef::try_default;
+                let _: fn(&Pool<usize>, usize) -> Result<PoolRef<usize>, usize> = PoolRef::try_new;
+                let _: fn(&Pool<usize>, &usize) -> PoolRef<usize> = PoolRef::clone_from;
+                let _: fn(&Pool<usize>, &PoolRef<usize>) -> PoolRef<usize> = PoolRef::cloned;
+                let _: fn(PoolRef<usize>) -> Result<usize, PoolRef<usize>> = PoolRef::try_unwrap;
+                let _: fn(PoolRef<usize>, &Pool<usize>) -> Result<PoolRef<usize>, PoolRef<usize>> =
+                    PoolRef::migrate;
+                let _: fn(&PoolRef<usize>) -> usize = PoolRef::strong_count;
+                let _: fn(PoolRef<usize>) -> *const usize = PoolRef::into_raw;
+                let _: unsafe fn(*const usize) -> PoolRef<usize> = PoolRef::from_raw;
+
+                let _: fn(&Pool<usize>, usize) -> PoolBox<usize> = PoolBox::new;
+                let _: fn(&Pool<usize>) -> Option<PoolBox<usize>> = PoolBox::try_default;
+                let _: fn(&Pool<usize>, &usize) -> PoolBox<usize> = PoolBox::clone_from;
+                let _: fn(PoolBox<usize>) -> std::ptr::NonNull<usize> = PoolBox::into_raw_non_null;
+                let _: fn(PoolBox<usize>) -> *mut usize = PoolBox::into_raw;
+                let _: unsafe fn(*mut usize) -> PoolBox<usize> = PoolBox::from_raw;
+                let _: fn(&mut PoolBox<usize>) -> std::ptr::NonNull<usize> = PoolBox::as_non_null;
+                let _: fn(PoolBox<usize>) -> std::pin::Pin<PoolBox<usize>> = PoolBox::into_pin;
+                let _: fn(&Pool<usize>) -> PoolBox<std::mem::MaybeUninit<usize>> = PoolBox::new_uninit;
+                let _: unsafe fn(PoolBox<std::mem::MaybeUninit<usize>>) -> PoolBox<usize> =
+                    PoolBox::assume_init;
+                let _: fn(PoolBox<std::mem::MaybeUninit<usize>>, usize) -> PoolBox<usize> =
+                    PoolBox::write;
+                let _: fn(&Pool<usize>) -> PoolRef<std::mem::MaybeUninit<usize>> = PoolRef::new_uninit;
+                let _: unsafe fn(PoolRef<std::mem::MaybeUninit<usize>>) -> PoolRef<usize> =
+                    PoolRef::assume_init;
+                let _: fn(PoolRef<std::mem::MaybeUninit<usize>>, usize) -> PoolRef<usize> =
+                    PoolRef::write;
+
+                let pool: Pool<usize> = Pool::builder().max_size(16).prefill(true).build();
+                let pool = pool.into_send_token().unwrap().into_pool();
+                let value = pool.reserve().commit_ref(1);
+                let boxed = pool.reserve().commit_box(2);
+                assert_eq!(3, *value + *boxed.clone());
+                let mut guard = pool.checkout();
+                *guard = 3;
+                assert_eq!(3, *PoolGuard::into_box(guard));
+                let speculation = pool.speculate();
+                let speculative_value = PoolRef::new(&speculation, 4);
+                drop(speculative_value);
+                speculation.commit();
+                let refs = pool.refs_from_iter(0..4);
+                PoolRef::drop_batch(refs);
+                assert_eq!(6, *PoolRef::new_with(&pool, |slot| slot.write(6)));
+                assert_eq!(0, unsafe { *PoolRef::assume_init(PoolRef::new_zeroed(&pool)) });
+                assert_eq!(0, unsafe { *PoolBox::assume_init(PoolBox::new_zeroed(&pool)) });
+                #[cfg(feature = "bytemuck")]
+                assert_eq!(0, *PoolRef::zeroed(&pool) + *PoolBox::zeroed(&pool));
+                assert_eq!(7, *PoolBox::new_with(&pool, |slot| slot.write(7)));
+
+                let shared = PoolRef::new(&pool, 5);
+                let borrowed: PoolRefBorrow<'_, usize> = PoolRef::borrow_ref(&shared);
+                let copied = borrowed;
+                assert_eq!(5, *copied.get());
+                assert_eq!(1, PoolRefBorrow::strong_count(borrowed));
+                let owned = borrowed.to_owned();
+                assert!(PoolRef::ptr_eq(&shared, &owned));
+                assert_eq!(2, PoolRefBorrow::strong_count(copied));
+            }
+        };
+    }
+
+    #[cfg(not(feature = "disable-pool"))]
+    api_parity!(real_api_parity, crate);
+    api_parity!(fake_api_parity, crate::fakepool);
 }
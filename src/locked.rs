@@ -0,0 +1,65 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Locking chunks of pool memory into RAM, for pools built with
+//! `PoolBuilder::lock_memory()`.
+//!
+//! Locking works on whole pages, so a locked pool's chunks are aligned to and
+//! padded out to the page size, which means no two chunks share a page and
+//! unlocking one chunk can't unlock another.
+
+#[cfg(not(any(unix, windows)))]
+compile_error!("the locked-memory feature flag is only supported on Unix and Windows");
+
+/// Get the size of a page of memory.
+#[cfg(unix)]
+pub(crate) fn page_size() -> usize {
+    match unsafe { libc::sysconf(libc::_SC_PAGESIZE) } {
+        size if size > 0 => size as usize,
+        _ => 4096,
+    }
+}
+
+/// Get the size of a page of memory.
+#[cfg(windows)]
+pub(crate) fn page_size() -> usize {
+    use windows_sys::Win32::System::SystemInformation::{GetSystemInfo, SYSTEM_INFO};
+    let mut info = std::mem::MaybeUninit::<SYSTEM_INFO>::uninit();
+    unsafe {
+        GetSystemInfo(info.as_mut_ptr());
+        info.assume_init().dwPageSize as usize
+    }
+}
+
+/// Lock `size` bytes of memory at `ptr` into RAM, so that they're never
+/// written out to swap, returning `false` if the operating system refused.
+#[cfg(unix)]
+pub(crate) unsafe fn lock(ptr: *mut u8, size: usize) -> bool {
+    libc::mlock(ptr.cast(), size) == 0
+}
+
+/// Lock `size` bytes of memory at `ptr` into RAM, so that they're never
+/// written out to swap, returning `false` if the operating system refused.
+#[cfg(windows)]
+pub(crate) unsafe fn lock(ptr: *mut u8, size: usize) -> bool {
+    windows_sys::Win32::System::Memory::VirtualLock(ptr.cast(), size) != 0
+}
+
+/// Zero and unlock memory locked with [`lock()`][lock].
+///
+/// [lock]: fn.lock.html
+#[cfg(unix)]
+pub(crate) unsafe fn unlock(ptr: *mut u8, size: usize) {
+    ptr.write_bytes(0, size);
+    libc::munlock(ptr.cast(), size);
+}
+
+/// Zero and unlock memory locked with [`lock()`][lock].
+///
+/// [lock]: fn.lock.html
+#[cfg(windows)]
+pub(crate) unsafe fn unlock(ptr: *mut u8, size: usize) {
+    ptr.write_bytes(0, size);
+    windows_sys::Win32::System::Memory::VirtualUnlock(ptr.cast(), size);
+}
@@ -0,0 +1,278 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::mem::MaybeUninit;
+
+use crate::{PoolClone, PoolDefault};
+
+/// Initialise a single field of an uninitialised struct to its default value.
+///
+/// Used by [`pool_default_fields!`][pool_default_fields], not to be called
+/// directly.
+///
+/// [pool_default_fields]: macro.pool_default_fields.html
+#[doc(hidden)]
+pub unsafe fn default_field_uninit<A: PoolDefault>(field: *mut A) {
+    A::default_uninit(&mut *(field as *mut MaybeUninit<A>));
+}
+
+/// Clone a single field into an uninitialised struct.
+///
+/// Used by [`pool_clone_fields!`][pool_clone_fields], not to be called
+/// directly.
+///
+/// [pool_clone_fields]: macro.pool_clone_fields.html
+#[doc(hidden)]
+pub unsafe fn clone_field_uninit<A: PoolClone>(source: &A, field: *mut A) {
+    source.clone_uninit(&mut *(field as *mut MaybeUninit<A>));
+}
+
+/// Implement [`PoolDefault`][PoolDefault] for types whose default value is
+/// all zero bytes.
+///
+/// The generated `default_uninit()` just zeroes the memory, which is about as
+/// cheap as initialisation gets.
+///
+/// This can't check that an all zero value is actually what
+/// [`Default::default()`][Default::default] would produce, or even a valid
+/// value for the type, so you're promising that it is. References, `Box`es
+/// and most other pointers can't be zero, for instance.
+///
+/// # Examples
+///
+/// ```rust
+/// # use refpool::{pool_default_zeroed, Pool, PoolRef};
+/// #[derive(Default)]
+/// struct Counters {
+///     hits: u64,
+///     misses: u64,
+///     bytes: [u8; 32],
+/// }
+///
+/// pool_default_zeroed!(Counters);
+///
+/// let pool: Pool<Counters> = Pool::new(16);
+/// let counters = PoolRef::default(&pool);
+/// assert_eq!(0, counters.hits + counters.misses);
+/// ```
+///
+/// [PoolDefault]: trait.PoolDefault.html
+/// [Default::default]: https://doc.rust-lang.org/std/default/trait.Default.html#tymethod.default
+#[macro_export]
+macro_rules! pool_default_zeroed {
+    ($($type:ty),+ $(,)?) => {
+        $(
+            impl $crate::PoolDefault for $type {
+                unsafe fn default_uninit(target: &mut ::std::mem::MaybeUninit<Self>) {
+                    target.as_mut_ptr().write_bytes(0, 1);
+                }
+            }
+        )+
+    };
+}
+
+/// Implement [`PoolDefault`][PoolDefault] for an enum whose default is a unit
+/// variant.
+///
+/// The generated `default_uninit()` writes the variant directly, so only its
+/// discriminant needs storing, however large the enum's other variants are.
+/// The variant should be what [`Default::default()`][Default::default]
+/// produces for the enum.
+///
+/// # Examples
+///
+/// ```rust
+/// # use refpool::{pool_default_variant, Pool, PoolBox};
+/// enum Slot {
+///     Empty,
+///     Full([u8; 4096]),
+/// }
+///
+/// impl Default for Slot {
+///     fn default() -> Self {
+///         Slot::Empty
+///     }
+/// }
+///
+/// pool_default_variant!(Slot::Empty);
+///
+/// let pool: Pool<Slot> = Pool::new(16);
+/// let slot = PoolBox::default(&pool);
+/// assert!(matches!(*slot, Slot::Empty));
+/// ```
+///
+/// [PoolDefault]: trait.PoolDefault.html
+/// [Default::default]: https://doc.rust-lang.org/std/default/trait.Default.html#tymethod.default
+#[macro_export]
+macro_rules! pool_default_variant {
+    ($type:ident :: $variant:ident) => {
+        impl $crate::PoolDefault for $type {
+            unsafe fn default_uninit(target: &mut ::std::mem::MaybeUninit<Self>) {
+                target.as_mut_ptr().write($type::$variant);
+            }
+        }
+    };
+}
+
+/// Implement [`PoolDefault`][PoolDefault] for a struct by initialising each
+/// of its fields with their own `PoolDefault` implementations.
+///
+/// Every field has to be listed, and the macro will fail to compile if one is
+/// missing, so that no part of the struct is left uninitialised. Each field's
+/// type must implement [`PoolDefault`][PoolDefault], and the struct itself
+/// still needs to implement [`Default`][Default], which should produce the
+/// same value. For tuple structs, list the field indices instead of names.
+///
+/// Generic types aren't supported; implement `PoolDefault` by hand for those.
+///
+/// Like the other helper macros, this isn't available with the
+/// `default_impl` feature flag, which already provides blanket
+/// implementations of `PoolDefault` and `PoolClone`.
+///
+/// # Examples
+///
+/// ```rust
+/// # use refpool::{pool_default_fields, Pool, PoolRef};
+/// #[derive(Default)]
+/// struct Node {
+///     children: Vec<usize>,
+///     name: String,
+///     weight: Option<u64>,
+/// }
+///
+/// pool_default_fields!(Node { children, name, weight });
+///
+/// let pool: Pool<Node> = Pool::new(16);
+/// let node = PoolRef::default(&pool);
+/// assert!(node.children.is_empty());
+/// assert_eq!(None, node.weight);
+/// ```
+///
+/// [PoolDefault]: trait.PoolDefault.html
+/// [Default]: https://doc.rust-lang.org/std/default/trait.Default.html
+#[macro_export]
+macro_rules! pool_default_fields {
+    ($type:ty { $($field:tt),+ $(,)? }) => {
+        impl $crate::PoolDefault for $type {
+            unsafe fn default_uninit(target: &mut ::std::mem::MaybeUninit<Self>) {
+                let _ = |value: &Self| {
+                    let Self { $($field: _),+ } = value;
+                };
+                let target = target.as_mut_ptr();
+                $(
+                    $crate::default_field_uninit(::std::ptr::addr_of_mut!((*target).$field));
+                )+
+            }
+        }
+    };
+}
+
+/// Implement [`PoolClone`][PoolClone] for a struct by cloning each of its
+/// fields with their own `PoolClone` implementations.
+///
+/// This works like [`pool_default_fields!`][pool_default_fields]: every
+/// field has to be listed, each field's type must implement
+/// [`PoolClone`][PoolClone], and the struct itself still needs to implement
+/// [`Clone`][Clone].
+///
+/// # Examples
+///
+/// ```rust
+/// # use refpool::{pool_clone_fields, Pool, PoolRef};
+/// #[derive(Clone)]
+/// struct Point(i64, i64);
+///
+/// pool_clone_fields!(Point { 0, 1 });
+///
+/// let pool: Pool<Point> = Pool::new(16);
+/// let mut point = PoolRef::new(&pool, Point(1, 2));
+/// let other = point.clone();
+/// PoolRef::make_mut(&pool, &mut point).0 = 3;
+/// assert_eq!(3, point.0);
+/// assert_eq!(1, other.0);
+/// ```
+///
+/// [PoolClone]: trait.PoolClone.html
+/// [pool_default_fields]: macro.pool_default_fields.html
+/// [Clone]: https://doc.rust-lang.org/std/clone/trait.Clone.html
+#[macro_export]
+macro_rules! pool_clone_fields {
+    ($type:ty { $($field:tt),+ $(,)? }) => {
+        impl $crate::PoolClone for $type {
+            unsafe fn clone_uninit(&self, target: &mut ::std::mem::MaybeUninit<Self>) {
+                let Self { $($field: _),+ } = self;
+                let target = target.as_mut_ptr();
+                $(
+                    $crate::clone_field_uninit(&self.$field, ::std::ptr::addr_of_mut!((*target).$field));
+                )+
+            }
+        }
+    };
+}
+
+/// Implement [`PoolDefault`][PoolDefault] and [`PoolClone`][PoolClone] for
+/// types which implement [`Default`][Default] and [`Clone`][Clone], by
+/// writing out a whole default value or clone.
+///
+/// These are the same implementations you'd get from implementing
+/// [`PoolDefaultImpl`][PoolDefaultImpl], but this way you can list all your
+/// types in one go. For generic types, list the type parameters without
+/// bounds: the implementations apply whenever the type implements
+/// [`Default`][Default] and [`Clone`][Clone] respectively. Types with
+/// lifetime parameters aren't supported.
+///
+/// # Examples
+///
+/// ```rust
+/// # use refpool::{impl_pool_default, Pool, PoolBox};
+/// #[derive(Default, Clone)]
+/// struct Request {
+///     path: String,
+///     headers: Vec<(String, String)>,
+/// }
+///
+/// #[derive(Default, Clone)]
+/// struct Tagged<A> {
+///     tag: u32,
+///     value: A,
+/// }
+///
+/// impl_pool_default!(Request, Tagged<A>);
+///
+/// let pool: Pool<Tagged<Request>> = Pool::new(16);
+/// let request = PoolBox::default(&pool);
+/// let copy = request.clone();
+/// assert_eq!(0, copy.tag);
+/// assert!(copy.value.path.is_empty());
+/// ```
+///
+/// [PoolDefault]: trait.PoolDefault.html
+/// [PoolClone]: trait.PoolClone.html
+/// [PoolDefaultImpl]: trait.PoolDefaultImpl.html
+/// [Default]: https://doc.rust-lang.org/std/default/trait.Default.html
+/// [Clone]: https://doc.rust-lang.org/std/clone/trait.Clone.html
+#[macro_export]
+macro_rules! impl_pool_default {
+    ($($type:ident $(< $($param:ident),+ >)?),+ $(,)?) => {
+        $(
+            impl $(< $($param),+ >)? $crate::PoolDefault for $type $(< $($param),+ >)?
+            where
+                Self: ::std::default::Default,
+            {
+                unsafe fn default_uninit(target: &mut ::std::mem::MaybeUninit<Self>) {
+                    target.as_mut_ptr().write(::std::default::Default::default());
+                }
+            }
+
+            impl $(< $($param),+ >)? $crate::PoolClone for $type $(< $($param),+ >)?
+            where
+                Self: ::std::clone::Clone,
+            {
+                unsafe fn clone_uninit(&self, target: &mut ::std::mem::MaybeUninit<Self>) {
+                    target.as_mut_ptr().write(::std::clone::Clone::clone(self));
+                }
+            }
+        )+
+    };
+}
@@ -0,0 +1,39 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Exporting pool metrics to monitoring systems.
+//!
+//! [`Pool::metrics()`][Pool::metrics] takes a [`PoolMetrics`][PoolMetrics]
+//! snapshot of a pool's state, which doesn't depend on the pool's type, so
+//! that snapshots of pools of different types can be collected together and
+//! handed to an exporter, such as [`prometheus::render()`][render].
+//!
+//! [Pool::metrics]: ../struct.Pool.html#method.metrics
+//! [PoolMetrics]: struct.PoolMetrics.html
+//! [render]: prometheus/fn.render.html
+
+use crate::PoolStats;
+
+pub mod prometheus;
+
+/// A snapshot of the state of a [`Pool`][Pool].
+///
+/// [Pool]: ../struct.Pool.html
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct PoolMetrics {
+    /// The number of chunks of memory currently held by the pool.
+    pub pool_size: usize,
+    /// The maximum number of chunks the pool will hold.
+    pub max_size: usize,
+    /// The number of bytes of memory currently held by the pool.
+    pub retained_bytes: usize,
+    /// The number of values allocated by the pool which are currently alive,
+    /// if the pool is counting them. See
+    /// [`PoolBuilder::live_count()`][PoolBuilder::live_count].
+    ///
+    /// [PoolBuilder::live_count]: ../struct.PoolBuilder.html#method.live_count
+    pub live: Option<usize>,
+    /// The pool's usage statistics, if it's collecting them.
+    pub stats: Option<PoolStats>,
+}
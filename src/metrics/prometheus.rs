@@ -0,0 +1,128 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! An exporter for the [Prometheus text format][format].
+//!
+//! [format]: https://prometheus.io/docs/instrumenting/exposition_formats/
+
+use std::fmt::Write;
+
+use super::PoolMetrics;
+
+type Metric = (
+    &'static str,
+    &'static str,
+    &'static str,
+    fn(&PoolMetrics) -> Option<usize>,
+);
+
+const METRICS: &[Metric] = &[
+    (
+        "refpool_retained_chunks",
+        "gauge",
+        "Chunks of memory currently held by the pool.",
+        |m| Some(m.pool_size),
+    ),
+    (
+        "refpool_max_chunks",
+        "gauge",
+        "Maximum number of chunks the pool will hold.",
+        |m| Some(m.max_size),
+    ),
+    (
+        "refpool_retained_bytes",
+        "gauge",
+        "Bytes of memory currently held by the pool.",
+        |m| Some(m.retained_bytes),
+    ),
+    (
+        "refpool_live_allocations",
+        "gauge",
+        "Values allocated by the pool which are currently alive.",
+        |m| m.live,
+    ),
+    (
+        "refpool_hits_total",
+        "counter",
+        "Allocations served from memory held by the pool.",
+        |m| m.stats.map(|s| s.hits),
+    ),
+    (
+        "refpool_misses_total",
+        "counter",
+        "Allocations which fell back to the system allocator.",
+        |m| m.stats.map(|s| s.misses),
+    ),
+    (
+        "refpool_returns_total",
+        "counter",
+        "Allocations returned to the pool.",
+        |m| m.stats.map(|s| s.returns),
+    ),
+    (
+        "refpool_discards_total",
+        "counter",
+        "Allocations handed back to the system allocator.",
+        |m| m.stats.map(|s| s.discards),
+    ),
+];
+
+fn write_label(out: &mut String, value: &str) {
+    for c in value.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '"' => out.push_str("\\\""),
+            '\n' => out.push_str("\\n"),
+            c => out.push(c),
+        }
+    }
+}
+
+/// Render metrics for a set of named pools in the Prometheus text format.
+///
+/// Each pool's metrics are labelled with `pool="<name>"`. Metrics which
+/// depend on statistics collection or live counting are left out for pools
+/// which aren't collecting them.
+///
+/// # Examples
+///
+#[cfg_attr(feature = "disable-pool", doc = "```ignore")]
+#[cfg_attr(not(feature = "disable-pool"), doc = "```rust")]
+/// # use refpool::{Pool, PoolRef};
+/// use refpool::metrics::prometheus;
+///
+/// let nodes: Pool<usize> = Pool::builder()
+///     .max_size(16)
+///     .stats(true)
+///     .live_count(true)
+///     .build();
+/// let leaves: Pool<[u8; 64]> = Pool::new(16).filled();
+/// let node = PoolRef::new(&nodes, 1337);
+///
+/// let output = prometheus::render(&[("nodes", nodes.metrics()), ("leaves", leaves.metrics())]);
+/// assert!(output.contains("refpool_retained_chunks{pool=\"leaves\"} 16\n"));
+/// assert!(output.contains("refpool_live_allocations{pool=\"nodes\"} 1\n"));
+/// assert!(!output.contains("refpool_live_allocations{pool=\"leaves\"}"));
+/// ```
+pub fn render<S: AsRef<str>>(pools: &[(S, PoolMetrics)]) -> String {
+    let mut out = String::new();
+    for (name, kind, help, get) in METRICS {
+        let mut header = false;
+        for (pool, metrics) in pools {
+            let value = match get(metrics) {
+                Some(value) => value,
+                None => continue,
+            };
+            if !header {
+                writeln!(out, "# HELP {} {}", name, help).unwrap();
+                writeln!(out, "# TYPE {} {}", name, kind).unwrap();
+                header = true;
+            }
+            write!(out, "{}{{pool=\"", name).unwrap();
+            write_label(&mut out, pool.as_ref());
+            writeln!(out, "\"}} {}", value).unwrap();
+        }
+    }
+    out
+}
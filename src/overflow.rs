@@ -0,0 +1,71 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+// With the `disable-pool` feature flag, there's no real pool to make use of
+// the crate internals here, only the fake pool which ignores them.
+#![cfg_attr(feature = "disable-pool", allow(dead_code))]
+
+use std::fmt::{Debug, Error, Formatter};
+
+/// What a [`Pool`][Pool] should do with memory returned to it when it's full.
+///
+/// You can choose a policy for a pool using
+/// [`PoolBuilder::overflow()`][PoolBuilder::overflow].
+///
+/// # Examples
+///
+#[cfg_attr(feature = "disable-pool", doc = "```ignore")]
+#[cfg_attr(not(feature = "disable-pool"), doc = "```rust")]
+/// # use refpool::{OverflowPolicy, Pool, PoolRef};
+/// let pool: Pool<usize> = Pool::builder()
+///     .max_size(1)
+///     .overflow(OverflowPolicy::Grow(4))
+///     .build();
+/// let refs: Vec<_> = (0..8).map(|i| PoolRef::new(&pool, i)).collect();
+/// drop(refs);
+/// assert_eq!(4, pool.get_max_size());
+/// assert_eq!(4, pool.get_pool_size());
+/// ```
+///
+/// [Pool]: struct.Pool.html
+/// [PoolBuilder::overflow]: struct.PoolBuilder.html#method.overflow
+pub enum OverflowPolicy {
+    /// Hand the memory back to the system allocator. This is the default.
+    Discard,
+    /// Raise the maximum size of the pool to make room, doubling it each time
+    /// the pool overflows, but never raising it above the given cap. Once the
+    /// cap has been reached, memory is discarded.
+    Grow(usize),
+    /// Ask a function what to do.
+    ///
+    /// The function is called with the pool's current maximum size, and
+    /// should return the maximum size the pool should have from now on. If the
+    /// new size isn't larger than the current size, the memory is discarded.
+    ///
+    /// The function may drop values from the same pool, but the policy isn't
+    /// applied to those while it's running: they're discarded if the pool is
+    /// full.
+    Callback(Box<dyn FnMut(usize) -> usize>),
+}
+
+impl OverflowPolicy {
+    /// Decide on a new maximum size for a full pool of size `max_size`.
+    pub(crate) fn grow(&mut self, max_size: usize) -> usize {
+        match self {
+            OverflowPolicy::Discard => max_size,
+            OverflowPolicy::Grow(cap) => max_size.saturating_mul(2).min(*cap).max(max_size),
+            OverflowPolicy::Callback(f) => f(max_size),
+        }
+    }
+}
+
+impl Debug for OverflowPolicy {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        match self {
+            OverflowPolicy::Discard => write!(f, "Discard"),
+            OverflowPolicy::Grow(cap) => write!(f, "Grow({})", cap),
+            OverflowPolicy::Callback(_) => write!(f, "Callback"),
+        }
+    }
+}
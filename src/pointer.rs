@@ -7,7 +7,6 @@ use std::ptr::NonNull;
 pub(crate) trait Pointer<A>: std::fmt::Pointer {
     fn wrap(ptr: *mut A) -> Self;
     fn get_ptr(&self) -> *mut A;
-    fn cast<B>(self) -> *mut B;
     fn get_ptr_checked(&self) -> Option<*mut A>;
     fn null() -> Self;
 }
@@ -15,7 +14,7 @@ pub(crate) trait Pointer<A>: std::fmt::Pointer {
 impl<A> Pointer<A> for NonNull<A> {
     #[inline(always)]
     fn wrap(ptr: *mut A) -> Self {
-        debug_assert_eq!(false, ptr.is_null());
+        debug_assert!(!ptr.is_null());
         unsafe { NonNull::new_unchecked(ptr) }
     }
 
@@ -24,11 +23,6 @@ impl<A> Pointer<A> for NonNull<A> {
         self.as_ptr()
     }
 
-    #[inline(always)]
-    fn cast<B>(self) -> *mut B {
-        self.as_ptr().cast()
-    }
-
     #[inline(always)]
     fn get_ptr_checked(&self) -> Option<*mut A> {
         if *self == NonNull::dangling() {
@@ -2,20 +2,75 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
+use std::alloc::{alloc, dealloc, handle_alloc_error, Layout};
 use std::fmt::{Debug, Error, Formatter};
 use std::mem::MaybeUninit;
+use std::panic::{RefUnwindSafe, UnwindSafe};
+use std::ptr::NonNull;
+#[cfg(debug_assertions)]
+use std::thread::ThreadId;
+#[cfg(feature = "hdrhistogram")]
+use std::time::Instant;
 
+use crate::adaptive::Adaptive;
+use crate::arena::PoolArena;
+use crate::box_handle::PoolBox;
+use crate::builder::PoolBuilder;
+use crate::chunk_guard::ChunkGuard;
 use crate::counter::Counter;
+#[cfg(feature = "debug-leaks")]
+use crate::debug_report::{DebugReport, LiveRegistry};
+use crate::error::{AllocError, LayoutError};
+use crate::group::PoolGroup;
+use crate::idle::IdleTrim;
+#[cfg(feature = "hdrhistogram")]
+use crate::latency::PoolLatency;
+use crate::layout::SameLayout;
+#[cfg(feature = "locked-memory")]
+use crate::locked;
+use crate::metrics::PoolMetrics;
+use crate::overflow::OverflowPolicy;
 use crate::pointer::Pointer;
-use crate::refbox::RefBox;
-use crate::stack::Stack;
+use crate::pool_guard::PoolGuard;
+use crate::recycle::Recycler;
+use crate::ref_handle::PoolRef;
+use crate::refbox::{value_ptr, RefBox};
+use crate::send_token::PoolSendToken;
+use crate::speculate::{Speculation, SpeculativePool};
+use crate::stats::PoolStats;
+use crate::storage::{InlineStack, Storage};
+#[cfg(feature = "trace-alloc")]
+use crate::trace::{AllocSite, AllocSites};
 use crate::types::{ElementPointer, PoolPointer};
+use crate::PoolDefault;
+
+/// Allocate an uninitialised chunk of memory for a `RefBox<A>` from the
+/// system allocator, using the pool's chunk layout.
+fn alloc_chunk<A>(layout: Layout) -> Result<ElementPointer<A>, AllocError> {
+    let chunk = unsafe { alloc(layout) };
+    if chunk.is_null() {
+        Err(AllocError)
+    } else {
+        Ok(ElementPointer::wrap(chunk.cast()))
+    }
+}
+
+/// Hand a chunk back to the system allocator. The layout must be the one the
+/// chunk was allocated with, which is the chunk layout of its pool.
+unsafe fn dealloc_chunk<A>(chunk: ElementPointer<A>, layout: Layout) {
+    dealloc(chunk.as_ptr().cast(), layout);
+}
 
 unsafe fn init_box<A>(ref_box: *mut RefBox<A>, pool: Pool<A>) {
     let count_ptr: *mut _ = &mut (*(ref_box)).count;
     let pool_ptr: *mut _ = &mut (*(ref_box)).pool;
     count_ptr.write(Default::default());
     pool_ptr.write(pool);
+    #[cfg(feature = "debug-aliasing")]
+    {
+        let raw_ptr: *mut _ = &mut (*(ref_box)).raw_pointers;
+        raw_ptr.write(0);
+    }
 }
 
 /// A pool of preallocated memory sized to match type `A`.
@@ -34,7 +89,6 @@ unsafe fn init_box<A>(ref_box: *mut RefBox<A>, pool: Pool<A>) {
 ///
 /// [PoolRef::new]: struct.PoolRef.html#method.new
 /// [PoolRef::default]: struct.PoolRef.html#method.default
-
 pub struct Pool<A> {
     inner: PoolPointer<A>,
 }
@@ -59,45 +113,926 @@ impl<A> Pool<A> {
                 inner: PoolPointer::null(),
             }
         } else {
-            Box::new(PoolInner::new(max_size)).into_ref()
+            PoolInner::allocate(Self::builder().max_size(max_size))
+        }
+    }
+
+    /// Construct a new pool which can hold up to `bytes` bytes of memory.
+    ///
+    /// This is a shorthand for
+    /// [`Pool::builder().max_bytes(bytes).build()`][PoolBuilder::max_bytes],
+    /// which sets the maximum size of the pool to as many values of `A`, along
+    /// with the pool's per-value bookkeeping overhead, as fit in the given
+    /// number of bytes.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use refpool::Pool;
+    /// let pool: Pool<[u64; 127]> = Pool::with_byte_capacity(1024 * 1024).filled();
+    /// assert!(pool.retained_bytes() <= 1024 * 1024);
+    /// ```
+    ///
+    /// [PoolBuilder::max_bytes]: struct.PoolBuilder.html#method.max_bytes
+    pub fn with_byte_capacity(bytes: usize) -> Self {
+        Self::builder().max_bytes(bytes).build()
+    }
+
+    /// Construct a new pool with a given max size whose chunks of memory are
+    /// aligned to at least `align` bytes.
+    ///
+    /// This is a shorthand for
+    /// [`Pool::builder().max_size(max_size).alignment(align).build()`][PoolBuilder::alignment].
+    /// Every value allocated through the pool starts on an `align` byte
+    /// boundary, including values allocated from the system allocator when
+    /// the pool is empty.
+    ///
+    /// # Panics
+    ///
+    /// This panics if `align` isn't a power of two.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use refpool::{Pool, PoolRef};
+    /// let pool: Pool<[f32; 8]> = Pool::with_alignment(16, 64);
+    /// let vector = PoolRef::new(&pool, [0.5; 8]);
+    /// assert_eq!(0, vector.as_ptr() as usize % 64);
+    /// ```
+    ///
+    /// [PoolBuilder::alignment]: struct.PoolBuilder.html#method.alignment
+    pub fn with_alignment(max_size: usize, align: usize) -> Self {
+        Self::builder().max_size(max_size).alignment(align).build()
+    }
+
+    /// Construct a new pool which holds up to `N` values' worth of memory in
+    /// a fixed size array.
+    ///
+    /// This works like a pool using a [`FixedStack`][FixedStack], except the
+    /// array lives in the same allocation as the rest of the pool, so the
+    /// pool itself takes up a single allocation, and it's never reallocated.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use refpool::Pool;
+    /// let pool: Pool<usize> = Pool::fixed::<64>().filled();
+    /// assert_eq!(64, pool.get_pool_size());
+    /// ```
+    ///
+    /// [FixedStack]: struct.FixedStack.html
+    pub fn fixed<const N: usize>() -> Self {
+        let mut builder = Self::builder().max_size(N);
+        builder.inline_storage = Some(N);
+        builder.build()
+    }
+
+    /// Get the current thread's pool for `A`, setting up a new pool with the
+    /// given maximum size if there isn't one yet.
+    ///
+    /// Once a thread has a pool for `A`, constructors like
+    /// [`PoolRef::new_tl()`][PoolRef::new_tl] will use it. If the thread
+    /// already has a pool for `A`, `max_size` is ignored.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use refpool::{Pool, PoolRef};
+    /// let pool = Pool::<usize>::thread_local(1024);
+    /// let number: PoolRef<usize> = PoolRef::new_tl(1337);
+    /// drop(number);
+    /// assert_eq!(1, pool.get_pool_size());
+    /// ```
+    ///
+    /// [PoolRef::new_tl]: struct.PoolRef.html#method.new_tl
+    #[cfg(feature = "thread-local")]
+    pub fn thread_local(max_size: usize) -> Self
+    where
+        A: 'static,
+    {
+        crate::thread_local::install(max_size)
+    }
+
+    /// Get the current thread's pool for `A`.
+    ///
+    /// If there's no pool for `A` set up using
+    /// [`Pool::thread_local()`][thread_local], you get a null pool, which
+    /// allocates from the heap as usual.
+    ///
+    /// [thread_local]: #method.thread_local
+    #[cfg(feature = "thread-local")]
+    pub fn current() -> Self
+    where
+        A: 'static,
+    {
+        crate::thread_local::current()
+    }
+
+    /// Construct a [`PoolBuilder`][PoolBuilder] for configuring a new pool.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use refpool::Pool;
+    /// let pool: Pool<usize> = Pool::builder().max_size(1024).prefill(true).build();
+    /// assert_eq!(1024, pool.get_pool_size());
+    /// ```
+    ///
+    /// [PoolBuilder]: struct.PoolBuilder.html
+    pub fn builder() -> PoolBuilder<A> {
+        PoolBuilder::new()
+    }
+
+    /// Return a value's memory to the pool after the value has been dropped.
+    pub(crate) fn push(&self, value: ElementPointer<A>) {
+        debug_assert!(self.inner.get_ptr_checked().is_some());
+        unsafe { (*self.inner.get_ptr()).recycle(value) }
+    }
+
+    /// Test whether the pool can take back a value's memory, applying the
+    /// pool's [`OverflowPolicy`][OverflowPolicy] if it's full.
+    ///
+    /// [OverflowPolicy]: enum.OverflowPolicy.html
+    pub(crate) fn has_room(&self) -> bool {
+        match self.inner.get_ptr_checked() {
+            Some(inner) => unsafe { PoolInner::has_room(inner) },
+            None => false,
+        }
+    }
+
+    /// Get a pointer identifying the pool, for telling pools apart.
+    pub(crate) fn id(&self) -> *const () {
+        self.inner.as_ptr().cast()
+    }
+
+    /// Record that a value's memory was handed back to the system allocator
+    /// because the pool was full.
+    pub(crate) fn discard(&self, chunk: *const RefBox<A>) {
+        if let Some(inner) = self.inner.get_ptr_checked() {
+            unsafe {
+                (*inner).record(|stats| stats.discards += 1);
+                (*inner).checked_in(chunk, true);
+            }
+        }
+    }
+
+    /// Record an allocation against the call site which requested it.
+    #[cfg(feature = "trace-alloc")]
+    #[track_caller]
+    fn trace(&self, heap_fallback: bool) {
+        if let Some(inner) = self.inner.get_ptr_checked() {
+            unsafe {
+                (*inner)
+                    .alloc_sites
+                    .record(std::panic::Location::caller(), heap_fallback)
+            };
+        }
+    }
+
+    /// Start timing a pool operation, if the pool records latencies.
+    #[cfg(feature = "hdrhistogram")]
+    pub(crate) fn start_timer(&self) -> Option<Instant> {
+        self.deref()
+            .and_then(|p| p.latency.as_ref())
+            .map(|_| Instant::now())
+    }
+
+    /// Record the latency of an allocation timed with
+    /// [`start_timer()`][start_timer].
+    ///
+    /// [start_timer]: #method.start_timer
+    #[cfg(feature = "hdrhistogram")]
+    fn record_pop_latency(&self, start: Option<Instant>, hit: bool) {
+        if let (Some(start), Some(inner)) = (start, self.inner.get_ptr_checked()) {
+            if let Some(ref mut latency) = unsafe { &mut *inner }.latency {
+                latency.record_pop(start, hit);
+            }
+        }
+    }
+
+    /// Record the latency of returning a value's memory, timed with
+    /// [`start_timer()`][start_timer].
+    ///
+    /// [start_timer]: #method.start_timer
+    #[cfg(feature = "hdrhistogram")]
+    pub(crate) fn record_return_latency(&self, start: Option<Instant>) {
+        if let (Some(start), Some(inner)) = (start, self.inner.get_ptr_checked()) {
+            if let Some(ref mut latency) = unsafe { &mut *inner }.latency {
+                latency.record_return(start);
+            }
+        }
+    }
+
+    /// Prepare a chunk taken from the pool or the system allocator for use.
+    fn check_out(&self, chunk: ElementPointer<A>) {
+        if let Some(inner) = self.inner.get_ptr_checked() {
+            unsafe {
+                (*inner).checked_out(chunk.as_ptr());
+                (*inner).inc();
+            }
+        }
+        // We've already counted the chunk's handle to the pool above, which
+        // saves going through `clone()` and looking the pool up again.
+        unsafe { init_box(chunk.as_ptr(), Pool { inner: self.inner }) };
+    }
+
+    /// Pop a chunk with an initialised header and an uninitialised value.
+    ///
+    /// Chunks are handed out as raw pointers rather than `Box`es, because
+    /// they're allocated with the pool's chunk layout, which isn't
+    /// necessarily the layout of a `RefBox<A>`.
+    #[cfg_attr(feature = "trace-alloc", track_caller)]
+    pub(crate) fn pop_chunk(&self) -> ElementPointer<A> {
+        self.pop_from(self.inner.get_ptr_checked())
+    }
+
+    /// Pop a chunk from an already resolved pool, so that batch allocations
+    /// only need to look it up once.
+    #[cfg_attr(feature = "trace-alloc", track_caller)]
+    fn pop_from(&self, inner: Option<*mut PoolInner<A>>) -> ElementPointer<A> {
+        #[cfg(feature = "hdrhistogram")]
+        let start = self.start_timer();
+        let popped = match inner {
+            Some(inner) => unsafe { (*inner).pop() },
+            None => None,
+        };
+        #[cfg(feature = "trace-alloc")]
+        self.trace(popped.is_none());
+        #[cfg(feature = "hdrhistogram")]
+        let hit = popped.is_some();
+        let chunk = match popped {
+            Some(chunk) => chunk,
+            None => self.alloc_chunk_or_abort(),
+        };
+        self.check_out(chunk);
+        #[cfg(feature = "hdrhistogram")]
+        self.record_pop_latency(start, hit);
+        chunk
+    }
+
+    /// Pop a chunk from the pool, falling back to the system allocator, and
+    /// failing if that fails too.
+    #[cfg_attr(feature = "trace-alloc", track_caller)]
+    pub(crate) fn try_pop_or_alloc_chunk(&self) -> Result<ElementPointer<A>, AllocError> {
+        #[cfg(feature = "hdrhistogram")]
+        let start = self.start_timer();
+        let popped = match self.inner.get_ptr_checked() {
+            Some(inner) => unsafe { (*inner).pop() },
+            None => None,
+        };
+        #[cfg(feature = "trace-alloc")]
+        self.trace(popped.is_none());
+        #[cfg(feature = "hdrhistogram")]
+        let hit = popped.is_some();
+        let chunk = match popped {
+            Some(chunk) => chunk,
+            None => self.alloc_chunk()?,
+        };
+        self.check_out(chunk);
+        #[cfg(feature = "hdrhistogram")]
+        self.record_pop_latency(start, hit);
+        Ok(chunk)
+    }
+
+    /// Pop a chunk from the pool and initialise it to a default value, reusing
+    /// a recycled value if there is one.
+    #[cfg_attr(feature = "trace-alloc", track_caller)]
+    pub(crate) fn pop_default_chunk(&self) -> ElementPointer<A>
+    where
+        A: PoolDefault,
+    {
+        let recycled = match self.inner.get_ptr_checked() {
+            Some(inner) => unsafe { (*inner).take_recycled() },
+            None => None,
+        };
+        match recycled {
+            Some(chunk) => {
+                #[cfg(feature = "trace-alloc")]
+                self.trace(false);
+                self.check_out(chunk);
+                chunk
+            }
+            None => {
+                let chunk = self.pop_chunk();
+                unsafe {
+                    PoolDefault::default_uninit(&mut *value_ptr(chunk).cast::<MaybeUninit<A>>())
+                };
+                chunk
+            }
+        }
+    }
+
+    /// Reset a value being returned to a recycling pool, returning `false` if
+    /// the pool doesn't recycle values.
+    pub(crate) fn reset_value(&self, value: &mut A) -> bool {
+        match self.deref().and_then(|p| p.recycler) {
+            Some(recycler) => {
+                (recycler.reset)(value);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Prepare a chunk without a value for being returned to the pool.
+    pub(crate) fn prepare_uninit(&self, chunk: ElementPointer<A>) -> ElementPointer<A> {
+        match self.deref() {
+            Some(inner) => unsafe { inner.prepare(chunk) },
+            None => chunk,
+        }
+    }
+
+    /// Pop a chunk from the pool without falling back to the system allocator
+    /// if the pool is empty.
+    #[cfg_attr(feature = "trace-alloc", track_caller)]
+    pub(crate) fn try_pop_chunk(&self) -> Option<ElementPointer<A>> {
+        let chunk = unsafe { (*self.inner.get_ptr_checked()?).take()? };
+        #[cfg(feature = "trace-alloc")]
+        self.trace(false);
+        self.check_out(chunk);
+        Some(chunk)
+    }
+
+    /// Register the pool with the group it was configured to join, if any.
+    pub(crate) fn join_group(&self) {
+        if let Some(inner) = self.deref() {
+            if let Some(ref group) = inner.group {
+                group.join(
+                    self.inner.get_ptr() as *const (),
+                    PoolInner::<A>::trim_erased,
+                );
+            }
+        }
+    }
+
+    /// Get the number of handles to the pool, including the ones held by
+    /// values allocated from it.
+    pub(crate) fn handle_count(&self) -> usize {
+        self.deref().map(|p| p.count.count()).unwrap_or(0)
+    }
+
+    /// Turn the pool into a token which can be sent to another thread.
+    ///
+    /// This only succeeds if this is the only handle to the pool, no values
+    /// allocated from the pool are still alive, and the pool doesn't hold
+    /// anything which can't be sent between threads, which means it can't be
+    /// part of a [`PoolGroup`][PoolGroup], have an
+    /// [`OverflowPolicy::Callback`][Callback], or recycle values. Otherwise, you get the pool
+    /// back in the `Err` value.
+    ///
+    /// See [`PoolSendToken`][PoolSendToken].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use refpool::{Pool, PoolRef};
+    /// let pool: Pool<usize> = Pool::new(16);
+    /// let value = PoolRef::new(&pool, 1);
+    /// let pool = pool.into_send_token().unwrap_err();
+    /// drop(value);
+    /// assert!(pool.into_send_token().is_ok());
+    /// ```
+    ///
+    /// [PoolGroup]: struct.PoolGroup.html
+    /// [Callback]: enum.OverflowPolicy.html#variant.Callback
+    /// [PoolSendToken]: struct.PoolSendToken.html
+    pub fn into_send_token(self) -> Result<PoolSendToken<A>, Self> {
+        let sendable = match self.deref() {
+            None => true,
+            Some(inner) => {
+                self.handle_count() == 1
+                    && inner.group.is_none()
+                    && inner.recycler.is_none()
+                    && !matches!(inner.overflow, OverflowPolicy::Callback(_))
+            }
+        };
+        if sendable {
+            Ok(PoolSendToken::new(self))
+        } else {
+            Err(self)
+        }
+    }
+
+    /// Make the current thread the owner of the pool.
+    pub(crate) fn adopt(&self) {
+        #[cfg(debug_assertions)]
+        {
+            if let Some(inner) = self.inner.get_ptr_checked() {
+                unsafe { (*inner).thread = std::thread::current().id() };
+            }
+        }
+    }
+
+    fn deref(&self) -> Option<&PoolInner<A>> {
+        self.inner.get_ptr_checked().map(|p| unsafe { &*p })
+    }
+
+    /// Reserve a chunk of memory from the pool for a value to be constructed
+    /// later.
+    ///
+    /// If the pool is empty, the memory is allocated from the system allocator
+    /// instead, just like when constructing a value. The returned
+    /// [`ChunkGuard`][ChunkGuard] gives the memory back to the pool when it's
+    /// dropped, unless it's been used to construct a value.
+    ///
+    /// This is useful for transactional code which needs to make sure memory
+    /// is available before committing to an operation, without having to
+    /// construct a dummy value or risk losing the memory on an early return.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use refpool::{Pool, PoolRef};
+    /// fn insert(pool: &Pool<usize>, list: &mut Vec<PoolRef<usize>>, value: usize) -> bool {
+    ///     let chunk = pool.reserve();
+    ///     if list.iter().any(|item| **item == value) {
+    ///         return false; // the chunk goes back to the pool
+    ///     }
+    ///     list.push(chunk.commit_ref(value));
+    ///     true
+    /// }
+    ///
+    /// let pool: Pool<usize> = Pool::new(16).filled();
+    /// let mut list = Vec::new();
+    /// assert!(insert(&pool, &mut list, 1));
+    /// assert!(!insert(&pool, &mut list, 1));
+    /// assert_eq!(15, pool.get_pool_size());
+    /// ```
+    ///
+    /// [ChunkGuard]: struct.ChunkGuard.html
+    #[cfg_attr(feature = "trace-alloc", track_caller)]
+    pub fn reserve(&self) -> ChunkGuard<A> {
+        ChunkGuard::new(self)
+    }
+
+    /// Check an object out of the pool.
+    ///
+    /// The object goes back to the pool when the returned
+    /// [`PoolGuard`][PoolGuard] is dropped. See [`PoolGuard`][PoolGuard] for
+    /// details.
+    ///
+    /// [PoolGuard]: struct.PoolGuard.html
+    #[cfg_attr(feature = "trace-alloc", track_caller)]
+    pub fn checkout(&self) -> PoolGuard<A>
+    where
+        A: PoolDefault,
+    {
+        PoolGuard::new(self)
+    }
+
+    /// Construct a [`PoolArena`][PoolArena] which allocates from this pool.
+    ///
+    /// See [`PoolArena`][PoolArena] for details.
+    ///
+    /// [PoolArena]: struct.PoolArena.html
+    pub fn arena(&self) -> PoolArena<A> {
+        PoolArena::new(self)
+    }
+
+    /// Lend a default value from the pool to a function.
+    ///
+    /// The value is returned to the pool when the function returns, or if it
+    /// panics. This saves you the bookkeeping of a
+    /// [`PoolBox`][PoolBox] for short lived scratch values. In a pool built
+    /// with [`PoolBuilder::recycle()`][recycle], this reuses values along with
+    /// their resources, such as a [`Vec`][Vec]'s buffer.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use refpool::Pool;
+    /// let pool: Pool<Vec<u8>> = Pool::builder().max_size(4).recycle().build();
+    /// let len = pool.with_tmp(|buffer| {
+    ///     buffer.extend_from_slice(b"Hello Joe!");
+    ///     buffer.len()
+    /// });
+    /// assert_eq!(10, len);
+    /// assert!(pool.with_tmp(|buffer| buffer.is_empty() && buffer.capacity() >= 10));
+    /// ```
+    ///
+    /// [PoolBox]: struct.PoolBox.html
+    /// [recycle]: struct.PoolBuilder.html#method.recycle
+    /// [Vec]: https://doc.rust-lang.org/std/vec/struct.Vec.html
+    #[cfg_attr(feature = "trace-alloc", track_caller)]
+    pub fn with_tmp<F, R>(&self, f: F) -> R
+    where
+        A: PoolDefault,
+        F: FnOnce(&mut A) -> R,
+    {
+        let mut value = PoolBox::default(self);
+        f(&mut value)
+    }
+
+    /// Allocate `count` default values from the pool in one go.
+    ///
+    /// This does the same as calling [`PoolRef::default()`][PoolRef::default]
+    /// `count` times, but only looks up the pool once for the whole batch.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use refpool::Pool;
+    /// let pool: Pool<usize> = Pool::new(1024).filled();
+    /// let refs = pool.alloc_batch_default(1000);
+    /// assert_eq!(1000, refs.len());
+    /// assert_eq!(24, pool.get_pool_size());
+    /// ```
+    ///
+    /// [PoolRef::default]: struct.PoolRef.html#method.default
+    #[cfg_attr(feature = "trace-alloc", track_caller)]
+    pub fn alloc_batch_default(&self, count: usize) -> Vec<PoolRef<A>>
+    where
+        A: PoolDefault,
+    {
+        let mut refs = Vec::with_capacity(count);
+        let inner = self.inner.get_ptr_checked();
+        let recycling = match inner {
+            Some(inner) => unsafe { (*inner).recycler.is_some() },
+            None => false,
+        };
+        if recycling {
+            for _ in 0..count {
+                refs.push(unsafe { RefBox::into_ref(self.pop_default_chunk()) });
+            }
+        } else {
+            for _ in 0..count {
+                let chunk = self.pop_from(inner);
+                unsafe {
+                    PoolDefault::default_uninit(&mut *value_ptr(chunk).cast::<MaybeUninit<A>>());
+                    refs.push((*chunk.as_ptr()).new_ref());
+                }
+            }
+        }
+        refs
+    }
+
+    /// Allocate every value from an iterator in the pool in one go.
+    ///
+    /// This does the same as calling [`PoolRef::new()`][PoolRef::new] on every
+    /// value, but only looks up the pool once for the whole batch.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use refpool::Pool;
+    /// let pool: Pool<usize> = Pool::new(1024);
+    /// let refs = pool.refs_from_iter(0..100);
+    /// assert_eq!(100, refs.len());
+    /// assert_eq!(99, *refs[99]);
+    /// ```
+    ///
+    /// [PoolRef::new]: struct.PoolRef.html#method.new
+    #[cfg_attr(feature = "trace-alloc", track_caller)]
+    pub fn refs_from_iter<I>(&self, values: I) -> Vec<PoolRef<A>>
+    where
+        I: IntoIterator<Item = A>,
+    {
+        let values = values.into_iter();
+        let mut refs = Vec::with_capacity(values.size_hint().0);
+        let inner = self.inner.get_ptr_checked();
+        for value in values {
+            let chunk = self.pop_from(inner);
+            unsafe {
+                value_ptr(chunk).write(value);
+                refs.push((*chunk.as_ptr()).new_ref());
+            }
+        }
+        refs
+    }
+
+    /// Start a speculation on the pool.
+    ///
+    /// Allocations made through the returned
+    /// [`SpeculativePool`][SpeculativePool] are tracked so that they can be
+    /// rolled back later. See [`SpeculativePool`][SpeculativePool] for
+    /// details.
+    ///
+    /// # Panics
+    ///
+    /// This panics if the pool is already running a speculation.
+    ///
+    /// [SpeculativePool]: struct.SpeculativePool.html
+    pub fn speculate(&self) -> SpeculativePool<A> {
+        SpeculativePool::new(self)
+    }
+
+    pub(crate) fn begin_speculation(&self) {
+        if let Some(inner) = self.inner.get_ptr_checked() {
+            let inner = unsafe { &mut *inner };
+            assert!(
+                inner.speculation.is_none(),
+                "Pool::speculate: the pool is already running a speculation"
+            );
+            inner.speculation = Some(Speculation::new(inner.stats));
+        }
+    }
+
+    pub(crate) fn end_speculation(&self) {
+        if let Some(inner) = self.inner.get_ptr_checked() {
+            unsafe { (*inner).speculation = None };
+        }
+    }
+
+    pub(crate) fn rollback_speculation(&self) {
+        if let Some(inner) = self.inner.get_ptr_checked() {
+            let inner = unsafe { &mut *inner };
+            if let Some(speculation) = inner.speculation.take() {
+                inner.stats = speculation.rollback(inner.stats);
+            }
+        }
+    }
+
+    /// Get the alignment of the memory the pool hands out.
+    ///
+    /// This is the alignment requested with
+    /// [`Pool::with_alignment()`][with_alignment], or the alignment the pool
+    /// needs for a value of `A` and its bookkeeping, whichever is stricter.
+    ///
+    /// [with_alignment]: #method.with_alignment
+    pub fn alignment(&self) -> usize {
+        self.chunk_layout().align()
+    }
+
+    /// Get the maximum size of the pool.
+    pub fn get_max_size(&self) -> usize {
+        self.deref().map(|p| p.get_max_size()).unwrap_or(0)
+    }
+
+    /// Get the current size of the pool.
+    pub fn get_pool_size(&self) -> usize {
+        self.deref().map(|p| p.get_pool_size()).unwrap_or(0)
+    }
+
+    /// Get the number of bytes of memory currently held by the pool.
+    ///
+    /// This is the current size of the pool multiplied by the size of a
+    /// value of `A` plus its bookkeeping overhead.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use refpool::Pool;
+    /// let pool: Pool<u64> = Pool::new(16);
+    /// assert_eq!(0, pool.retained_bytes());
+    /// pool.fill();
+    /// assert!(pool.retained_bytes() >= 16 * std::mem::size_of::<u64>());
+    /// ```
+    pub fn retained_bytes(&self) -> usize {
+        self.get_pool_size() * self.chunk_size()
+    }
+
+    /// Get the number of bytes of memory the pool will hold when it's full.
+    ///
+    /// This is the maximum size of the pool multiplied by the size of a
+    /// value of `A` plus its bookkeeping overhead.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use refpool::Pool;
+    /// let pool: Pool<u64> = Pool::new(16);
+    /// pool.fill();
+    /// assert_eq!(pool.max_retained_bytes(), pool.retained_bytes());
+    /// ```
+    pub fn max_retained_bytes(&self) -> usize {
+        self.get_max_size() * self.chunk_size()
+    }
+
+    /// Allocate a chunk for the pool from the system allocator.
+    fn alloc_chunk(&self) -> Result<ElementPointer<A>, AllocError> {
+        match self.deref() {
+            Some(inner) => inner.alloc(),
+            None => alloc_chunk(Layout::new::<RefBox<A>>()),
+        }
+    }
+
+    /// Allocate a chunk for the pool, aborting through
+    /// [`handle_alloc_error`][handle_alloc_error] if the allocation fails.
+    ///
+    /// [handle_alloc_error]: https://doc.rust-lang.org/std/alloc/fn.handle_alloc_error.html
+    fn alloc_chunk_or_abort(&self) -> ElementPointer<A> {
+        self.alloc_chunk()
+            .unwrap_or_else(|_| handle_alloc_error(self.chunk_layout()))
+    }
+
+    /// Hand a chunk which was allocated for the pool back to the system
+    /// allocator.
+    pub(crate) unsafe fn dealloc_chunk(&self, chunk: ElementPointer<A>) {
+        match self.deref() {
+            Some(inner) => inner.dealloc_chunk(chunk),
+            None => dealloc_chunk(chunk, Layout::new::<RefBox<A>>()),
+        }
+    }
+
+    /// The number of bytes of memory used for each value allocated by the
+    /// pool.
+    pub(crate) fn chunk_size(&self) -> usize {
+        self.chunk_layout().size()
+    }
+
+    /// The layout of the chunks of memory the pool allocates.
+    ///
+    /// This is the layout of a `RefBox<A>`, unless the pool was built with a
+    /// stricter alignment.
+    pub(crate) fn chunk_layout(&self) -> Layout {
+        match self.deref() {
+            Some(inner) => inner.layout,
+            None => Layout::new::<RefBox<A>>(),
         }
     }
 
-    pub(crate) fn push(&self, value: ElementPointer<A>) {
-        debug_assert!(self.inner.get_ptr_checked().is_some());
-        unsafe { (*self.inner.get_ptr()).push(value) }
+    /// Get the usage statistics for the pool.
+    ///
+    /// This returns `None` unless the pool was constructed with
+    /// [`PoolBuilder::stats(true)`][PoolBuilder::stats].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use refpool::{Pool, PoolRef};
+    /// let pool: Pool<usize> = Pool::builder().max_size(1).stats(true).build();
+    /// let first = PoolRef::new(&pool, 1);
+    /// let second = PoolRef::new(&pool, 2);
+    /// drop(first);
+    /// drop(second);
+    /// let stats = pool.stats().unwrap();
+    /// assert_eq!(0, stats.hits);
+    /// assert_eq!(2, stats.misses);
+    /// assert_eq!(1, stats.returns);
+    /// assert_eq!(1, stats.discards);
+    /// ```
+    ///
+    /// [PoolBuilder::stats]: struct.PoolBuilder.html#method.stats
+    pub fn stats(&self) -> Option<PoolStats> {
+        self.deref().and_then(|p| p.stats)
     }
 
-    pub(crate) fn pop(&self) -> Box<MaybeUninit<RefBox<A>>> {
-        let mut obj = if let Some(inner) = self.inner.get_ptr_checked() {
-            unsafe { (*inner).pop() }
-        } else {
-            None
+    /// Get histograms of the latencies of allocating from and returning
+    /// memory to the pool.
+    ///
+    /// This returns `None` unless the pool was constructed with
+    /// [`PoolBuilder::latency(true)`][PoolBuilder::latency]. It's only
+    /// available with the `hdrhistogram` feature flag enabled. See
+    /// [`PoolLatency`][PoolLatency].
+    ///
+    /// [PoolBuilder::latency]: struct.PoolBuilder.html#method.latency
+    /// [PoolLatency]: struct.PoolLatency.html
+    #[cfg(feature = "hdrhistogram")]
+    pub fn latency(&self) -> Option<PoolLatency> {
+        self.deref().and_then(|p| p.latency.clone())
+    }
+
+    /// Get the number of values allocated from the pool which are currently
+    /// alive.
+    ///
+    /// Values which have been converted into something else, such as with
+    /// [`PoolRef::try_unwrap()`][PoolRef::try_unwrap], no longer count as
+    /// alive, while chunks of memory held by a [`ChunkGuard`][ChunkGuard] do.
+    ///
+    /// This is only available if the pool was built with
+    /// [`PoolBuilder::live_count()`][PoolBuilder::live_count] enabled,
+    /// otherwise it returns `None`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use refpool::{Pool, PoolRef};
+    /// let pool: Pool<usize> = Pool::builder().max_size(16).live_count(true).build();
+    /// let first = PoolRef::new(&pool, 1);
+    /// let second = PoolRef::new(&pool, 2);
+    /// assert_eq!(Some(2), pool.live_count());
+    /// drop(first);
+    /// assert_eq!(Some(1), pool.live_count());
+    /// ```
+    ///
+    /// [ChunkGuard]: struct.ChunkGuard.html
+    /// [PoolRef::try_unwrap]: struct.PoolRef.html#method.try_unwrap
+    /// [PoolBuilder::live_count]: struct.PoolBuilder.html#method.live_count
+    pub fn live_count(&self) -> Option<usize> {
+        self.deref().and_then(|p| p.live)
+    }
+
+    /// List the addresses of the chunks of memory currently in the pool.
+    ///
+    /// A value lives at the start of its chunk, so these are the addresses
+    /// you'd get from [`PoolRef::into_raw()`][PoolRef::into_raw] or
+    /// [`PoolBox::into_raw()`][PoolBox::into_raw] for a value which has since
+    /// been returned to the pool. If a raw pointer you're still holding on to
+    /// shows up in here, it's dangling.
+    ///
+    /// This is only available with the `debug-dump` feature flag enabled.
+    /// Pools using a custom [`PoolStorage`][PoolStorage] which doesn't
+    /// implement [`PoolStorage::for_each_chunk()`][for_each_chunk] always
+    /// look empty.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use refpool::{Pool, PoolRef};
+    /// let pool: Pool<usize> = Pool::new(16);
+    /// let number = PoolRef::into_raw(PoolRef::new(&pool, 1337));
+    /// assert!(pool.debug_dump().is_empty());
+    /// drop(unsafe { PoolRef::from_raw(number) });
+    /// assert_eq!(vec![number.cast::<()>()], pool.debug_dump());
+    /// ```
+    ///
+    /// [PoolRef::into_raw]: struct.PoolRef.html#method.into_raw
+    /// [PoolBox::into_raw]: struct.PoolBox.html#method.into_raw
+    /// [PoolStorage]: trait.PoolStorage.html
+    /// [for_each_chunk]: trait.PoolStorage.html#method.for_each_chunk
+    #[cfg(feature = "debug-dump")]
+    pub fn debug_dump(&self) -> Vec<*const ()> {
+        let mut chunks = Vec::new();
+        if let Some(inner) = self.deref() {
+            inner
+                .stack
+                .for_each_chunk(&mut |chunk| chunks.push(chunk.as_ptr() as *const ()));
         }
-        .unwrap_or_else(|| Box::new(MaybeUninit::uninit()));
-        unsafe { init_box(obj.as_mut_ptr(), self.clone()) };
-        obj
+        chunks
     }
 
-    fn deref(&self) -> Option<&PoolInner<A>> {
-        self.inner.get_ptr_checked().map(|p| unsafe { &*p })
+    /// Get a report of the values allocated from the pool which are still
+    /// alive.
+    ///
+    /// This is only available with the `debug-leaks` feature flag enabled.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use refpool::{Pool, PoolRef};
+    /// let pool: Pool<usize> = Pool::new(16);
+    /// let leaked = PoolRef::new(&pool, 1);
+    /// let report = pool.debug_report();
+    /// assert_eq!(1, report.len());
+    /// println!("{}", report);
+    /// drop(leaked);
+    /// assert!(pool.debug_report().is_empty());
+    /// ```
+    #[cfg(feature = "debug-leaks")]
+    pub fn debug_report(&self) -> DebugReport {
+        self.deref()
+            .map(|p| p.registry.report::<A>())
+            .unwrap_or_default()
     }
 
-    /// Get the maximum size of the pool.
-    pub fn get_max_size(&self) -> usize {
-        self.deref().map(|p| p.get_max_size()).unwrap_or(0)
+    /// Get allocation counts for each location in the source code which has
+    /// allocated from the pool, with the locations causing the most fallbacks
+    /// to the system allocator first.
+    ///
+    /// This is only available with the `trace-alloc` feature flag enabled.
+    /// Pools with a maximum size of zero don't keep track of anything.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use refpool::{Pool, PoolRef};
+    /// let pool: Pool<usize> = Pool::new(16);
+    /// for i in 0..4 {
+    ///     PoolRef::new(&pool, i);
+    /// }
+    /// let sites = pool.alloc_sites();
+    /// assert_eq!(1, sites.len());
+    /// assert_eq!(4, sites[0].allocations);
+    /// assert_eq!(1, sites[0].heap_fallbacks);
+    /// ```
+    #[cfg(feature = "trace-alloc")]
+    pub fn alloc_sites(&self) -> Vec<AllocSite> {
+        self.deref()
+            .map(|p| p.alloc_sites.to_vec())
+            .unwrap_or_default()
     }
 
-    /// Get the current size of the pool.
-    pub fn get_pool_size(&self) -> usize {
-        self.deref().map(|p| p.get_pool_size()).unwrap_or(0)
+    /// Take a snapshot of the pool's current state for exporting to a
+    /// monitoring system.
+    ///
+    /// See the [`metrics`][metrics] module.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use refpool::Pool;
+    /// let pool: Pool<usize> = Pool::new(16).filled();
+    /// let metrics = pool.metrics();
+    /// assert_eq!(16, metrics.pool_size);
+    /// assert_eq!(16, metrics.max_size);
+    /// assert_eq!(None, metrics.live);
+    /// ```
+    ///
+    /// [metrics]: metrics/index.html
+    pub fn metrics(&self) -> PoolMetrics {
+        let stats = self.stats();
+        PoolMetrics {
+            pool_size: self.get_pool_size(),
+            max_size: self.get_max_size(),
+            retained_bytes: self.retained_bytes(),
+            live: self.live_count(),
+            stats,
+        }
     }
 
     /// Test if the pool is currently full.
     pub fn is_full(&self) -> bool {
-        self.deref()
-            .map(|p| p.get_pool_size() >= p.get_max_size())
-            .unwrap_or(true)
+        self.deref().map(|p| p.is_full()).unwrap_or(true)
     }
 
     /// Fill the pool with empty allocations.
@@ -116,17 +1051,54 @@ impl<A> Pool<A> {
     /// assert_eq!(1024, pool.get_pool_size());
     /// ```
     pub fn fill(&self) {
-        if let Some(inner) = self.deref() {
-            while inner.get_max_size() > inner.get_pool_size() {
-                let chunk = unsafe {
-                    std::alloc::alloc(std::alloc::Layout::from_size_align_unchecked(
-                        std::mem::size_of::<RefBox<A>>(),
-                        std::mem::align_of::<RefBox<A>>(),
-                    ))
-                };
-                self.push(ElementPointer::wrap(chunk.cast()));
+        if let Some(inner) = self.inner.get_ptr_checked() {
+            unsafe {
+                while !(*inner).is_full() && PoolInner::acquire(inner) {
+                    let chunk = (*inner)
+                        .alloc()
+                        .unwrap_or_else(|_| handle_alloc_error((*inner).layout));
+                    let chunk = (*inner).prepare(chunk);
+                    (*inner).push(chunk);
+                }
+            }
+        }
+    }
+
+    /// Fill the pool with empty allocations, stopping if the system allocator
+    /// runs out of memory.
+    ///
+    /// This works like [`fill()`][fill], except that rather than aborting
+    /// when an allocation fails, it stops and returns an error. The chunks
+    /// which were allocated before the failure remain in the pool.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use refpool::Pool;
+    /// let pool: Pool<usize> = Pool::new(1024);
+    /// pool.try_fill().expect("out of memory");
+    /// assert!(pool.is_full());
+    /// ```
+    ///
+    /// [fill]: #method.fill
+    pub fn try_fill(&self) -> Result<(), AllocError> {
+        if let Some(inner) = self.inner.get_ptr_checked() {
+            unsafe {
+                while !(*inner).is_full() && PoolInner::acquire(inner) {
+                    match (*inner).alloc() {
+                        Ok(chunk) => {
+                            let chunk = (*inner).prepare(chunk);
+                            (*inner).push(chunk)
+                        }
+                        Err(error) => {
+                            (*inner).release(1);
+                            return Err(error);
+                        }
+                    }
+                }
             }
         }
+        Ok(())
     }
 
     /// Fill the pool and return it.
@@ -155,15 +1127,64 @@ impl<A> Pool<A> {
         self
     }
 
+    /// Move up to `count` free chunks of memory from this pool into another
+    /// pool, returning the number of chunks moved.
+    ///
+    /// The other pool can be for a different type, as long as its chunks have
+    /// the same size and alignment, which lets you move warm memory between
+    /// pools without handing it back to the system allocator. Fewer than
+    /// `count` chunks are moved if this pool runs out or the other pool fills
+    /// up.
+    ///
+    /// With the `locked-memory` feature flag, no chunks are moved between a
+    /// pool built with [`PoolBuilder::lock_memory(true)`][lock_memory] and
+    /// one built without it, as locked chunks must stay locked until they're
+    /// freed by a pool which knows to unlock them.
+    ///
+    /// # Panics
+    ///
+    /// This panics if the two pools' chunks don't have the same size and
+    /// alignment.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use refpool::Pool;
+    /// let u64_pool: Pool<u64> = Pool::new(1024).filled();
+    /// let i64_pool: Pool<i64> = Pool::new(16);
+    /// assert_eq!(16, u64_pool.transfer_to(&i64_pool, 100));
+    /// assert_eq!(1008, u64_pool.get_pool_size());
+    /// assert_eq!(16, i64_pool.get_pool_size());
+    /// ```
+    ///
+    /// [lock_memory]: struct.PoolBuilder.html#method.lock_memory
+    pub fn transfer_to<B>(&self, other: &Pool<B>, count: usize) -> usize {
+        assert!(
+            self.chunk_layout() == other.chunk_layout(),
+            "Pool::transfer_to: pools have different chunk layouts"
+        );
+        match (self.inner.get_ptr_checked(), other.inner.get_ptr_checked()) {
+            (Some(from), Some(to)) if from.cast::<()>() != to.cast::<()>() => unsafe {
+                PoolInner::transfer_to(from, to, count)
+            },
+            _ => 0,
+        }
+    }
+
     /// Convert a pool handle for type `A` into a handle for type `B`.
     ///
-    /// The types `A` and `B` must have the same size and alignment, as
-    /// per [`std::mem::size_of`][size_of] and
-    /// [`std::mem::align_of`][align_of], or this method will panic.
+    /// The types `A` and `B` must have the same size, as per
+    /// [`std::mem::size_of`][size_of], and the alignment of `B`, as per
+    /// [`std::mem::align_of`][align_of], must be no stricter than the pool's
+    /// [`alignment()`][alignment], or this method will panic. It will also
+    /// panic if the pool was built with [`PoolBuilder::recycle()`][recycle],
+    /// as its memory isn't empty.
     ///
     /// This lets you use the same pool to construct values of different
     /// types, as long as they are of the same size and alignment, so
-    /// they can reuse each others' memory allocations.
+    /// they can reuse each others' memory allocations. A pool built with
+    /// [`Pool::with_alignment()`][with_alignment] keeps its alignment when
+    /// it's cast, and can be cast to any type which fits its chunks.
     ///
     /// # Examples
     ///
@@ -180,17 +1201,143 @@ impl<A> Pool<A> {
     ///
     /// [size_of]: https://doc.rust-lang.org/std/mem/fn.size_of.html
     /// [align_of]: https://doc.rust-lang.org/std/mem/fn.align_of.html
+    /// [alignment]: #method.alignment
+    /// [with_alignment]: #method.with_alignment
+    /// [recycle]: struct.PoolBuilder.html#method.recycle
     pub fn cast<B>(&self) -> Pool<B> {
         assert!(std::mem::size_of::<A>() == std::mem::size_of::<B>());
-        assert!(std::mem::align_of::<A>() >= std::mem::align_of::<B>());
+        assert!(
+            self.fits::<B>(),
+            "Pool::cast: the pool's chunks aren't aligned for the target type"
+        );
+        assert!(
+            !self.is_recycling(),
+            "Pool::cast: can't cast a pool which recycles values"
+        );
+        unsafe { self.cast_unchecked() }
+    }
+
+    /// Turn a pool handle for type `A` into a handle for type `B`.
+    ///
+    /// This works like [`cast()`][cast], with the same requirements, except
+    /// that it consumes the handle instead of making a new one, which is useful
+    /// when you're permanently changing the type of a pool.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use refpool::{Pool, PoolRef};
+    /// let u64_pool: Pool<u64> = Pool::new(1024);
+    /// let i64_pool: Pool<i64> = u64_pool.cast_into();
+    /// let number = PoolRef::new(&i64_pool, -1337);
+    /// # assert_eq!(-1337, *number);
+    /// ```
+    ///
+    /// [cast]: #method.cast
+    pub fn cast_into<B>(self) -> Pool<B> {
+        assert!(std::mem::size_of::<A>() == std::mem::size_of::<B>());
+        assert!(
+            self.fits::<B>(),
+            "Pool::cast_into: the pool's chunks aren't aligned for the target type"
+        );
+        assert!(
+            !self.is_recycling(),
+            "Pool::cast_into: can't cast a pool which recycles values"
+        );
+        match self.inner.get_ptr_checked() {
+            Some(ptr) => {
+                std::mem::forget(self);
+                Pool {
+                    inner: PoolPointer::wrap(ptr.cast()),
+                }
+            }
+            None => Pool::new(0),
+        }
+    }
+
+    /// Convert a pool handle for type `A` into a handle for type `B`, if their
+    /// layouts are compatible.
+    ///
+    /// This works like [`cast()`][cast], except that instead of panicking it
+    /// returns a [`LayoutError`][LayoutError] if the types don't have
+    /// compatible sizes and alignments, or if the pool recycles values.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use refpool::Pool;
+    /// let pool: Pool<u64> = Pool::new(1024);
+    /// assert!(pool.try_cast::<i64>().is_ok());
+    /// assert!(pool.try_cast::<u32>().is_err());
+    /// ```
+    ///
+    /// [cast]: #method.cast
+    /// [LayoutError]: struct.LayoutError.html
+    pub fn try_cast<B>(&self) -> Result<Pool<B>, LayoutError> {
+        if std::mem::size_of::<A>() != std::mem::size_of::<B>()
+            || !self.fits::<B>()
+            || self.is_recycling()
+        {
+            Err(LayoutError)
+        } else {
+            Ok(unsafe { self.cast_unchecked() })
+        }
+    }
+
+    /// Convert a pool handle for type `A` into a handle for type `B`, where
+    /// `A` is known to have a compatible layout through the
+    /// [`SameLayout`][SameLayout] trait.
+    ///
+    /// # Panics
+    ///
+    /// This panics if the pool was built with
+    /// [`PoolBuilder::recycle()`][recycle], as its memory isn't empty.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use refpool::Pool;
+    /// let pool: Pool<u64> = Pool::new(1024);
+    /// let signed: Pool<i64> = pool.cast_checked();
+    /// ```
+    ///
+    /// [SameLayout]: trait.SameLayout.html
+    /// [recycle]: struct.PoolBuilder.html#method.recycle
+    pub fn cast_checked<B>(&self) -> Pool<B>
+    where
+        A: SameLayout<B>,
+    {
+        assert!(
+            !self.is_recycling(),
+            "Pool::cast_checked: can't cast a pool which recycles values"
+        );
+        unsafe { self.cast_unchecked() }
+    }
+
+    /// Test whether the pool's chunks are laid out to hold a `RefBox<B>`, which
+    /// for types of the same size comes down to their alignment.
+    fn fits<B>(&self) -> bool {
+        let layout = Layout::new::<RefBox<B>>();
+        layout.align() <= self.alignment() && layout.size() <= self.chunk_size()
+    }
 
+    /// Convert a pool handle without checking that the types are compatible.
+    unsafe fn cast_unchecked<B>(&self) -> Pool<B> {
         if let Some(ptr) = self.inner.get_ptr_checked() {
             let inner: *mut PoolInner<B> = ptr.cast();
-            unsafe { (*inner).make_ref() }
+            (*inner).make_ref()
         } else {
             Pool::new(0)
         }
     }
+
+    /// Test whether the pool was built with
+    /// [`PoolBuilder::recycle()`][recycle].
+    ///
+    /// [recycle]: struct.PoolBuilder.html#method.recycle
+    pub(crate) fn is_recycling(&self) -> bool {
+        self.deref().map(|p| p.recycler.is_some()).unwrap_or(false)
+    }
 }
 
 impl<A> Clone for Pool<A> {
@@ -207,12 +1354,19 @@ impl<A> Drop for Pool<A> {
     fn drop(&mut self) {
         if let Some(ptr) = self.inner.get_ptr_checked() {
             if unsafe { (*ptr).dec() } == 1 {
-                std::mem::drop(unsafe { Box::from_raw(ptr) });
+                unsafe { PoolInner::destroy(ptr) };
             }
         }
     }
 }
 
+// A pool is a handle to a list of free chunks and some counters, none of
+// which can be left in a state observably broken by a panic: the worst a
+// panicking destructor or callback can do is leak a chunk.
+impl<A> UnwindSafe for Pool<A> {}
+impl<A> RefUnwindSafe for Pool<A> {}
+impl<A> Unpin for Pool<A> {}
+
 impl<A> Debug for Pool<A> {
     /// Debug implementation for `Pool`.
     ///
@@ -239,26 +1393,118 @@ impl<A> Debug for Pool<A> {
 pub(crate) struct PoolInner<A> {
     count: usize,
     max_size: usize,
-    stack: Vec<ElementPointer<A>>,
+    layout: Layout,
+    #[cfg(feature = "locked-memory")]
+    locked: bool,
+    stack: Storage,
+    zero_on_return: bool,
+    stats: Option<PoolStats>,
+    #[cfg(feature = "hdrhistogram")]
+    latency: Option<PoolLatency>,
+    live: Option<usize>,
+    overflow: OverflowPolicy,
+    group: Option<PoolGroup>,
+    adaptive: Option<Adaptive>,
+    idle: Option<IdleTrim>,
+    speculation: Option<Speculation>,
+    #[cfg(feature = "debug-leaks")]
+    registry: LiveRegistry,
+    #[cfg(feature = "trace-alloc")]
+    alloc_sites: AllocSites,
+    recycler: Option<Recycler<A>>,
+    #[cfg(debug_assertions)]
+    thread: ThreadId,
 }
 
 impl<A> PoolInner<A> {
-    fn new(max_size: usize) -> Self {
-        Self {
-            count: Default::default(),
-            max_size,
-            stack: Stack::stack_new(max_size),
+    /// Allocate a pool as configured by `builder`, along with its inline
+    /// storage, if it has any, which goes in the same allocation right after
+    /// the pool.
+    pub(crate) fn allocate(builder: PoolBuilder<A>) -> Pool<A> {
+        let (layout, offset) = Self::allocation_layout(builder.inline_storage);
+        unsafe {
+            let ptr = alloc(layout);
+            if ptr.is_null() {
+                handle_alloc_error(layout);
+            }
+            let stack = match builder.inline_storage {
+                Some(capacity) => Storage::Inline(InlineStack::new(
+                    NonNull::new_unchecked(ptr.add(offset).cast()),
+                    capacity,
+                )),
+                None => Storage::new(builder.max_size, builder.storage),
+            };
+            let ptr = ptr.cast::<Self>();
+            ptr.write(Self::from_builder(builder, stack));
+            (*ptr).inc();
+            Pool {
+                inner: PoolPointer::wrap(ptr),
+            }
         }
     }
 
-    fn into_ref(mut self: Box<Self>) -> Pool<A> {
-        self.inc();
-        Pool {
-            inner: PoolPointer::wrap(Box::into_raw(self)),
+    /// Drop the pool and free its allocation.
+    unsafe fn destroy(this: *mut Self) {
+        let (layout, _) = Self::allocation_layout((*this).stack.inline_capacity());
+        this.drop_in_place();
+        dealloc(this.cast(), layout);
+    }
+
+    /// Get the layout of a pool's allocation with room for `inline` chunk
+    /// pointers after it, along with the offset of the first one.
+    fn allocation_layout(inline: Option<usize>) -> (Layout, usize) {
+        let pool = Layout::new::<Self>();
+        match inline {
+            Some(capacity) => {
+                let (layout, offset) = Layout::array::<MaybeUninit<NonNull<u8>>>(capacity)
+                    .and_then(|slots| pool.extend(slots))
+                    .expect("Pool::fixed: pool too large");
+                (layout.pad_to_align(), offset)
+            }
+            None => (pool, pool.size()),
+        }
+    }
+
+    fn from_builder(builder: PoolBuilder<A>, stack: Storage) -> Self {
+        Self {
+            count: Default::default(),
+            max_size: builder.max_size,
+            layout: builder.chunk_layout(),
+            #[cfg(feature = "locked-memory")]
+            locked: builder.lock_memory,
+            stack,
+            zero_on_return: builder.zero_on_return,
+            stats: if builder.stats {
+                Some(PoolStats::default())
+            } else {
+                None
+            },
+            #[cfg(feature = "hdrhistogram")]
+            latency: if builder.latency {
+                Some(PoolLatency::new())
+            } else {
+                None
+            },
+            adaptive: builder
+                .adaptive
+                .map(|cap| Adaptive::new(builder.max_size, cap)),
+            idle: builder.idle_timeout.map(IdleTrim::new),
+            speculation: None,
+            #[cfg(feature = "debug-leaks")]
+            registry: LiveRegistry::default(),
+            #[cfg(feature = "trace-alloc")]
+            alloc_sites: AllocSites::default(),
+            recycler: builder.recycler,
+            #[cfg(debug_assertions)]
+            thread: std::thread::current().id(),
+            live: if builder.live_count { Some(0) } else { None },
+            overflow: builder.overflow,
+            group: builder.group,
         }
     }
 
     fn make_ref(&mut self) -> Pool<A> {
+        self.check_thread();
         self.inc();
         Pool {
             inner: PoolPointer::wrap(self),
@@ -270,9 +1516,14 @@ impl<A> PoolInner<A> {
         self.max_size
     }
 
+    /// Test whether the pool can't take any more chunks without growing.
+    fn is_full(&self) -> bool {
+        self.get_pool_size() >= self.max_size || self.stack.is_full()
+    }
+
     /// Get the current size of the pool.
     fn get_pool_size(&self) -> usize {
-        self.stack.stack_len()
+        self.stack.len()
     }
 
     #[inline(always)]
@@ -285,30 +1536,326 @@ impl<A> PoolInner<A> {
         self.count.dec()
     }
 
-    fn pop(&mut self) -> Option<Box<MaybeUninit<RefBox<A>>>> {
-        self.stack.stack_pop().map(|value_ptr| {
-            let box_ptr = value_ptr.cast::<MaybeUninit<RefBox<A>>>();
-            unsafe { Box::from_raw(box_ptr.as_ptr()) }
-        })
+    /// Test whether the pool can take back a chunk, applying its overflow
+    /// policy if it's full.
+    ///
+    /// An overflow callback is free to drop values allocated from this very
+    /// pool, which would re-enter it, so this takes a pointer rather than a
+    /// reference, and doesn't hold a reference to the pool while the callback
+    /// runs. The policy is taken out of the pool for the duration, so that
+    /// the nested returns just discard their chunks if the pool is full.
+    unsafe fn has_room(this: *mut Self) -> bool {
+        let max_size = {
+            let inner = &mut *this;
+            if inner.stack.is_full() {
+                return false;
+            }
+            if inner.get_pool_size() < inner.max_size {
+                return PoolInner::acquire(this);
+            }
+            inner.max_size
+        };
+        let mut overflow = std::mem::replace(&mut (*this).overflow, OverflowPolicy::Discard);
+        let new_size = overflow.grow(max_size);
+        let inner = &mut *this;
+        inner.overflow = overflow;
+        if new_size > inner.max_size {
+            inner.max_size = new_size;
+        }
+        // The callback might have changed the pool, so check it again.
+        if inner.stack.is_full() || inner.get_pool_size() >= inner.max_size {
+            return false;
+        }
+        PoolInner::acquire(this)
+    }
+
+    /// Make room in the pool's group for another chunk, if it's in a group.
+    ///
+    /// The group may trim other pools to make room, dropping the values
+    /// they're recycling, which can return memory to this pool, so this takes
+    /// a pointer rather than a reference, and doesn't hold a reference to the
+    /// pool while the group is at work.
+    unsafe fn acquire(this: *mut Self) -> bool {
+        let group = match (*this).group {
+            Some(ref group) => group.clone(),
+            None => return true,
+        };
+        group.acquire(this as *const (), (*this).layout.size())
+    }
+
+    /// Tell the pool's group that `chunks` chunks have left the pool.
+    fn release(&mut self, chunks: usize) {
+        if let Some(ref group) = self.group {
+            group.release(chunks * self.layout.size());
+        }
+    }
+
+    /// Release chunks back to the system allocator until at least `bytes`
+    /// bytes have been freed or the pool is empty, returning the number of
+    /// bytes freed.
+    ///
+    /// This doesn't notify the pool's group, because it's called by the group.
+    unsafe fn trim_erased(pool: *const (), bytes: usize) -> usize {
+        let inner = pool as *mut Self;
+        let mut freed = 0;
+        while freed < bytes {
+            match (*inner).stack.pop() {
+                Some(chunk) => PoolInner::dealloc(inner, chunk),
+                None => break,
+            }
+            freed += (*inner).layout.size();
+        }
+        freed
+    }
+
+    /// Hand a chunk from the pool back to the system allocator.
+    ///
+    /// Dropping a recycled value can return memory to any pool, including
+    /// this one, so this doesn't hold a reference to the pool while it does.
+    unsafe fn dealloc(this: *const Self, chunk: ElementPointer<A>) {
+        if (*this).recycler.is_some() {
+            std::ptr::drop_in_place(&mut (*chunk.get_ptr()).value);
+        }
+        (*this).dealloc_chunk(chunk);
+    }
+
+    /// Allocate a chunk from the system allocator, locking it into memory if
+    /// the pool was asked to.
+    fn alloc(&self) -> Result<ElementPointer<A>, AllocError> {
+        let chunk = alloc_chunk(self.layout)?;
+        #[cfg(feature = "locked-memory")]
+        {
+            if self.locked && !unsafe { locked::lock(chunk.as_ptr().cast(), self.layout.size()) } {
+                unsafe { dealloc_chunk(chunk, self.layout) };
+                return Err(AllocError);
+            }
+        }
+        Ok(chunk)
+    }
+
+    /// Hand an empty chunk back to the system allocator, zeroing and
+    /// unlocking it first if it's locked.
+    unsafe fn dealloc_chunk(&self, chunk: ElementPointer<A>) {
+        #[cfg(feature = "locked-memory")]
+        {
+            if self.locked {
+                locked::unlock(chunk.as_ptr().cast(), self.layout.size());
+            }
+        }
+        dealloc_chunk(chunk, self.layout);
+    }
+
+    /// Prepare a newly allocated chunk for going into the pool, initialising
+    /// its value if the pool is recycling values.
+    unsafe fn prepare(&self, chunk: ElementPointer<A>) -> ElementPointer<A> {
+        if let Some(recycler) = self.recycler {
+            let value = &mut (*chunk.get_ptr()).value as *mut A as *mut MaybeUninit<A>;
+            (recycler.init)(&mut *value);
+        }
+        chunk
+    }
+
+    #[inline(always)]
+    fn record(&mut self, f: impl FnOnce(&mut PoolStats)) {
+        if let Some(ref mut stats) = self.stats {
+            f(stats);
+        }
+    }
+
+    /// Assert that the pool is being used from the thread that created it.
+    ///
+    /// This is only checked in debug builds.
+    #[inline(always)]
+    fn check_thread(&self) {
+        #[cfg(debug_assertions)]
+        {
+            let current = std::thread::current().id();
+            assert!(
+                self.thread == current,
+                "refpool: a Pool created on thread {:?} was used from thread {:?}; pools must only be used from the thread that created them",
+                self.thread,
+                current
+            );
+        }
+    }
+
+    /// Take a chunk from the pool, dropping its value if the pool is
+    /// recycling values.
+    fn take(&mut self) -> Option<ElementPointer<A>> {
+        let result = self.take_chunk();
+        if let (Some(chunk), Some(_)) = (result, self.recycler) {
+            unsafe { value_ptr(chunk).drop_in_place() };
+        }
+        result
+    }
+
+    /// Take a chunk with an initialised value from a recycling pool.
+    fn take_recycled(&mut self) -> Option<ElementPointer<A>> {
+        self.recycler?;
+        self.take_chunk()
+    }
+
+    /// Pop a chunk off the stack, if there is one.
+    fn take_chunk(&mut self) -> Option<ElementPointer<A>> {
+        self.check_thread();
+        let result = self.stack.pop::<A>();
+        if result.is_some() {
+            self.release(1);
+            self.record(|stats| stats.hits += 1);
+            self.trim_idle(true);
+        }
+        result
+    }
+
+    /// Pop a chunk off the stack, recording a miss if the caller is going to
+    /// have to allocate instead.
+    fn pop(&mut self) -> Option<ElementPointer<A>> {
+        let result = self.take();
+        if result.is_none() {
+            self.record(|stats| stats.misses += 1);
+        }
+        result
     }
 
     fn push(&mut self, handle: ElementPointer<A>) {
-        self.stack.stack_push(handle);
+        self.stack.push(handle);
+        self.trim_idle(false);
+    }
+
+    /// Release any chunks which have been sitting unused in the pool for
+    /// longer than the idle timeout, after chunks have been `taken` from the
+    /// pool or returned to it.
+    #[inline(always)]
+    fn trim_idle(&mut self, taken: bool) {
+        let pool_size = self.get_pool_size();
+        if let Some(idle) = self
+            .idle
+            .as_mut()
+            .and_then(|idle| idle.observe(pool_size, taken))
+        {
+            self.free(idle);
+        }
+    }
+
+    /// Deallocate `count` chunks from the pool.
+    fn free(&mut self, count: usize) {
+        let mut released = 0;
+        while released < count {
+            match self.stack.pop() {
+                Some(chunk) => unsafe { PoolInner::dealloc(self, chunk) },
+                None => break,
+            }
+            released += 1;
+        }
+        self.release(released);
+    }
+
+    /// Called when a chunk leaves the pool's control, either from the stack or
+    /// from the system allocator.
+    #[inline(always)]
+    fn checked_out(&mut self, chunk: *const RefBox<A>) {
+        if let Some(ref mut live) = self.live {
+            *live += 1;
+        }
+        if let Some(ref mut speculation) = self.speculation {
+            speculation.allocated(chunk.cast());
+        }
+        #[cfg(feature = "debug-leaks")]
+        self.registry.allocated(chunk.cast());
+        if let Some(size) = self.adaptive.as_mut().and_then(Adaptive::allocated) {
+            self.resize(size);
+        }
+    }
+
+    /// Called when a chunk comes back under the pool's control, whether it's
+    /// going to be kept or discarded.
+    #[inline(always)]
+    fn checked_in(&mut self, chunk: *const RefBox<A>, discarded: bool) {
+        if let Some(ref mut live) = self.live {
+            *live -= 1;
+        }
+        if let Some(ref mut adaptive) = self.adaptive {
+            adaptive.freed();
+        }
+        if let Some(ref mut speculation) = self.speculation {
+            speculation.freed(chunk.cast(), discarded);
+        }
+        #[cfg(feature = "debug-leaks")]
+        self.registry.freed(chunk.cast());
+    }
+
+    /// Change the maximum size of the pool, releasing any chunks in excess of
+    /// the new size.
+    fn resize(&mut self, max_size: usize) {
+        self.max_size = max_size;
+        self.free(self.get_pool_size().saturating_sub(max_size));
+    }
+
+    /// Zero a chunk's memory if the pool was asked to.
+    fn scrub(&self, handle: ElementPointer<A>) {
+        if self.zero_on_return && self.recycler.is_none() {
+            unsafe {
+                handle
+                    .as_ptr()
+                    .cast::<u8>()
+                    .write_bytes(0, self.layout.size())
+            };
+        }
+    }
+
+    fn recycle(&mut self, handle: ElementPointer<A>) {
+        self.check_thread();
+        self.scrub(handle);
+        self.record(|stats| stats.returns += 1);
+        self.checked_in(handle.get_ptr(), false);
+        self.push(handle);
+    }
+
+    /// Move up to `count` free chunks into another pool, returning the number
+    /// of chunks moved.
+    ///
+    /// This takes pointers for the same reason as
+    /// [`has_room()`](#method.has_room): the target pool's overflow callback
+    /// might re-enter either pool.
+    unsafe fn transfer_to<B>(from: *mut Self, to: *mut PoolInner<B>, count: usize) -> usize {
+        (*from).check_thread();
+        (*to).check_thread();
+        #[cfg(feature = "locked-memory")]
+        {
+            if (*from).locked != (*to).locked {
+                return 0;
+            }
+        }
+        let mut moved = 0;
+        while moved < count && (*from).get_pool_size() > 0 && PoolInner::has_room(to) {
+            let chunk = match (*from).stack.pop::<A>() {
+                Some(chunk) => chunk,
+                None => break,
+            };
+            if (*from).recycler.is_some() {
+                (*chunk.as_ptr()).value_as_mut_ptr().drop_in_place();
+            }
+            let chunk = chunk.cast::<RefBox<B>>();
+            (*to).scrub(chunk);
+            (*to).push((*to).prepare(chunk));
+            moved += 1;
+        }
+        (*from).release(moved);
+        (*from).trim_idle(true);
+        moved
     }
 }
 
 impl<A> Drop for PoolInner<A> {
     fn drop(&mut self) {
-        while let Some(chunk) = self.stack.stack_pop() {
-            unsafe {
-                std::alloc::dealloc(
-                    chunk.as_ptr().cast(),
-                    std::alloc::Layout::from_size_align_unchecked(
-                        std::mem::size_of::<RefBox<A>>(),
-                        std::mem::align_of::<RefBox<A>>(),
-                    ),
-                );
-            }
+        if let Some(ref group) = self.group {
+            group.leave(
+                self as *const _ as *const (),
+                self.get_pool_size() * self.layout.size(),
+            );
+        }
+        while let Some(chunk) = self.stack.pop() {
+            unsafe { PoolInner::dealloc(self, chunk) };
         }
     }
 }
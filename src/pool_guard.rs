@@ -0,0 +1,101 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::fmt::{Debug, Error, Formatter};
+use std::ops::{Deref, DerefMut};
+
+use crate::box_handle::PoolBox;
+use crate::pool::Pool;
+use crate::PoolDefault;
+
+/// An object checked out of a [`Pool`][Pool].
+///
+/// You get one of these from [`Pool::checkout()`][Pool::checkout]. It
+/// dereferences to the object, and gives it back to the pool when it's
+/// dropped.
+///
+/// This is meant to be used with a pool built with
+/// [`PoolBuilder::recycle()`][recycle], which gives you classic object pool
+/// semantics: the pool holds on to a set of initialised objects, such as
+/// buffers or connections, and checking one out and returning it neither
+/// constructs nor destroys anything, apart from resetting the object using
+/// [`PoolRecycle::reset()`][reset] when it's returned. With any other pool,
+/// objects are default constructed on checkout and dropped on return, just
+/// like a [`PoolBox`][PoolBox].
+///
+/// # Examples
+///
+/// ```rust
+/// # use refpool::{Pool, PoolGuard};
+/// let pool: Pool<String> = Pool::builder().max_size(4).recycle().build().filled();
+/// {
+///     let mut buffer = pool.checkout();
+///     buffer.push_str("Hello Joe!");
+///     assert_eq!(3, pool.get_pool_size());
+/// }
+/// assert_eq!(4, pool.get_pool_size());
+/// assert!(pool.checkout().is_empty());
+/// ```
+///
+/// [Pool]: struct.Pool.html
+/// [Pool::checkout]: struct.Pool.html#method.checkout
+/// [PoolBox]: struct.PoolBox.html
+/// [recycle]: struct.PoolBuilder.html#method.recycle
+/// [reset]: trait.PoolRecycle.html#tymethod.reset
+pub struct PoolGuard<A> {
+    object: PoolBox<A>,
+}
+
+impl<A> PoolGuard<A> {
+    #[cfg_attr(feature = "trace-alloc", track_caller)]
+    pub(crate) fn new(pool: &Pool<A>) -> Self
+    where
+        A: PoolDefault,
+    {
+        PoolGuard {
+            object: PoolBox::default(pool),
+        }
+    }
+
+    /// Keep the object, turning the guard into a [`PoolBox`][PoolBox].
+    ///
+    /// [PoolBox]: struct.PoolBox.html
+    pub fn into_box(this: Self) -> PoolBox<A> {
+        this.object
+    }
+}
+
+impl<A> Deref for PoolGuard<A> {
+    type Target = A;
+    fn deref(&self) -> &Self::Target {
+        &self.object
+    }
+}
+
+impl<A> DerefMut for PoolGuard<A> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.object
+    }
+}
+
+impl<A> AsRef<A> for PoolGuard<A> {
+    fn as_ref(&self) -> &A {
+        self.deref()
+    }
+}
+
+impl<A> AsMut<A> for PoolGuard<A> {
+    fn as_mut(&mut self) -> &mut A {
+        self.deref_mut()
+    }
+}
+
+impl<A> Debug for PoolGuard<A>
+where
+    A: Debug,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        f.debug_tuple("PoolGuard").field(self.deref()).finish()
+    }
+}
@@ -0,0 +1,211 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::cell::RefCell;
+use std::fmt::{Debug, Error, Formatter};
+use std::rc::Rc;
+
+use proptest::bool::weighted;
+use proptest::strategy::{NewTree, Strategy, ValueTree};
+use proptest::test_runner::{Reason, TestRunner};
+
+use crate::pool::Pool;
+use crate::ref_handle::PoolRef;
+
+/// The number of previously generated values a
+/// [`PoolRefStrategy`][PoolRefStrategy] remembers for sharing.
+///
+/// [PoolRefStrategy]: struct.PoolRefStrategy.html
+const SHARED_HISTORY: usize = 64;
+
+/// Construct a [`Strategy`][Strategy] which generates [`PoolRef`][PoolRef]s
+/// allocated from `pool`, with values generated by `inner`.
+///
+/// Some of the time, instead of allocating a new value, the strategy hands
+/// out a clone of a reference it generated earlier, so that the values
+/// generated for a test share allocations the way a real pooled data
+/// structure would. By default, one in four values is shared; use
+/// [`PoolRefStrategy::sharing()`][sharing] to change that.
+///
+/// # Examples
+///
+/// ```rust
+/// # use refpool::{pool_ref_strategy, Pool, PoolRef};
+/// # use proptest::prelude::*;
+/// let pool: Pool<u32> = Pool::new(64);
+/// let strategy = proptest::collection::vec(pool_ref_strategy(&pool, any::<u32>()), 0..32);
+/// let mut runner = proptest::test_runner::TestRunner::default();
+/// runner
+///     .run(&strategy, |values| {
+///         for value in &values {
+///             prop_assert!(PoolRef::strong_count(value) >= 1);
+///         }
+///         Ok(())
+///     })
+///     .unwrap();
+/// ```
+///
+/// [Strategy]: https://docs.rs/proptest/1/proptest/strategy/trait.Strategy.html
+/// [PoolRef]: struct.PoolRef.html
+/// [sharing]: struct.PoolRefStrategy.html#method.sharing
+pub fn pool_ref_strategy<S>(pool: &Pool<S::Value>, inner: S) -> PoolRefStrategy<S>
+where
+    S: Strategy,
+{
+    PoolRefStrategy {
+        pool: pool.clone(),
+        inner,
+        sharing: 0.25,
+        history: Rc::new(RefCell::new(Vec::new())),
+    }
+}
+
+/// A [`Strategy`][Strategy] which generates [`PoolRef`][PoolRef]s.
+///
+/// You get one of these from [`pool_ref_strategy()`][pool_ref_strategy].
+///
+/// [Strategy]: https://docs.rs/proptest/1/proptest/strategy/trait.Strategy.html
+/// [PoolRef]: struct.PoolRef.html
+/// [pool_ref_strategy]: fn.pool_ref_strategy.html
+pub struct PoolRefStrategy<S: Strategy> {
+    pool: Pool<S::Value>,
+    inner: S,
+    sharing: f64,
+    history: Rc<RefCell<Vec<PoolRef<S::Value>>>>,
+}
+
+impl<S: Strategy> PoolRefStrategy<S> {
+    /// Set the probability, between 0 and 1, that a generated value is a
+    /// clone of a previously generated reference rather than a new
+    /// allocation.
+    ///
+    /// A probability of 0 means every value gets its own allocation.
+    pub fn sharing(mut self, probability: f64) -> Self {
+        self.sharing = probability;
+        self
+    }
+}
+
+/// Pick an index below `len` using the runner's random number generator.
+fn pick(runner: &mut TestRunner, len: usize) -> Result<usize, Reason> {
+    Ok((0..len).new_tree(runner)?.current())
+}
+
+impl<S: Strategy> Strategy for PoolRefStrategy<S> {
+    type Tree = PoolRefValueTree<S::Tree>;
+    type Value = PoolRef<S::Value>;
+
+    fn new_tree(&self, runner: &mut TestRunner) -> NewTree<Self> {
+        let len = self.history.borrow().len();
+        if len > 0 && weighted(self.sharing).new_tree(runner)?.current() {
+            let index = pick(runner, len)?;
+            let value = self.history.borrow()[index].clone();
+            return Ok(PoolRefValueTree(TreeState::Shared(value)));
+        }
+        let tree = self.inner.new_tree(runner)?;
+        let value = PoolRef::new(&self.pool, tree.current());
+        let mut history = self.history.borrow_mut();
+        if history.len() < SHARED_HISTORY {
+            history.push(value.clone());
+        } else {
+            let index = pick(runner, SHARED_HISTORY)?;
+            history[index] = value.clone();
+        }
+        Ok(PoolRefValueTree(TreeState::Fresh {
+            pool: self.pool.clone(),
+            tree,
+            value,
+        }))
+    }
+}
+
+impl<S: Strategy> Clone for PoolRefStrategy<S>
+where
+    S: Clone,
+{
+    fn clone(&self) -> Self {
+        PoolRefStrategy {
+            pool: self.pool.clone(),
+            inner: self.inner.clone(),
+            sharing: self.sharing,
+            history: self.history.clone(),
+        }
+    }
+}
+
+impl<S: Strategy> Debug for PoolRefStrategy<S> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        f.debug_struct("PoolRefStrategy")
+            .field("pool", &self.pool)
+            .field("inner", &self.inner)
+            .field("sharing", &self.sharing)
+            .finish()
+    }
+}
+
+/// The [`ValueTree`][ValueTree] for a [`PoolRefStrategy`][PoolRefStrategy].
+///
+/// A freshly allocated value shrinks along with its inner value tree, while
+/// a shared value is left as it is, because shrinking it would break the
+/// sharing.
+///
+/// [ValueTree]: https://docs.rs/proptest/1/proptest/strategy/trait.ValueTree.html
+/// [PoolRefStrategy]: struct.PoolRefStrategy.html
+pub struct PoolRefValueTree<T: ValueTree>(TreeState<T>);
+
+enum TreeState<T: ValueTree> {
+    Fresh {
+        pool: Pool<T::Value>,
+        tree: T,
+        value: PoolRef<T::Value>,
+    },
+    Shared(PoolRef<T::Value>),
+}
+
+impl<T: ValueTree> PoolRefValueTree<T> {
+    fn update(&mut self, changed: bool) -> bool {
+        if changed {
+            if let TreeState::Fresh { pool, tree, value } = &mut self.0 {
+                *value = PoolRef::new(pool, tree.current());
+            }
+        }
+        changed
+    }
+}
+
+impl<T: ValueTree> ValueTree for PoolRefValueTree<T> {
+    type Value = PoolRef<T::Value>;
+
+    fn current(&self) -> Self::Value {
+        match &self.0 {
+            TreeState::Fresh { value, .. } => value.clone(),
+            TreeState::Shared(value) => value.clone(),
+        }
+    }
+
+    fn simplify(&mut self) -> bool {
+        let changed = match &mut self.0 {
+            TreeState::Fresh { tree, .. } => tree.simplify(),
+            TreeState::Shared(_) => false,
+        };
+        self.update(changed)
+    }
+
+    fn complicate(&mut self) -> bool {
+        let changed = match &mut self.0 {
+            TreeState::Fresh { tree, .. } => tree.complicate(),
+            TreeState::Shared(_) => false,
+        };
+        self.update(changed)
+    }
+}
+
+impl<T: ValueTree> Debug for PoolRefValueTree<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        match &self.0 {
+            TreeState::Fresh { value, .. } => write!(f, "PoolRefValueTree({:?})", value),
+            TreeState::Shared(value) => write!(f, "PoolRefValueTree(shared {:?})", value),
+        }
+    }
+}
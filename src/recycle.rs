@@ -0,0 +1,142 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+// With the `disable-pool` feature flag, there's no real pool to make use of
+// the crate internals here, only the fake pool which ignores them.
+#![cfg_attr(feature = "disable-pool", allow(dead_code))]
+
+use std::collections::{BTreeMap, BTreeSet, BinaryHeap, HashMap, HashSet, LinkedList, VecDeque};
+use std::hash::{BuildHasher, Hash};
+use std::mem::MaybeUninit;
+use std::path::PathBuf;
+
+use crate::PoolDefault;
+
+/// A trait for resetting a value to its default state in place.
+///
+/// A pool built with [`PoolBuilder::recycle()`][recycle] doesn't drop values
+/// returned to it, but calls [`reset()`][reset] on them and keeps them
+/// around, so that [`PoolRef::default()`][PoolRef::default] and
+/// [`PoolBox::default()`][PoolBox::default] can hand them out again without
+/// having to initialise new values. This is useful for types which own other
+/// resources, such as the heap buffer of a [`Vec`][Vec], which can then be
+/// reused along with the value itself.
+///
+/// # Examples
+///
+#[cfg_attr(feature = "disable-pool", doc = "```ignore")]
+#[cfg_attr(not(feature = "disable-pool"), doc = "```rust")]
+/// # use refpool::{Pool, PoolBox};
+/// let pool: Pool<Vec<u8>> = Pool::builder().max_size(16).recycle().build();
+/// let mut buffer = PoolBox::default(&pool);
+/// buffer.extend_from_slice(b"Hello Joe!");
+/// let capacity = buffer.capacity();
+/// drop(buffer);
+///
+/// let buffer = PoolBox::default(&pool);
+/// assert!(buffer.is_empty());
+/// assert_eq!(capacity, buffer.capacity());
+/// ```
+///
+/// [reset]: #tymethod.reset
+/// [recycle]: struct.PoolBuilder.html#method.recycle
+/// [PoolRef::default]: struct.PoolRef.html#method.default
+/// [PoolBox::default]: struct.PoolBox.html#method.default
+/// [Vec]: https://doc.rust-lang.org/std/vec/struct.Vec.html
+pub trait PoolRecycle: PoolDefault {
+    /// Reset `self` to its default state.
+    ///
+    /// After calling `self.reset()`, the object's state should be equal to
+    /// what `<Self as Default>::default()` would produce, except for things
+    /// like allocated capacity which don't affect equality.
+    fn reset(&mut self);
+}
+
+/// The operations a recycling pool needs on its values.
+pub(crate) struct Recycler<A> {
+    pub(crate) reset: fn(&mut A),
+    pub(crate) init: unsafe fn(&mut MaybeUninit<A>),
+}
+
+impl<A> Recycler<A> {
+    pub(crate) fn new() -> Self
+    where
+        A: PoolRecycle,
+    {
+        Recycler {
+            reset: A::reset,
+            init: A::default_uninit,
+        }
+    }
+}
+
+impl<A> Clone for Recycler<A> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<A> Copy for Recycler<A> {}
+
+impl<A> PoolRecycle for Vec<A> {
+    fn reset(&mut self) {
+        self.clear()
+    }
+}
+
+impl<A> PoolRecycle for VecDeque<A> {
+    fn reset(&mut self) {
+        self.clear()
+    }
+}
+
+impl<A> PoolRecycle for LinkedList<A> {
+    fn reset(&mut self) {
+        self.clear()
+    }
+}
+
+impl<A: Ord> PoolRecycle for BinaryHeap<A> {
+    fn reset(&mut self) {
+        self.clear()
+    }
+}
+
+impl<A: Hash + Eq, S: BuildHasher + Default> PoolRecycle for HashSet<A, S> {
+    fn reset(&mut self) {
+        self.clear()
+    }
+}
+
+impl<A: Hash + Eq, B, S: BuildHasher + Default> PoolRecycle for HashMap<A, B, S> {
+    fn reset(&mut self) {
+        self.clear()
+    }
+}
+
+impl<A: Ord> PoolRecycle for BTreeSet<A> {
+    fn reset(&mut self) {
+        self.clear()
+    }
+}
+
+impl<A: Ord, B> PoolRecycle for BTreeMap<A, B> {
+    fn reset(&mut self) {
+        self.clear()
+    }
+}
+
+impl PoolRecycle for String {
+    fn reset(&mut self) {
+        self.clear()
+    }
+}
+
+impl PoolRecycle for PathBuf {
+    fn reset(&mut self) {
+        let mut path = std::mem::take(self).into_os_string();
+        path.clear();
+        *self = path.into();
+    }
+}
@@ -0,0 +1,140 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::fmt::{Debug, Display, Error, Formatter};
+use std::marker::PhantomData;
+use std::mem::ManuallyDrop;
+use std::ops::Deref;
+
+use crate::pointer::Pointer;
+use crate::ref_handle::PoolRef;
+use crate::types::ElementPointer;
+
+/// A borrowed [`PoolRef`][PoolRef].
+///
+/// This points directly at a pooled value, just like a `PoolRef`, but it
+/// borrows its reference from an existing `PoolRef` instead of holding one of
+/// its own. It's `Copy`, so you can pass it around by value without the
+/// double indirection of a `&PoolRef<A>`, and without touching the reference
+/// count, and you can turn it into a `PoolRef` of its own with
+/// [`to_owned()`][to_owned] when you need to keep the value around.
+///
+/// You get one of these from [`PoolRef::borrow_ref()`][borrow_ref].
+///
+/// # Examples
+///
+/// ```rust
+/// # use refpool::{Pool, PoolRef, PoolRefBorrow};
+/// fn keep_if_big(number: PoolRefBorrow<'_, usize>) -> Option<PoolRef<usize>> {
+///     if *number > 9000 {
+///         Some(number.to_owned())
+///     } else {
+///         None
+///     }
+/// }
+///
+/// let pool: Pool<usize> = Pool::new(1);
+/// let number = PoolRef::new(&pool, 31337);
+/// let kept = keep_if_big(PoolRef::borrow_ref(&number)).unwrap();
+/// assert!(PoolRef::ptr_eq(&number, &kept));
+/// assert_eq!(2, PoolRef::strong_count(&number));
+/// ```
+///
+/// [PoolRef]: struct.PoolRef.html
+/// [to_owned]: #method.to_owned
+/// [borrow_ref]: struct.PoolRef.html#method.borrow_ref
+pub struct PoolRefBorrow<'a, A> {
+    handle: ElementPointer<A>,
+    lifetime: PhantomData<&'a A>,
+}
+
+impl<'a, A> PoolRefBorrow<'a, A> {
+    pub(crate) fn new(pool_ref: &'a PoolRef<A>) -> Self {
+        PoolRefBorrow {
+            handle: pool_ref.handle,
+            lifetime: PhantomData,
+        }
+    }
+
+    /// Construct a new [`PoolRef`][PoolRef] to the borrowed value, increasing
+    /// its reference count.
+    ///
+    /// [PoolRef]: struct.PoolRef.html
+    pub fn to_owned(self) -> PoolRef<A> {
+        let pool_ref = ManuallyDrop::new(PoolRef {
+            handle: self.handle,
+            _type: PhantomData,
+        });
+        (*pool_ref).clone()
+    }
+
+    /// Get a reference to the borrowed value with the lifetime of the
+    /// original [`PoolRef`][PoolRef].
+    ///
+    /// [PoolRef]: struct.PoolRef.html
+    pub fn get(self) -> &'a A {
+        unsafe { &*self.handle.get_ptr() }.value_as_ref()
+    }
+
+    /// Test two borrowed references for pointer equality.
+    pub fn ptr_eq(left: Self, right: Self) -> bool {
+        std::ptr::eq(left.handle.get_ptr(), right.handle.get_ptr())
+    }
+
+    /// Get the current number of [`PoolRef`][PoolRef] references to the
+    /// borrowed value.
+    ///
+    /// [PoolRef]: struct.PoolRef.html
+    pub fn strong_count(this: Self) -> usize {
+        PoolRef::strong_count(&ManuallyDrop::new(PoolRef {
+            handle: this.handle,
+            _type: PhantomData,
+        }))
+    }
+}
+
+impl<'a, A> Clone for PoolRefBorrow<'a, A> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, A> Copy for PoolRefBorrow<'a, A> {}
+
+impl<'a, A> From<&'a PoolRef<A>> for PoolRefBorrow<'a, A> {
+    fn from(pool_ref: &'a PoolRef<A>) -> Self {
+        Self::new(pool_ref)
+    }
+}
+
+impl<'a, A> Deref for PoolRefBorrow<'a, A> {
+    type Target = A;
+    fn deref(&self) -> &Self::Target {
+        self.get()
+    }
+}
+
+impl<'a, A> AsRef<A> for PoolRefBorrow<'a, A> {
+    fn as_ref(&self) -> &A {
+        self.get()
+    }
+}
+
+impl<'a, A> Display for PoolRefBorrow<'a, A>
+where
+    A: Display,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        (**self).fmt(f)
+    }
+}
+
+impl<'a, A> Debug for PoolRefBorrow<'a, A>
+where
+    A: Debug,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        (**self).fmt(f)
+    }
+}
@@ -0,0 +1,248 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::fmt::{Debug, Error, Formatter};
+use std::ops::{Deref, DerefMut};
+use std::ptr::addr_of_mut;
+
+use crate::counter::{Counter, RefCount};
+use crate::pointer::Pointer;
+use crate::pool::Pool;
+use crate::refbox::{value_ptr, RefBox};
+use crate::types::ElementPointer;
+use crate::PoolDefault;
+
+// The reference count word of a `PoolRefCell` holds the strong count in its
+// lower half and the borrow state in its upper half: the number of shared
+// borrows, with the top bit set while the value is mutably borrowed.
+const HALF: u32 = RefCount::BITS / 2;
+const STRONG_MASK: RefCount = (1 << HALF) - 1;
+const READER: RefCount = 1 << HALF;
+const WRITER: RefCount = 1 << (RefCount::BITS - 1);
+const READERS_MASK: RefCount = !STRONG_MASK & !WRITER;
+
+/// A reference counted pointer to a pool allocated value of `A` with interior
+/// mutability.
+///
+/// This is the equivalent of a [`PoolRef`][PoolRef]`<`[`RefCell`][RefCell]`<A>>`,
+/// except that the borrow flag shares a word with the reference count, so it
+/// takes up no extra space, and values come from a plain `Pool<A>`, which
+/// means they can share memory with [`PoolRef`][PoolRef]s of the same type.
+///
+/// Because of the shared word, which is a `usize`, or a `u32` with the
+/// `small-count` feature flag, the number of handles to the same value is
+/// limited to what fits in half of its bits, and the number of simultaneous
+/// shared borrows to what fits in the other half less one bit. With
+/// `small-count`, that's 65535 handles and 32767 borrows. Exceeding either
+/// limit panics.
+///
+/// # Examples
+///
+/// ```rust
+/// # use refpool::{Pool, PoolRefCell};
+/// let pool: Pool<Vec<usize>> = Pool::new(16);
+/// let list = PoolRefCell::new(&pool, vec![1, 2]);
+/// let same_list = list.clone();
+/// list.borrow_mut().push(3);
+/// assert_eq!(vec![1, 2, 3], *same_list.borrow());
+///
+/// let reading = list.borrow();
+/// assert!(same_list.try_borrow_mut().is_none());
+/// ```
+///
+/// [PoolRef]: struct.PoolRef.html
+/// [RefCell]: https://doc.rust-lang.org/std/cell/struct.RefCell.html
+pub struct PoolRefCell<A> {
+    handle: ElementPointer<A>,
+}
+
+impl<A> PoolRefCell<A> {
+    /// Take ownership of a chunk holding an initialised `RefBox`.
+    unsafe fn from_chunk(chunk: ElementPointer<A>) -> Self {
+        (*chunk.as_ptr()).count = 1;
+        PoolRefCell { handle: chunk }
+    }
+
+    /// Construct a `PoolRefCell` with a newly initialised value of `A`.
+    #[cfg_attr(feature = "trace-alloc", track_caller)]
+    pub fn default(pool: &Pool<A>) -> Self
+    where
+        A: PoolDefault,
+    {
+        unsafe { Self::from_chunk(pool.pop_default_chunk()) }
+    }
+
+    /// Wrap a value in a `PoolRefCell`.
+    #[cfg_attr(feature = "trace-alloc", track_caller)]
+    pub fn new(pool: &Pool<A>, value: A) -> Self {
+        let chunk = pool.pop_chunk();
+        unsafe {
+            value_ptr(chunk).write(value);
+            Self::from_chunk(chunk)
+        }
+    }
+
+    fn state(&self) -> *mut RefCount {
+        unsafe { addr_of_mut!((*self.handle.get_ptr()).count) }
+    }
+
+    fn value(&self) -> *mut A {
+        unsafe { addr_of_mut!((*self.handle.get_ptr()).value) }
+    }
+
+    /// Borrow the value immutably.
+    ///
+    /// # Panics
+    ///
+    /// This panics if the value is currently mutably borrowed.
+    pub fn borrow(&self) -> PoolRefCellRef<'_, A> {
+        self.try_borrow()
+            .expect("PoolRefCell::borrow: value is already mutably borrowed")
+    }
+
+    /// Borrow the value immutably, returning `None` if it's currently mutably
+    /// borrowed.
+    pub fn try_borrow(&self) -> Option<PoolRefCellRef<'_, A>> {
+        let state = unsafe { &mut *self.state() };
+        if *state & WRITER != 0 {
+            return None;
+        }
+        assert!(
+            *state & READERS_MASK != READERS_MASK,
+            "PoolRefCell::borrow: too many shared borrows"
+        );
+        *state += READER;
+        Some(PoolRefCellRef { cell: self })
+    }
+
+    /// Borrow the value mutably.
+    ///
+    /// # Panics
+    ///
+    /// This panics if the value is currently borrowed.
+    pub fn borrow_mut(&self) -> PoolRefCellRefMut<'_, A> {
+        self.try_borrow_mut()
+            .expect("PoolRefCell::borrow_mut: value is already borrowed")
+    }
+
+    /// Borrow the value mutably, returning `None` if it's currently borrowed.
+    pub fn try_borrow_mut(&self) -> Option<PoolRefCellRefMut<'_, A>> {
+        let state = unsafe { &mut *self.state() };
+        if *state & !STRONG_MASK != 0 {
+            return None;
+        }
+        *state |= WRITER;
+        Some(PoolRefCellRefMut { cell: self })
+    }
+
+    /// Get the number of handles to the value.
+    pub fn strong_count(this: &Self) -> usize {
+        unsafe { (*this.state() & STRONG_MASK).count() }
+    }
+
+    /// Test two `PoolRefCell`s for pointer equality.
+    pub fn ptr_eq(left: &Self, right: &Self) -> bool {
+        std::ptr::eq(left.handle.get_ptr(), right.handle.get_ptr())
+    }
+}
+
+impl<A> Clone for PoolRefCell<A> {
+    fn clone(&self) -> Self {
+        let state = unsafe { &mut *self.state() };
+        assert!(
+            *state & STRONG_MASK != STRONG_MASK,
+            "PoolRefCell::clone: too many handles to the same value"
+        );
+        *state += 1;
+        PoolRefCell {
+            handle: ElementPointer::wrap(self.handle.get_ptr()),
+        }
+    }
+}
+
+impl<A> Drop for PoolRefCell<A> {
+    fn drop(&mut self) {
+        let state = unsafe { &mut *self.state() };
+        *state -= 1;
+        if *state & STRONG_MASK == 0 {
+            unsafe { RefBox::release(self.handle) };
+        }
+    }
+}
+
+impl<A> Debug for PoolRefCell<A>
+where
+    A: Debug,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        match self.try_borrow() {
+            Some(value) => f.debug_tuple("PoolRefCell").field(&*value).finish(),
+            None => write!(f, "PoolRefCell(<borrowed>)"),
+        }
+    }
+}
+
+/// A shared borrow of the value in a [`PoolRefCell`][PoolRefCell].
+///
+/// [PoolRefCell]: struct.PoolRefCell.html
+pub struct PoolRefCellRef<'a, A> {
+    cell: &'a PoolRefCell<A>,
+}
+
+impl<'a, A> Deref for PoolRefCellRef<'a, A> {
+    type Target = A;
+    fn deref(&self) -> &Self::Target {
+        unsafe { &*self.cell.value() }
+    }
+}
+
+impl<'a, A> Drop for PoolRefCellRef<'a, A> {
+    fn drop(&mut self) {
+        unsafe { *self.cell.state() -= READER };
+    }
+}
+
+impl<'a, A> Debug for PoolRefCellRef<'a, A>
+where
+    A: Debug,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        self.deref().fmt(f)
+    }
+}
+
+/// A mutable borrow of the value in a [`PoolRefCell`][PoolRefCell].
+///
+/// [PoolRefCell]: struct.PoolRefCell.html
+pub struct PoolRefCellRefMut<'a, A> {
+    cell: &'a PoolRefCell<A>,
+}
+
+impl<'a, A> Deref for PoolRefCellRefMut<'a, A> {
+    type Target = A;
+    fn deref(&self) -> &Self::Target {
+        unsafe { &*self.cell.value() }
+    }
+}
+
+impl<'a, A> DerefMut for PoolRefCellRefMut<'a, A> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        unsafe { &mut *self.cell.value() }
+    }
+}
+
+impl<'a, A> Drop for PoolRefCellRefMut<'a, A> {
+    fn drop(&mut self) {
+        unsafe { *self.cell.state() &= !WRITER };
+    }
+}
+
+impl<'a, A> Debug for PoolRefCellRefMut<'a, A>
+where
+    A: Debug,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        self.deref().fmt(f)
+    }
+}
@@ -6,18 +6,27 @@ use std::borrow::Borrow;
 use std::cmp::Ordering;
 use std::fmt::{Debug, Display, Error, Formatter};
 use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+use std::mem::{ManuallyDrop, MaybeUninit};
 use std::ops::Deref;
+use std::panic::{RefUnwindSafe, UnwindSafe};
 use std::pin::Pin;
 
+use crate::box_handle::PoolBox;
+use crate::chunk_guard::ChunkGuard;
 use crate::counter::Counter;
 use crate::pointer::Pointer;
-use crate::refbox::{assume_init, data_ptr, RefBox};
+use crate::pool::Pool;
+use crate::ref_borrow::PoolRefBorrow;
+use crate::ref_map::PoolRefMap;
+use crate::refbox::{value_ptr, RefBox};
 use crate::types::ElementPointer;
-use crate::{Pool, PoolClone, PoolDefault};
+use crate::{AllocError, PoolClone, PoolDefault, PoolTryClone};
 
 /// A reference counted pointer to a pool allocated value of `A`.
 pub struct PoolRef<A> {
     pub(crate) handle: ElementPointer<A>,
+    pub(crate) _type: PhantomData<A>,
 }
 
 impl<A> PoolRef<A> {
@@ -39,16 +48,12 @@ impl<A> PoolRef<A> {
     ///
     /// [new]: #method.new
     /// [default_uninit]: trait.PoolDefault.html#tymethod.default_uninit
+    #[cfg_attr(feature = "trace-alloc", track_caller)]
     pub fn default(pool: &Pool<A>) -> Self
     where
         A: PoolDefault,
     {
-        let mut handle = pool.pop();
-        unsafe {
-            PoolDefault::default_uninit(data_ptr(&mut handle));
-            assume_init(handle)
-        }
-        .into_ref()
+        unsafe { (*pool.pop_default_chunk().as_ptr()).new_ref() }
     }
 
     /// Wrap a value in a `PoolRef`.
@@ -68,13 +73,272 @@ impl<A> PoolRef<A> {
     /// ```
     ///
     /// [default]: #method.default
+    #[cfg_attr(feature = "trace-alloc", track_caller)]
     pub fn new(pool: &Pool<A>, value: A) -> Self {
-        let mut handle = pool.pop();
+        let chunk = pool.pop_chunk();
+        unsafe {
+            value_ptr(chunk).write(value);
+            (*chunk.as_ptr()).new_ref()
+        }
+    }
+
+    /// Wrap a value in a `PoolRef`, using the current thread's pool for `A`.
+    ///
+    /// See [`Pool::thread_local()`][Pool::thread_local]. If there's no pool
+    /// for `A` on this thread, the value is allocated from the heap as usual.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use refpool::{Pool, PoolRef};
+    /// Pool::<usize>::thread_local(1024);
+    /// let number: PoolRef<usize> = PoolRef::new_tl(1337);
+    /// assert_eq!(1337, *number);
+    /// ```
+    ///
+    /// [Pool::thread_local]: struct.Pool.html#method.thread_local
+    #[cfg(feature = "thread-local")]
+    #[cfg_attr(feature = "trace-alloc", track_caller)]
+    pub fn new_tl(value: A) -> Self
+    where
+        A: 'static,
+    {
+        Self::new(&Pool::current(), value)
+    }
+
+    /// Construct a `PoolRef` with a default value, using the current thread's
+    /// pool for `A`.
+    ///
+    /// See [`Pool::thread_local()`][Pool::thread_local]. If there's no pool
+    /// for `A` on this thread, the value is allocated from the heap as usual.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use refpool::{Pool, PoolRef};
+    /// Pool::<usize>::thread_local(1024);
+    /// let zero: PoolRef<usize> = PoolRef::default_tl();
+    /// assert_eq!(0, *zero);
+    /// ```
+    ///
+    /// [Pool::thread_local]: struct.Pool.html#method.thread_local
+    #[cfg(feature = "thread-local")]
+    #[cfg_attr(feature = "trace-alloc", track_caller)]
+    pub fn default_tl() -> Self
+    where
+        A: PoolDefault + 'static,
+    {
+        Self::default(&Pool::current())
+    }
+
+    /// Construct a `PoolRef` with a newly initialised value of `A`, using only
+    /// memory from the pool.
+    ///
+    /// This works like [`PoolRef::default()`][default], except that it will never
+    /// fall back to the system allocator. If the pool is empty, you get
+    /// `None`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use refpool::{Pool, PoolRef};
+    /// let pool: Pool<usize> = Pool::new(1).filled();
+    /// let zero = PoolRef::try_default(&pool);
+    /// assert_eq!(Some(0), zero.as_deref().copied());
+    /// assert!(PoolRef::try_default(&pool).is_none());
+    /// ```
+    ///
+    /// [default]: #method.default
+    #[cfg_attr(feature = "trace-alloc", track_caller)]
+    pub fn try_default(pool: &Pool<A>) -> Option<Self>
+    where
+        A: PoolDefault,
+    {
+        let guard = ChunkGuard::from_chunk(pool.try_pop_chunk()?);
+        Some(unsafe {
+            RefBox::into_ref(guard.init_in_place(|slot| PoolDefault::default_uninit(slot)))
+        })
+    }
+
+    /// Wrap a value in a `PoolRef`, using only memory from the pool.
+    ///
+    /// This works like [`PoolRef::new()`][new], except that it will never fall
+    /// back to the system allocator. If the pool is empty, you get your value
+    /// back in the `Err` case.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use refpool::{Pool, PoolRef};
+    /// let pool: Pool<usize> = Pool::new(1).filled();
+    /// let number = PoolRef::try_new(&pool, 1337).unwrap();
+    /// assert_eq!(1337, *number);
+    /// assert_eq!(Err(31337), PoolRef::try_new(&pool, 31337).map(|_| ()));
+    /// ```
+    ///
+    /// [new]: #method.new
+    #[cfg_attr(feature = "trace-alloc", track_caller)]
+    pub fn try_new(pool: &Pool<A>, value: A) -> Result<Self, A> {
+        let chunk = match pool.try_pop_chunk() {
+            Some(chunk) => chunk,
+            None => return Err(value),
+        };
+        unsafe {
+            value_ptr(chunk).write(value);
+            Ok(RefBox::into_ref(chunk))
+        }
+    }
+
+    /// Wrap a value in a `PoolRef`, failing if memory can't be allocated.
+    ///
+    /// This works like [`PoolRef::new()`][new], using memory from the pool if
+    /// there is any and falling back to the system allocator otherwise, but
+    /// if the system allocator fails, you get an [`AllocError`][AllocError]
+    /// instead of an abort.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use refpool::{Pool, PoolRef};
+    /// let pool: Pool<usize> = Pool::new(256);
+    /// let number = PoolRef::try_alloc(&pool, 1337).expect("out of memory");
+    /// assert_eq!(1337, *number);
+    /// ```
+    ///
+    /// [new]: #method.new
+    /// [AllocError]: struct.AllocError.html
+    #[cfg_attr(feature = "trace-alloc", track_caller)]
+    pub fn try_alloc(pool: &Pool<A>, value: A) -> Result<Self, AllocError> {
+        let chunk = pool.try_pop_or_alloc_chunk()?;
         unsafe {
-            data_ptr(&mut handle).as_mut_ptr().write(value);
-            assume_init(handle)
+            value_ptr(chunk).write(value);
+            Ok(RefBox::into_ref(chunk))
         }
-        .into_ref()
+    }
+
+    /// Construct a `PoolRef` with uninitialised contents.
+    ///
+    /// Use [`PoolRef::write()`][write] to move a value into it and get a
+    /// `PoolRef<A>` back, or initialise it in place through
+    /// [`PoolRef::get_mut()`][get_mut] and finish with
+    /// [`PoolRef::assume_init()`][assume_init]. Either way, you don't need a
+    /// [`PoolDefault`][PoolDefault] implementation for `A`.
+    ///
+    /// # Panics
+    ///
+    /// This panics if the pool was built with
+    /// [`PoolBuilder::recycle()`][recycle], as its memory isn't empty.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use refpool::{Pool, PoolRef};
+    /// let pool: Pool<String> = Pool::new(1);
+    /// let slot = PoolRef::new_uninit(&pool);
+    /// let name = PoolRef::write(slot, String::from("Joe Bloggs"));
+    /// assert_eq!("Joe Bloggs", *name);
+    /// ```
+    ///
+    /// [write]: #method.write
+    /// [get_mut]: #method.get_mut
+    /// [assume_init]: #method.assume_init
+    /// [PoolDefault]: trait.PoolDefault.html
+    /// [recycle]: struct.PoolBuilder.html#method.recycle
+    #[cfg_attr(feature = "trace-alloc", track_caller)]
+    pub fn new_uninit(pool: &Pool<A>) -> PoolRef<MaybeUninit<A>> {
+        assert!(
+            !pool.is_recycling(),
+            "PoolRef::new_uninit: can't take uninitialised memory from a pool which recycles values"
+        );
+        unsafe { RefBox::into_ref(pool.pop_chunk().cast::<RefBox<MaybeUninit<A>>>()) }
+    }
+
+    /// Construct a `PoolRef` with its memory zeroed.
+    ///
+    /// Like [`PoolRef::new_uninit()`][new_uninit], this gives you a
+    /// `PoolRef<MaybeUninit<A>>`, but with every byte set to zero, which is a
+    /// valid value for many plain data types. Use
+    /// [`PoolRef::assume_init()`][assume_init] once you've made sure it is.
+    ///
+    /// # Panics
+    ///
+    /// This panics if the pool was built with
+    /// [`PoolBuilder::recycle()`][recycle], as its memory isn't empty.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use refpool::{Pool, PoolRef};
+    /// let pool: Pool<[u32; 256]> = Pool::new(1);
+    /// let zeroes = PoolRef::new_zeroed(&pool);
+    /// let zeroes = unsafe { PoolRef::assume_init(zeroes) };
+    /// assert_eq!([0; 256], *zeroes);
+    /// ```
+    ///
+    /// [new_uninit]: #method.new_uninit
+    /// [assume_init]: #method.assume_init
+    /// [recycle]: struct.PoolBuilder.html#method.recycle
+    #[cfg_attr(feature = "trace-alloc", track_caller)]
+    pub fn new_zeroed(pool: &Pool<A>) -> PoolRef<MaybeUninit<A>> {
+        let mut this = Self::new_uninit(pool);
+        unsafe {
+            PoolRef::get_mut(&mut this)
+                .unwrap()
+                .as_mut_ptr()
+                .write_bytes(0, 1)
+        };
+        this
+    }
+
+    /// Construct a `PoolRef` containing a zeroed value of a type for which
+    /// that's known to be valid.
+    ///
+    /// This is only available with the `bytemuck` feature flag.
+    ///
+    /// # Panics
+    ///
+    /// This panics if the pool was built with
+    /// [`PoolBuilder::recycle()`][recycle], as its memory isn't empty.
+    ///
+    /// [recycle]: struct.PoolBuilder.html#method.recycle
+    #[cfg(feature = "bytemuck")]
+    #[cfg_attr(feature = "trace-alloc", track_caller)]
+    pub fn zeroed(pool: &Pool<A>) -> Self
+    where
+        A: bytemuck::Zeroable,
+    {
+        unsafe { PoolRef::assume_init(Self::new_zeroed(pool)) }
+    }
+
+    /// Construct a `PoolRef` by initialising its value in place.
+    ///
+    /// This works like [`PoolBox::new_with()`][PoolBox::new_with]: `init` must
+    /// initialise the memory it's given and return the reference that
+    /// [`MaybeUninit::write()`][write] gives back.
+    ///
+    /// # Panics
+    ///
+    /// This panics if `init` returns a reference to anything other than the
+    /// slot it was given. If `init` panics, the memory goes back to the pool,
+    /// but anything `init` had already written into it is leaked.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use refpool::{Pool, PoolRef};
+    /// let pool: Pool<(String, usize)> = Pool::new(1);
+    /// let entry = PoolRef::new_with(&pool, |slot| slot.write((String::from("answer"), 42)));
+    /// assert_eq!(42, entry.1);
+    /// ```
+    ///
+    /// [PoolBox::new_with]: struct.PoolBox.html#method.new_with
+    /// [write]: https://doc.rust-lang.org/std/mem/union.MaybeUninit.html#method.write
+    #[cfg_attr(feature = "trace-alloc", track_caller)]
+    pub fn new_with<F>(pool: &Pool<A>, init: F) -> Self
+    where
+        F: FnOnce(&mut MaybeUninit<A>) -> &mut A,
+    {
+        unsafe { RefBox::into_ref(pool.reserve().init_with(init)) }
     }
 
     /// Clone a value and return a new `PoolRef` to it.
@@ -95,16 +359,17 @@ impl<A> PoolRef<A> {
     ///
     /// [new]: #method.new
     /// [clone_uninit]: trait.PoolClone.html#tymethod.clone_uninit
+    #[cfg_attr(feature = "trace-alloc", track_caller)]
     pub fn clone_from(pool: &Pool<A>, value: &A) -> Self
     where
         A: PoolClone,
     {
-        let mut handle = pool.pop();
         unsafe {
-            value.clone_uninit(data_ptr(&mut handle));
-            assume_init(handle)
+            RefBox::into_ref(
+                pool.reserve()
+                    .init_in_place(|slot| value.clone_uninit(slot)),
+            )
         }
-        .into_ref()
     }
 
     /// Construct a [`Pin`][Pin]ned `PoolRef` with a default value.
@@ -119,6 +384,7 @@ impl<A> PoolRef<A> {
     /// ```
     ///
     /// [Pin]: https://doc.rust-lang.org/std/pin/struct.Pin.html
+    #[cfg_attr(feature = "trace-alloc", track_caller)]
     pub fn pin_default(pool: &Pool<A>) -> Pin<Self>
     where
         A: PoolDefault,
@@ -138,6 +404,7 @@ impl<A> PoolRef<A> {
     /// ```
     ///
     /// [Pin]: https://doc.rust-lang.org/std/pin/struct.Pin.html
+    #[cfg_attr(feature = "trace-alloc", track_caller)]
     pub fn pin(pool: &Pool<A>, value: A) -> Pin<Self> {
         unsafe { Pin::new_unchecked(Self::new(pool, value)) }
     }
@@ -148,6 +415,11 @@ impl<A> PoolRef<A> {
     /// the clone, which may be more efficient than using
     /// [`PoolRef::new((*this_ref).clone())`][new].
     ///
+    /// The clone is allocated from `pool`, which doesn't have to be the pool
+    /// `this` was allocated from, so you can also use this to copy a value
+    /// into a different pool, such as from a short lived pool into a long
+    /// lived one.
+    ///
     /// # Examples
     ///
     /// ```rust
@@ -158,20 +430,26 @@ impl<A> PoolRef<A> {
     /// *PoolRef::make_mut(&pool, &mut number) = 123;
     /// assert_eq!(123, *number);
     /// assert_eq!(1337, *other_number);
+    ///
+    /// let other_pool: Pool<usize> = Pool::new(256);
+    /// let moved_number = PoolRef::cloned(&other_pool, &number);
+    /// drop(moved_number);
+    /// assert_eq!(1, other_pool.get_pool_size());
     /// ```
     ///
     /// [new]: #method.new
     /// [clone_uninit]: trait.PoolClone.html#tymethod.clone_uninit
+    #[cfg_attr(feature = "trace-alloc", track_caller)]
     pub fn cloned(pool: &Pool<A>, this: &Self) -> Self
     where
         A: PoolClone,
     {
-        let mut handle = pool.pop();
         unsafe {
-            this.deref().clone_uninit(data_ptr(&mut handle));
-            assume_init(handle)
+            RefBox::into_ref(
+                pool.reserve()
+                    .init_in_place(|slot| this.deref().clone_uninit(slot)),
+            )
         }
-        .into_ref()
     }
 
     /// Get a mutable reference to the value inside a `PoolRef`, cloning it
@@ -188,23 +466,71 @@ impl<A> PoolRef<A> {
     /// assert_eq!(1, *ref1);
     /// assert_eq!(2, *ref2);
     /// ```
+    #[cfg_attr(feature = "trace-alloc", track_caller)]
     pub fn make_mut<'a>(pool: &Pool<A>, this: &'a mut Self) -> &'a mut A
     where
         A: PoolClone,
     {
         if this.box_ref().is_shared() {
-            let mut new_handle = pool.pop();
-            let mut new_handle = unsafe {
-                this.deref().deref().clone_uninit(data_ptr(&mut new_handle));
-                assume_init(new_handle)
+            let new_handle = unsafe {
+                pool.reserve()
+                    .init_in_place(|slot| this.deref().deref().clone_uninit(slot))
             };
-            new_handle.inc();
+            unsafe { (*new_handle.as_ptr()).inc() };
             this.box_ref_mut().dec();
-            this.handle = ElementPointer::wrap(Box::into_raw(new_handle));
+            this.handle = new_handle;
         }
         this.box_ref_mut().value_as_mut()
     }
 
+    /// Attempt to clone the value inside a `PoolRef` into a new `PoolRef`,
+    /// using [`PoolTryClone::try_clone_uninit()`][try_clone_uninit].
+    ///
+    /// This works like [`PoolRef::cloned()`][cloned], except that a failed
+    /// clone is returned as an error rather than forcing a panic, and the
+    /// memory reserved for the clone goes back to the pool.
+    ///
+    /// [try_clone_uninit]: trait.PoolTryClone.html#tymethod.try_clone_uninit
+    /// [cloned]: #method.cloned
+    #[cfg_attr(feature = "trace-alloc", track_caller)]
+    pub fn try_cloned(pool: &Pool<A>, this: &Self) -> Result<Self, A::Error>
+    where
+        A: PoolTryClone,
+    {
+        unsafe {
+            let chunk = pool
+                .reserve()
+                .try_init(|slot| this.deref().try_clone_uninit(slot))?;
+            Ok(RefBox::into_ref(chunk))
+        }
+    }
+
+    /// Attempt to get a mutable reference to the value inside a `PoolRef`,
+    /// cloning it first with
+    /// [`PoolTryClone::try_clone_uninit()`][try_clone_uninit] if this
+    /// `PoolRef` isn't a unique reference.
+    ///
+    /// If the clone fails, the error is returned and `this` is left pointing
+    /// at the original shared value.
+    ///
+    /// [try_clone_uninit]: trait.PoolTryClone.html#tymethod.try_clone_uninit
+    #[cfg_attr(feature = "trace-alloc", track_caller)]
+    pub fn try_make_mut<'a>(pool: &Pool<A>, this: &'a mut Self) -> Result<&'a mut A, A::Error>
+    where
+        A: PoolTryClone,
+    {
+        if this.box_ref().is_shared() {
+            let new_handle = unsafe {
+                pool.reserve()
+                    .try_init(|slot| this.deref().deref().try_clone_uninit(slot))?
+            };
+            unsafe { (*new_handle.as_ptr()).inc() };
+            this.box_ref_mut().dec();
+            this.handle = new_handle;
+        }
+        Ok(this.box_ref_mut().value_as_mut())
+    }
+
     /// Attempt to get a mutable reference to the value inside a `PoolRef`.
     ///
     /// This will produce a `None` if this `PoolRef` isn't a unique reference
@@ -266,9 +592,44 @@ impl<A> PoolRef<A> {
         if this.box_ref().is_shared() {
             Err(this)
         } else {
-            let handle = unsafe { Box::from_raw(this.handle.get_ptr()) };
+            let handle = this.handle;
             std::mem::forget(this);
-            Ok(handle.value)
+            Ok(unsafe { RefBox::into_value(handle) })
+        }
+    }
+
+    /// Move the value inside a `PoolRef` into memory from a different pool.
+    ///
+    /// If this `PoolRef` is a unique reference to the value, the value is
+    /// moved into a chunk from `pool`, and its old memory goes back to the
+    /// pool it came from. Otherwise, you get the `PoolRef` back as an error.
+    /// Unlike [`PoolRef::cloned()`][cloned], the value itself is never
+    /// cloned.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use refpool::{Pool, PoolRef};
+    /// let scratch: Pool<Vec<usize>> = Pool::new(16);
+    /// let long_lived: Pool<Vec<usize>> = Pool::new(16);
+    /// let numbers = PoolRef::new(&scratch, vec![1, 2, 3]);
+    /// let numbers = PoolRef::migrate(numbers, &long_lived).unwrap();
+    /// assert_eq!(vec![1, 2, 3], *numbers);
+    /// assert_eq!(1, scratch.get_pool_size());
+    /// ```
+    ///
+    /// [cloned]: #method.cloned
+    #[cfg_attr(feature = "trace-alloc", track_caller)]
+    pub fn migrate(this: Self, pool: &Pool<A>) -> Result<Self, Self> {
+        if this.box_ref().is_shared() {
+            return Err(this);
+        }
+        let chunk = pool.pop_chunk();
+        let old = ManuallyDrop::new(this).handle;
+        unsafe {
+            value_ptr(chunk).write(std::ptr::read(&(*old.as_ptr()).value));
+            RefBox::return_uninit(old);
+            Ok(RefBox::into_ref(chunk))
         }
     }
 
@@ -297,12 +658,80 @@ impl<A> PoolRef<A> {
         if this.box_ref().is_shared() {
             this.deref().clone()
         } else {
-            let handle = unsafe { Box::from_raw(this.handle.get_ptr()) };
+            let handle = this.handle;
             std::mem::forget(this);
-            handle.value
+            unsafe { RefBox::into_value(handle) }
         }
     }
 
+    /// Swap the values two `PoolRef`s point to.
+    ///
+    /// This only swaps the handles themselves, so neither value is moved and
+    /// no reference counts change.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use refpool::{Pool, PoolRef};
+    /// let pool: Pool<usize> = Pool::new(2);
+    /// let mut left = PoolRef::new(&pool, 1);
+    /// let mut right = PoolRef::new(&pool, 2);
+    /// let right_copy = right.clone();
+    /// PoolRef::swap(&mut left, &mut right);
+    /// assert_eq!(2, *left);
+    /// assert_eq!(1, *right);
+    /// assert!(PoolRef::ptr_eq(&left, &right_copy));
+    /// assert_eq!(2, PoolRef::strong_count(&left));
+    /// ```
+    pub fn swap(left: &mut Self, right: &mut Self) {
+        std::mem::swap(&mut left.handle, &mut right.handle)
+    }
+
+    /// Replace the handle in `this` with `other`, returning the old handle.
+    ///
+    /// Like [`PoolRef::swap()`][swap], this doesn't move any values or change
+    /// any reference counts. The value previously held by `this` will be
+    /// released when the returned handle is dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use refpool::{Pool, PoolRef};
+    /// let pool: Pool<usize> = Pool::new(2);
+    /// let mut number = PoolRef::new(&pool, 1);
+    /// let old = PoolRef::replace(&mut number, PoolRef::new(&pool, 2));
+    /// assert_eq!(1, *old);
+    /// assert_eq!(2, *number);
+    /// ```
+    ///
+    /// [swap]: #method.swap
+    pub fn replace(this: &mut Self, other: Self) -> Self {
+        std::mem::replace(this, other)
+    }
+
+    /// Point `this` at a new value, returning the old handle.
+    ///
+    /// The new value is allocated from the same pool as the value `this`
+    /// currently points to. Other references to the old value are unaffected.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use refpool::{Pool, PoolRef};
+    /// let pool: Pool<usize> = Pool::new(2);
+    /// let mut number = PoolRef::new(&pool, 1);
+    /// let other_ref = number.clone();
+    /// let old = PoolRef::set(&mut number, 2);
+    /// assert_eq!(2, *number);
+    /// assert_eq!(1, *other_ref);
+    /// assert!(PoolRef::ptr_eq(&old, &other_ref));
+    /// ```
+    #[cfg_attr(feature = "trace-alloc", track_caller)]
+    pub fn set(this: &mut Self, value: A) -> Self {
+        let new_ref = Self::new(&this.box_ref().pool, value);
+        std::mem::replace(this, new_ref)
+    }
+
     /// Test two `PoolRef`s for pointer equality.
     ///
     /// # Examples
@@ -318,6 +747,34 @@ impl<A> PoolRef<A> {
         std::ptr::eq(left.handle.get_ptr(), right.handle.get_ptr())
     }
 
+    /// Hash a `PoolRef` by the address of the value it points to, so that
+    /// two `PoolRef`s hash the same if they're
+    /// [`PoolRef::ptr_eq()`][ptr_eq].
+    ///
+    /// See also [`ByPtr`][ByPtr].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use refpool::{Pool, PoolRef};
+    /// # use std::collections::hash_map::DefaultHasher;
+    /// # use std::hash::Hasher;
+    /// let pool: Pool<usize> = Pool::new(1);
+    /// let ref1 = PoolRef::new(&pool, 1);
+    /// let ref2 = ref1.clone();
+    /// let mut hash1 = DefaultHasher::new();
+    /// let mut hash2 = DefaultHasher::new();
+    /// PoolRef::ptr_hash(&ref1, &mut hash1);
+    /// PoolRef::ptr_hash(&ref2, &mut hash2);
+    /// assert_eq!(hash1.finish(), hash2.finish());
+    /// ```
+    ///
+    /// [ptr_eq]: #method.ptr_eq
+    /// [ByPtr]: struct.ByPtr.html
+    pub fn ptr_hash<H: Hasher>(this: &Self, hasher: &mut H) {
+        std::ptr::hash(this.handle.get_ptr(), hasher)
+    }
+
     /// Get the current number of `LocalRef` references to the wrapped value.
     ///
     /// # Examples
@@ -334,6 +791,120 @@ impl<A> PoolRef<A> {
         this.box_ref().count.count()
     }
 
+    /// Borrow a [`PoolRefBorrow`][PoolRefBorrow] from a `PoolRef`.
+    ///
+    /// The borrowed handle points at the same value without increasing the
+    /// reference count, and can be passed around by value for as long as the
+    /// `PoolRef` lives.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use refpool::{Pool, PoolRef};
+    /// let pool: Pool<usize> = Pool::new(1);
+    /// let number = PoolRef::new(&pool, 1337);
+    /// let borrowed = PoolRef::borrow_ref(&number);
+    /// assert_eq!(1337, *borrowed);
+    /// assert_eq!(1, PoolRef::strong_count(&number));
+    /// ```
+    ///
+    /// [PoolRefBorrow]: struct.PoolRefBorrow.html
+    pub fn borrow_ref(this: &Self) -> PoolRefBorrow<'_, A> {
+        PoolRefBorrow::new(this)
+    }
+
+    /// Turn a `PoolRef` into a [`PoolRefMap`][PoolRefMap] pointing at a part
+    /// of its value.
+    ///
+    /// The `PoolRefMap` keeps the whole value alive, but only gives access to
+    /// whatever `f` returned.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use refpool::{Pool, PoolRef};
+    /// let pool: Pool<(usize, String)> = Pool::new(1);
+    /// let pair = PoolRef::new(&pool, (1337, "Hello Joe".to_string()));
+    /// let text = PoolRef::map(pair, |pair| pair.1.as_str());
+    /// assert_eq!("Hello Joe", &*text);
+    /// ```
+    ///
+    /// [PoolRefMap]: struct.PoolRefMap.html
+    pub fn map<T, F>(this: Self, f: F) -> PoolRefMap<A, T>
+    where
+        T: ?Sized,
+        F: FnOnce(&A) -> &T,
+    {
+        PoolRefMap::new(this, f)
+    }
+
+    /// Drop a collection of `PoolRef`s in one go.
+    ///
+    /// This does the same as dropping each `PoolRef` in turn, except that
+    /// once a pool turns out to be full, the rest of the values from that
+    /// pool go straight back to the system allocator without asking the pool
+    /// again. This speeds up dropping large numbers of values from a pool
+    /// which is too small to take them all back.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use refpool::{Pool, PoolRef};
+    /// let pool: Pool<usize> = Pool::new(16);
+    /// let refs: Vec<_> = (0..1000).map(|i| PoolRef::new(&pool, i)).collect();
+    /// PoolRef::drop_batch(refs);
+    /// assert_eq!(16, pool.get_pool_size());
+    /// ```
+    pub fn drop_batch<I>(refs: I)
+    where
+        I: IntoIterator<Item = Self>,
+    {
+        let mut full_pool = std::ptr::null();
+        for pool_ref in refs {
+            let mut pool_ref = ManuallyDrop::new(pool_ref);
+            if pool_ref.box_ref_mut().dec() != 1 {
+                continue;
+            }
+            let chunk = pool_ref.handle;
+            let pool = unsafe { (*chunk.as_ptr()).pool.id() };
+            if pool == full_pool {
+                drop(unsafe { RefBox::into_value(chunk) });
+            } else if !unsafe { RefBox::release(chunk) } {
+                full_pool = pool;
+            }
+        }
+    }
+
+    /// Clone a slice of `PoolRef`s into a `Vec`.
+    ///
+    /// Cloning a `PoolRef` never involves its pool, only its reference count,
+    /// so this just bumps each value's count in turn, writing the new
+    /// references into a `Vec` allocated up front. This is handy for copying
+    /// a node's children along a path in a persistent data structure.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use refpool::{Pool, PoolRef};
+    /// let pool: Pool<usize> = Pool::new(16);
+    /// let children: Vec<_> = (0..8).map(|i| PoolRef::new(&pool, i)).collect();
+    /// let copied = PoolRef::clone_slice(&children);
+    /// assert_eq!(children, copied);
+    /// assert_eq!(2, PoolRef::strong_count(&children[0]));
+    /// ```
+    pub fn clone_slice(refs: &[Self]) -> Vec<Self> {
+        let mut clones = Vec::with_capacity(refs.len());
+        clones.extend(refs.iter().map(|pool_ref| {
+            let mut clone: Self = PoolRef {
+                handle: pool_ref.handle,
+                _type: PhantomData,
+            };
+            clone.box_ref_mut().inc();
+            clone
+        }));
+        clones
+    }
+
     /// Consume the `PoolRef` and return a pointer to the contents.
     ///
     /// The pointer is guaranteed to be non-null.
@@ -342,7 +913,8 @@ impl<A> PoolRef<A> {
     /// is by using `PoolRef::from_raw` to turn it back into a `PoolRef`, because
     /// the value is followed by `PoolRef` metadata which also needs to
     /// be dropped.
-    pub fn into_raw(b: PoolRef<A>) -> *const A {
+    pub fn into_raw(mut b: PoolRef<A>) -> *const A {
+        b.box_ref_mut().raw_acquired();
         let ptr = b.handle.cast();
         std::mem::forget(b);
         ptr.as_ptr()
@@ -375,9 +947,12 @@ impl<A> PoolRef<A> {
     /// assert_eq!(31337, *ref2);
     /// ```
     pub unsafe fn from_raw(ptr: *const A) -> Self {
-        Self {
+        let mut this = Self {
             handle: ElementPointer::wrap((ptr as *mut A).cast()),
-        }
+            _type: PhantomData,
+        };
+        this.box_ref_mut().raw_released();
+        this
     }
 
     fn box_ref(&self) -> &RefBox<A> {
@@ -389,20 +964,93 @@ impl<A> PoolRef<A> {
     }
 }
 
+impl<A> PoolRef<MaybeUninit<A>> {
+    /// Move a value into an uninitialised `PoolRef`, turning it into a
+    /// `PoolRef<A>`.
+    ///
+    /// # Panics
+    ///
+    /// This panics if the `PoolRef` isn't a unique reference, because the
+    /// other references could be observing the memory being written.
+    pub fn write(mut this: Self, value: A) -> PoolRef<A> {
+        match PoolRef::get_mut(&mut this) {
+            Some(slot) => {
+                slot.write(value);
+            }
+            None => panic!("PoolRef::write: can't initialise a shared reference"),
+        }
+        unsafe { Self::assume_init(this) }
+    }
+
+    /// Convert a `PoolRef<MaybeUninit<A>>` into a `PoolRef<A>`.
+    ///
+    /// # Safety
+    ///
+    /// The contents of the `PoolRef` must have been fully initialised, as
+    /// with [`MaybeUninit::assume_init()`][assume_init].
+    ///
+    /// [assume_init]: https://doc.rust-lang.org/std/mem/union.MaybeUninit.html#method.assume_init
+    pub unsafe fn assume_init(this: Self) -> PoolRef<A> {
+        let ptr = ManuallyDrop::new(this).handle.get_ptr();
+        PoolRef {
+            handle: ElementPointer::wrap(ptr.cast()),
+            _type: PhantomData,
+        }
+    }
+}
+
+#[cfg(feature = "thread-local")]
+impl<A> Default for PoolRef<A>
+where
+    A: PoolDefault + 'static,
+{
+    /// Construct a default value using the current thread's pool for `A`.
+    ///
+    /// This is only available with the `thread-local` feature flag. See
+    /// [`PoolRef::default_tl()`][default_tl].
+    ///
+    /// [default_tl]: #method.default_tl
+    fn default() -> Self {
+        Self::default_tl()
+    }
+}
+
+#[cfg(feature = "thread-local")]
+impl<A> From<A> for PoolRef<A>
+where
+    A: 'static,
+{
+    /// Wrap a value using the current thread's pool for `A`.
+    ///
+    /// This is only available with the `thread-local` feature flag. See
+    /// [`PoolRef::new_tl()`][new_tl].
+    ///
+    /// [new_tl]: #method.new_tl
+    fn from(value: A) -> Self {
+        Self::new_tl(value)
+    }
+}
+
 impl<A> Drop for PoolRef<A> {
     fn drop(&mut self) {
         if self.box_ref_mut().dec() != 1 {
             return;
         }
-        let handle = unsafe { Box::from_raw(self.handle.get_ptr()) };
-        handle.return_to_pool();
+        unsafe { RefBox::release(self.handle) };
     }
 }
 
+// Like `Rc`, a `PoolRef` only gives out shared references to its value, so
+// it's unwind safe if the value is.
+impl<A> UnwindSafe for PoolRef<A> where A: RefUnwindSafe {}
+impl<A> RefUnwindSafe for PoolRef<A> where A: RefUnwindSafe {}
+impl<A> Unpin for PoolRef<A> {}
+
 impl<A> Clone for PoolRef<A> {
     fn clone(&self) -> Self {
         let mut new_ref: Self = PoolRef {
             handle: ElementPointer::wrap(self.handle.get_ptr()),
+            _type: PhantomData,
         };
         new_ref.box_ref_mut().inc();
         new_ref
@@ -439,6 +1087,24 @@ where
 
 impl<A> Eq for PoolRef<A> where A: Eq {}
 
+impl<A> PartialEq<A> for PoolRef<A>
+where
+    A: PartialEq,
+{
+    fn eq(&self, other: &A) -> bool {
+        (**self) == *other
+    }
+}
+
+impl<A> PartialEq<PoolBox<A>> for PoolRef<A>
+where
+    A: PartialEq,
+{
+    fn eq(&self, other: &PoolBox<A>) -> bool {
+        (**self) == (**other)
+    }
+}
+
 impl<A> PartialOrd for PoolRef<A>
 where
     A: PartialOrd,
@@ -489,3 +1155,17 @@ impl<A> std::fmt::Pointer for PoolRef<A> {
         std::fmt::Pointer::fmt(&(&**self as *const A), f)
     }
 }
+
+impl<A> std::error::Error for PoolRef<A>
+where
+    A: std::error::Error,
+{
+    #[allow(deprecated)]
+    fn description(&self) -> &str {
+        (**self).description()
+    }
+
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        (**self).source()
+    }
+}
@@ -0,0 +1,149 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::borrow::Borrow;
+use std::fmt::{Debug, Display, Error, Formatter};
+use std::ops::Deref;
+use std::ptr::NonNull;
+
+use crate::ref_handle::PoolRef;
+
+/// A [`PoolRef`][PoolRef] projected onto a part of its value.
+///
+/// This holds on to a `PoolRef<A>`, keeping the whole value alive, but
+/// dereferences to a `T` somewhere inside it, like a [`Ref::map()`][Ref::map]
+/// which owns its reference instead of borrowing it. This lets you hand out
+/// a field of a pooled value without cloning the field or exposing the rest
+/// of the value.
+///
+/// You get one of these from [`PoolRef::map()`][map].
+///
+/// # Examples
+///
+/// ```rust
+/// # use refpool::{Pool, PoolRef, PoolRefMap};
+/// struct Node {
+///     name: String,
+///     children: Vec<usize>,
+/// }
+///
+/// fn name_of(node: PoolRef<Node>) -> PoolRefMap<Node, str> {
+///     PoolRef::map(node, |node| node.name.as_str())
+/// }
+///
+/// let pool: Pool<Node> = Pool::new(1);
+/// let node = PoolRef::new(&pool, Node {
+///     name: "root".to_string(),
+///     children: vec![1, 2, 3],
+/// });
+/// let name = name_of(node.clone());
+/// assert_eq!("root", &*name);
+/// assert_eq!(2, PoolRef::strong_count(&node));
+/// ```
+///
+/// [PoolRef]: struct.PoolRef.html
+/// [map]: struct.PoolRef.html#method.map
+/// [Ref::map]: https://doc.rust-lang.org/std/cell/struct.Ref.html#method.map
+pub struct PoolRefMap<A, T: ?Sized> {
+    owner: PoolRef<A>,
+    value: NonNull<T>,
+}
+
+impl<A, T: ?Sized> PoolRefMap<A, T> {
+    pub(crate) fn new<F>(owner: PoolRef<A>, f: F) -> Self
+    where
+        F: FnOnce(&A) -> &T,
+    {
+        // A pooled value never moves while there's a `PoolRef` to it, so
+        // the projected reference stays valid for as long as we hold on to
+        // `owner`.
+        let value = NonNull::from(f(&owner));
+        PoolRefMap { owner, value }
+    }
+
+    /// Project the mapped reference further onto a part of itself.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use refpool::{Pool, PoolRef, PoolRefMap};
+    /// let pool: Pool<(String, usize)> = Pool::new(1);
+    /// let pair = PoolRef::new(&pool, ("Hello Joe".to_string(), 1337));
+    /// let text = PoolRef::map(pair, |pair| &pair.0);
+    /// let greeting = PoolRefMap::map(text, |text| &text[..5]);
+    /// assert_eq!("Hello", &*greeting);
+    /// ```
+    pub fn map<U, F>(this: Self, f: F) -> PoolRefMap<A, U>
+    where
+        U: ?Sized,
+        F: FnOnce(&T) -> &U,
+    {
+        let value = NonNull::from(f(&this));
+        PoolRefMap {
+            owner: this.owner,
+            value,
+        }
+    }
+
+    /// Get a reference to the [`PoolRef`][PoolRef] holding the whole value.
+    ///
+    /// [PoolRef]: struct.PoolRef.html
+    pub fn owner(this: &Self) -> &PoolRef<A> {
+        &this.owner
+    }
+
+    /// Discard the projection and get back the [`PoolRef`][PoolRef] holding
+    /// the whole value.
+    ///
+    /// [PoolRef]: struct.PoolRef.html
+    pub fn into_owner(this: Self) -> PoolRef<A> {
+        this.owner
+    }
+}
+
+impl<A, T: ?Sized> Clone for PoolRefMap<A, T> {
+    fn clone(&self) -> Self {
+        PoolRefMap {
+            owner: self.owner.clone(),
+            value: self.value,
+        }
+    }
+}
+
+impl<A, T: ?Sized> Deref for PoolRefMap<A, T> {
+    type Target = T;
+    fn deref(&self) -> &Self::Target {
+        unsafe { self.value.as_ref() }
+    }
+}
+
+impl<A, T: ?Sized> AsRef<T> for PoolRefMap<A, T> {
+    fn as_ref(&self) -> &T {
+        self.deref()
+    }
+}
+
+impl<A, T: ?Sized> Borrow<T> for PoolRefMap<A, T> {
+    fn borrow(&self) -> &T {
+        self.deref()
+    }
+}
+
+impl<A, T> Display for PoolRefMap<A, T>
+where
+    T: Display + ?Sized,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        (**self).fmt(f)
+    }
+}
+
+impl<A, T> Debug for PoolRefMap<A, T>
+where
+    T: Debug + ?Sized,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        (**self).fmt(f)
+    }
+}
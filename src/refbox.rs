@@ -2,51 +2,50 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
-use std::mem::MaybeUninit;
+use std::marker::PhantomData;
+use std::ptr::addr_of_mut;
 
 use crate::{
-    box_handle::PoolBox, counter::Counter, pointer::Pointer, pool::Pool, ref_handle::PoolRef,
+    box_handle::PoolBox,
+    counter::{Counter, RefCount},
+    pointer::Pointer,
+    pool::Pool,
+    ref_handle::PoolRef,
     types::ElementPointer,
 };
 
-pub(crate) unsafe fn assume_init<A>(maybe_boxed: Box<MaybeUninit<A>>) -> Box<A> {
-    Box::from_raw(Box::into_raw(maybe_boxed).cast())
-    // TODO: Change this to `maybe_boxed.assume_init()` when the `new_uninit`
-    // feature stabilises.
-}
-
-pub(crate) unsafe fn data_ptr<A>(this: &mut MaybeUninit<RefBox<A>>) -> &mut MaybeUninit<A> {
-    (*this.as_mut_ptr())
-        .value_as_mut_ptr()
-        .cast::<MaybeUninit<A>>()
-        .as_mut()
-        .unwrap()
+/// Get a pointer to the value in a chunk, without going through a reference
+/// to a `RefBox` which might not be initialised yet.
+#[inline(always)]
+pub(crate) unsafe fn value_ptr<A>(chunk: ElementPointer<A>) -> *mut A {
+    addr_of_mut!((*chunk.as_ptr()).value)
 }
 
 #[repr(C)]
 pub(crate) struct RefBox<A> {
     pub(crate) value: A,
-    pub(crate) count: usize,
+    pub(crate) count: RefCount,
     pub(crate) pool: Pool<A>,
+    #[cfg(feature = "debug-aliasing")]
+    pub(crate) raw_pointers: usize,
 }
 
 impl<A> RefBox<A> {
-    pub(crate) fn into_ref(mut self: Box<Self>) -> PoolRef<A> {
-        let ref_handle = self.new_ref();
-        Box::leak(self);
-        ref_handle
+    /// Wrap the initialised `RefBox` in `chunk` in a `PoolRef`.
+    pub(crate) unsafe fn into_ref(chunk: ElementPointer<A>) -> PoolRef<A> {
+        (*chunk.as_ptr()).new_ref()
     }
 
-    pub(crate) fn into_box(mut self: Box<Self>) -> PoolBox<A> {
-        let box_handle = self.new_box();
-        Box::leak(self);
-        box_handle
+    /// Wrap the initialised `RefBox` in `chunk` in a `PoolBox`.
+    pub(crate) unsafe fn into_box(chunk: ElementPointer<A>) -> PoolBox<A> {
+        (*chunk.as_ptr()).new_box()
     }
 
     pub(crate) fn new_ref(&mut self) -> PoolRef<A> {
         self.inc();
         PoolRef {
             handle: ElementPointer::wrap(self),
+            _type: PhantomData,
         }
     }
 
@@ -54,17 +53,72 @@ impl<A> RefBox<A> {
         self.inc();
         PoolBox {
             handle: ElementPointer::wrap(self),
+            _type: PhantomData,
         }
     }
 
-    pub(crate) fn return_to_pool(self: Box<Self>) {
-        if !self.pool.is_full() {
-            let pool = self.pool.clone();
-            let ptr = Box::into_raw(self);
-            unsafe {
-                ptr.drop_in_place();
-                pool.push(ElementPointer::wrap(ptr));
-            };
+    /// Drop the value in `chunk` and hand the memory back to the pool, or to
+    /// the system allocator if the pool is full, returning `false` if the
+    /// pool was full.
+    ///
+    /// # Safety
+    ///
+    /// The chunk must hold an initialised `RefBox` with no remaining handles.
+    pub(crate) unsafe fn release(chunk: ElementPointer<A>) -> bool {
+        let ptr = chunk.as_ptr();
+        // Move the chunk's handle to the pool out rather than cloning it, so
+        // that returning a value only touches the pool's reference count
+        // once.
+        let pool = std::ptr::read(&(*ptr).pool);
+        #[cfg(feature = "hdrhistogram")]
+        let start = pool.start_timer();
+        let returned = if !pool.has_room() {
+            pool.discard(ptr);
+            value_ptr(chunk).drop_in_place();
+            pool.dealloc_chunk(chunk);
+            false
+        } else {
+            if !pool.reset_value(&mut (*ptr).value) {
+                value_ptr(chunk).drop_in_place();
+            }
+            pool.push(chunk);
+            true
+        };
+        #[cfg(feature = "hdrhistogram")]
+        pool.record_return_latency(start);
+        returned
+    }
+
+    /// Move the value in `chunk` out and hand the memory back to the system
+    /// allocator.
+    ///
+    /// # Safety
+    ///
+    /// The chunk must hold an initialised `RefBox` with no remaining handles.
+    pub(crate) unsafe fn into_value(chunk: ElementPointer<A>) -> A {
+        // The chunk has to be freed by its pool, which knows how it was
+        // allocated.
+        let ptr = chunk.as_ptr();
+        let pool = std::ptr::read(&(*ptr).pool);
+        pool.discard(ptr);
+        let value = std::ptr::read(&(*ptr).value);
+        pool.dealloc_chunk(chunk);
+        value
+    }
+
+    /// Return a chunk whose value was never initialised to its pool.
+    ///
+    /// # Safety
+    ///
+    /// The chunk must have an initialised header and no handles.
+    pub(crate) unsafe fn return_uninit(chunk: ElementPointer<A>) {
+        let ptr = chunk.as_ptr();
+        let pool = std::ptr::read(&(*ptr).pool);
+        if pool.has_room() {
+            pool.push(pool.prepare_uninit(chunk));
+        } else {
+            pool.discard(ptr);
+            pool.dealloc_chunk(chunk);
         }
     }
 
@@ -73,6 +127,11 @@ impl<A> RefBox<A> {
     }
 
     pub(crate) fn value_as_mut(&mut self) -> &mut A {
+        #[cfg(feature = "debug-aliasing")]
+        assert_eq!(
+            0, self.raw_pointers,
+            "mutable reference to a pooled value requested while a raw pointer to it is live"
+        );
         &mut self.value
     }
 
@@ -80,6 +139,28 @@ impl<A> RefBox<A> {
         &mut self.value
     }
 
+    /// Record that a raw pointer to the value has been handed out.
+    #[inline(always)]
+    pub(crate) fn raw_acquired(&mut self) {
+        #[cfg(feature = "debug-aliasing")]
+        {
+            self.raw_pointers += 1;
+        }
+    }
+
+    /// Record that a raw pointer to the value is no longer in use.
+    #[inline(always)]
+    pub(crate) fn raw_released(&mut self) {
+        #[cfg(feature = "debug-aliasing")]
+        {
+            assert!(
+                self.raw_pointers > 0,
+                "raw pointer to a pooled value released more times than it was acquired"
+            );
+            self.raw_pointers -= 1;
+        }
+    }
+
     #[inline(always)]
     pub(crate) fn inc(&mut self) {
         self.count.inc()
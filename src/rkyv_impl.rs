@@ -0,0 +1,177 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use rkyv::{Archive, Deserialize, Fallible, Infallible};
+
+use crate::box_handle::PoolBox;
+use crate::pool::Pool;
+use crate::ref_handle::PoolRef;
+
+impl<A> PoolRef<A>
+where
+    A: Archive,
+{
+    /// Deserialise an archived value into a new [`PoolRef`][PoolRef]
+    /// allocated from `pool`.
+    ///
+    /// A chunk is reserved from the pool before deserialising, and the value
+    /// is written straight into it, without going through a `Box` first. Use
+    /// [`from_archived_with()`][from_archived_with] for values which need a
+    /// deserialiser other than [`Infallible`][Infallible].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use refpool::{Pool, PoolRef};
+    /// let pool: Pool<Vec<u32>> = Pool::new(16);
+    /// let bytes = rkyv::to_bytes::<_, 256>(&vec![1u32, 2, 3]).unwrap();
+    /// let archived = unsafe { rkyv::archived_root::<Vec<u32>>(&bytes) };
+    /// let numbers = PoolRef::from_archived(&pool, archived);
+    /// assert_eq!(&[1, 2, 3], numbers.as_slice());
+    /// ```
+    ///
+    /// [PoolRef]: struct.PoolRef.html
+    /// [from_archived_with]: #method.from_archived_with
+    /// [Infallible]: https://docs.rs/rkyv/0.7/rkyv/struct.Infallible.html
+    #[cfg_attr(feature = "trace-alloc", track_caller)]
+    pub fn from_archived(pool: &Pool<A>, archived: &A::Archived) -> Self
+    where
+        A::Archived: Deserialize<A, Infallible>,
+    {
+        match Self::from_archived_with(pool, archived, &mut Infallible) {
+            Ok(value) => value,
+            Err(never) => match never {},
+        }
+    }
+
+    /// Deserialise an archived value into a new [`PoolRef`][PoolRef]
+    /// allocated from `pool`, using the provided deserialiser.
+    ///
+    /// If deserialisation fails, the reserved chunk goes back to the pool.
+    ///
+    /// [PoolRef]: struct.PoolRef.html
+    #[cfg_attr(feature = "trace-alloc", track_caller)]
+    pub fn from_archived_with<D>(
+        pool: &Pool<A>,
+        archived: &A::Archived,
+        deserializer: &mut D,
+    ) -> Result<Self, D::Error>
+    where
+        A::Archived: Deserialize<A, D>,
+        D: Fallible + ?Sized,
+    {
+        let chunk = pool.reserve();
+        let value = archived.deserialize(deserializer)?;
+        Ok(chunk.commit_ref(value))
+    }
+}
+
+impl<A> PoolBox<A>
+where
+    A: Archive,
+{
+    /// Deserialise an archived value into a new [`PoolBox`][PoolBox]
+    /// allocated from `pool`.
+    ///
+    /// [PoolBox]: struct.PoolBox.html
+    #[cfg_attr(feature = "trace-alloc", track_caller)]
+    pub fn from_archived(pool: &Pool<A>, archived: &A::Archived) -> Self
+    where
+        A::Archived: Deserialize<A, Infallible>,
+    {
+        match Self::from_archived_with(pool, archived, &mut Infallible) {
+            Ok(value) => value,
+            Err(never) => match never {},
+        }
+    }
+
+    /// Deserialise an archived value into a new [`PoolBox`][PoolBox]
+    /// allocated from `pool`, using the provided deserialiser.
+    ///
+    /// If deserialisation fails, the reserved chunk goes back to the pool.
+    ///
+    /// [PoolBox]: struct.PoolBox.html
+    #[cfg_attr(feature = "trace-alloc", track_caller)]
+    pub fn from_archived_with<D>(
+        pool: &Pool<A>,
+        archived: &A::Archived,
+        deserializer: &mut D,
+    ) -> Result<Self, D::Error>
+    where
+        A::Archived: Deserialize<A, D>,
+        D: Fallible + ?Sized,
+    {
+        let chunk = pool.reserve();
+        let value = archived.deserialize(deserializer)?;
+        Ok(chunk.commit_box(value))
+    }
+}
+
+impl<A> crate::fakepool::PoolRef<A>
+where
+    A: Archive,
+{
+    /// Deserialise an archived value into a new `PoolRef`.
+    #[inline(always)]
+    pub fn from_archived(pool: &crate::fakepool::Pool<A>, archived: &A::Archived) -> Self
+    where
+        A::Archived: Deserialize<A, Infallible>,
+    {
+        match Self::from_archived_with(pool, archived, &mut Infallible) {
+            Ok(value) => value,
+            Err(never) => match never {},
+        }
+    }
+
+    /// Deserialise an archived value into a new `PoolRef` using the provided
+    /// deserialiser.
+    #[inline(always)]
+    pub fn from_archived_with<D>(
+        pool: &crate::fakepool::Pool<A>,
+        archived: &A::Archived,
+        deserializer: &mut D,
+    ) -> Result<Self, D::Error>
+    where
+        A::Archived: Deserialize<A, D>,
+        D: Fallible + ?Sized,
+    {
+        archived
+            .deserialize(deserializer)
+            .map(|value| Self::new(pool, value))
+    }
+}
+
+impl<A> crate::fakepool::PoolBox<A>
+where
+    A: Archive,
+{
+    /// Deserialise an archived value into a new `PoolBox`.
+    #[inline(always)]
+    pub fn from_archived(pool: &crate::fakepool::Pool<A>, archived: &A::Archived) -> Self
+    where
+        A::Archived: Deserialize<A, Infallible>,
+    {
+        match Self::from_archived_with(pool, archived, &mut Infallible) {
+            Ok(value) => value,
+            Err(never) => match never {},
+        }
+    }
+
+    /// Deserialise an archived value into a new `PoolBox` using the provided
+    /// deserialiser.
+    #[inline(always)]
+    pub fn from_archived_with<D>(
+        pool: &crate::fakepool::Pool<A>,
+        archived: &A::Archived,
+        deserializer: &mut D,
+    ) -> Result<Self, D::Error>
+    where
+        A::Archived: Deserialize<A, D>,
+        D: Fallible + ?Sized,
+    {
+        archived
+            .deserialize(deserializer)
+            .map(|value| Self::new(pool, value))
+    }
+}
@@ -0,0 +1,44 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! AddressSanitizer annotations for chunks of memory sitting in a pool.
+//!
+//! A chunk in a pool is still allocated as far as AddressSanitizer is
+//! concerned, so it can't tell a pointer to a value which has gone back to
+//! the pool from a pointer to a live value. Poisoning chunks while they're in
+//! the pool fixes that. These do nothing unless the crate is actually being
+//! built with `-Zsanitizer=address`.
+
+use std::mem::size_of;
+
+use crate::refbox::RefBox;
+use crate::types::ElementPointer;
+
+#[cfg(sanitize = "address")]
+extern "C" {
+    fn __asan_poison_memory_region(addr: *const u8, size: usize);
+    fn __asan_unpoison_memory_region(addr: *const u8, size: usize);
+}
+
+/// Mark a chunk as off limits while it's in the pool.
+#[inline(always)]
+pub(crate) fn poison<A>(chunk: ElementPointer<A>) {
+    #[cfg(sanitize = "address")]
+    unsafe {
+        __asan_poison_memory_region(chunk.as_ptr().cast(), size_of::<RefBox<A>>())
+    };
+    #[cfg(not(sanitize = "address"))]
+    let _ = (chunk, size_of::<RefBox<A>>());
+}
+
+/// Mark a chunk as usable again as it leaves the pool.
+#[inline(always)]
+pub(crate) fn unpoison<A>(chunk: ElementPointer<A>) {
+    #[cfg(sanitize = "address")]
+    unsafe {
+        __asan_unpoison_memory_region(chunk.as_ptr().cast(), size_of::<RefBox<A>>())
+    };
+    #[cfg(not(sanitize = "address"))]
+    let _ = (chunk, size_of::<RefBox<A>>());
+}
@@ -0,0 +1,62 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::fmt::{Debug, Error, Formatter};
+
+use crate::pool::Pool;
+
+/// A [`Pool`][Pool] in transit between threads.
+///
+/// You get one of these from [`Pool::into_send_token()`][into_send_token],
+/// which only hands one out when the pool has no other handles and no values
+/// allocated from it are alive, so nothing else can be touching it. The token
+/// can be sent to another thread, where
+/// [`into_pool()`][into_pool] turns it back into a [`Pool`][Pool] which then
+/// belongs to that thread.
+///
+/// # Examples
+///
+/// ```rust
+/// # use refpool::Pool;
+/// let pool: Pool<usize> = Pool::new(16).filled();
+/// let token = pool.into_send_token().unwrap();
+/// std::thread::spawn(move || {
+///     let pool = token.into_pool();
+///     assert_eq!(16, pool.get_pool_size());
+/// })
+/// .join()
+/// .unwrap();
+/// ```
+///
+/// [Pool]: struct.Pool.html
+/// [into_send_token]: struct.Pool.html#method.into_send_token
+/// [into_pool]: #method.into_pool
+pub struct PoolSendToken<A> {
+    pool: Pool<A>,
+}
+
+// The pool is guaranteed to have no other handles and no live values, and it
+// holds nothing but uninitialised memory, so whichever thread holds the token
+// has exclusive access to it.
+unsafe impl<A> Send for PoolSendToken<A> {}
+
+impl<A> PoolSendToken<A> {
+    pub(crate) fn new(pool: Pool<A>) -> Self {
+        PoolSendToken { pool }
+    }
+
+    /// Turn the token back into a [`Pool`][Pool] owned by the current thread.
+    ///
+    /// [Pool]: struct.Pool.html
+    pub fn into_pool(self) -> Pool<A> {
+        self.pool.adopt();
+        self.pool
+    }
+}
+
+impl<A> Debug for PoolSendToken<A> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        write!(f, "PoolSendToken[{:?}]", self.pool)
+    }
+}
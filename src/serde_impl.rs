@@ -0,0 +1,175 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::fmt::{Debug, Error, Formatter};
+
+use serde::de::{Deserialize, DeserializeSeed, Deserializer};
+use serde::ser::{Serialize, Serializer};
+
+use crate::box_handle::PoolBox;
+use crate::pool::Pool;
+use crate::ref_handle::PoolRef;
+
+impl<A> Serialize for PoolRef<A>
+where
+    A: Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        (**self).serialize(serializer)
+    }
+}
+
+impl<A> Serialize for PoolBox<A>
+where
+    A: Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        (**self).serialize(serializer)
+    }
+}
+
+/// A [`DeserializeSeed`][DeserializeSeed] which deserialises a value into a
+/// [`PoolRef`][PoolRef] allocated from the pool it carries.
+///
+/// [`PoolRef`][PoolRef] and [`PoolBox`][PoolBox] can't implement
+/// [`Deserialize`][Deserialize], because there's no way to pass a pool to
+/// it, so you use this instead. Use [`PoolSeed::boxed()`][boxed] for a seed
+/// which deserialises into a [`PoolBox`][PoolBox].
+///
+/// # Examples
+///
+/// ```rust
+/// # use refpool::{Pool, PoolRef, PoolSeed};
+/// # use serde::de::DeserializeSeed;
+/// let pool: Pool<Vec<u32>> = Pool::new(16);
+/// let numbers = PoolRef::new(&pool, vec![1, 2, 3]);
+/// let json = serde_json::to_string(&numbers).unwrap();
+/// assert_eq!("[1,2,3]", json);
+///
+/// let mut deserializer = serde_json::Deserializer::from_str(&json);
+/// let copy = PoolSeed::new(&pool).deserialize(&mut deserializer).unwrap();
+/// assert_eq!(numbers, copy);
+/// ```
+///
+/// [DeserializeSeed]: https://docs.rs/serde/1/serde/de/trait.DeserializeSeed.html
+/// [Deserialize]: https://docs.rs/serde/1/serde/de/trait.Deserialize.html
+/// [PoolRef]: struct.PoolRef.html
+/// [PoolBox]: struct.PoolBox.html
+/// [boxed]: #method.boxed
+pub struct PoolSeed<'a, A> {
+    pool: &'a Pool<A>,
+}
+
+impl<'a, A> PoolSeed<'a, A> {
+    /// Construct a seed which allocates from `pool`.
+    pub fn new(pool: &'a Pool<A>) -> Self {
+        PoolSeed { pool }
+    }
+
+    /// Turn the seed into one which deserialises into a
+    /// [`PoolBox`][PoolBox].
+    ///
+    /// [PoolBox]: struct.PoolBox.html
+    pub fn boxed(self) -> PoolBoxSeed<'a, A> {
+        PoolBoxSeed { pool: self.pool }
+    }
+}
+
+impl<'a, 'de, A> DeserializeSeed<'de> for PoolSeed<'a, A>
+where
+    A: Deserialize<'de>,
+{
+    type Value = PoolRef<A>;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        A::deserialize(deserializer).map(|value| PoolRef::new(self.pool, value))
+    }
+}
+
+impl<'a, A> Clone for PoolSeed<'a, A> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, A> Copy for PoolSeed<'a, A> {}
+
+impl<'a, A> Debug for PoolSeed<'a, A> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        write!(f, "PoolSeed({:?})", self.pool)
+    }
+}
+
+/// A [`DeserializeSeed`][DeserializeSeed] which deserialises a value into a
+/// [`PoolBox`][PoolBox] allocated from the pool it carries.
+///
+/// You get one of these from [`PoolSeed::boxed()`][PoolSeed::boxed].
+///
+/// [DeserializeSeed]: https://docs.rs/serde/1/serde/de/trait.DeserializeSeed.html
+/// [PoolBox]: struct.PoolBox.html
+/// [PoolSeed::boxed]: struct.PoolSeed.html#method.boxed
+pub struct PoolBoxSeed<'a, A> {
+    pool: &'a Pool<A>,
+}
+
+impl<'a, 'de, A> DeserializeSeed<'de> for PoolBoxSeed<'a, A>
+where
+    A: Deserialize<'de>,
+{
+    type Value = PoolBox<A>;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        A::deserialize(deserializer).map(|value| PoolBox::new(self.pool, value))
+    }
+}
+
+impl<'a, A> Clone for PoolBoxSeed<'a, A> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, A> Copy for PoolBoxSeed<'a, A> {}
+
+impl<'a, A> Debug for PoolBoxSeed<'a, A> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        write!(f, "PoolBoxSeed({:?})", self.pool)
+    }
+}
+
+impl<A> Serialize for crate::fakepool::PoolRef<A>
+where
+    A: Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        (**self).serialize(serializer)
+    }
+}
+
+impl<A> Serialize for crate::fakepool::PoolBox<A>
+where
+    A: Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        (**self).serialize(serializer)
+    }
+}
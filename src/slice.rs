@@ -0,0 +1,413 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::alloc::{alloc, dealloc, handle_alloc_error, Layout};
+use std::borrow::{Borrow, BorrowMut};
+use std::cell::RefCell;
+use std::fmt::{Debug, Error, Formatter};
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut};
+use std::ptr::NonNull;
+use std::rc::Rc;
+
+/// The header in front of the elements of a [`PoolSlice`][PoolSlice].
+struct SliceHeader<A> {
+    pool: SlicePool<A>,
+    len: usize,
+}
+
+struct SlicePoolInner<A> {
+    len: usize,
+    max_size: usize,
+    layout: Layout,
+    offset: usize,
+    stack: RefCell<Vec<NonNull<u8>>>,
+    _type: PhantomData<A>,
+}
+
+impl<A> Drop for SlicePoolInner<A> {
+    fn drop(&mut self) {
+        for chunk in self.stack.get_mut().drain(..) {
+            unsafe { dealloc(chunk.as_ptr(), self.layout) };
+        }
+    }
+}
+
+/// A pool of preallocated memory for slices of `A` of a fixed length.
+///
+/// Slices are allocated from the pool as [`PoolSlice`][PoolSlice]s, which
+/// behave like a `Box<[A]>`, and go back to the pool when dropped. Every slice
+/// in a pool has the same length, so if you need slices of a few different
+/// lengths, such as I/O buffers in a few size classes, use one pool per
+/// length.
+///
+/// A `SlicePool` is much simpler than a [`Pool`][Pool]. It keeps its free
+/// memory on a plain stack and can't be configured with a
+/// [`PoolBuilder`][PoolBuilder], so it has no custom storage, statistics,
+/// overflow policies, groups, idle trimming, alignment or locked memory. It
+/// also isn't affected by the `disable-pool`, `sanitize`, `valgrind`,
+/// `debug-leaks` or `trace-alloc` feature flags.
+///
+/// # Examples
+///
+/// ```rust
+/// # use refpool::{PoolSlice, SlicePool};
+/// let pool: SlicePool<u8> = SlicePool::new(4096, 16);
+/// let mut buffer = PoolSlice::default(&pool);
+/// assert_eq!(4096, buffer.len());
+/// buffer[..5].copy_from_slice(b"hello");
+/// drop(buffer);
+/// assert_eq!(1, pool.get_pool_size());
+/// ```
+///
+/// [Pool]: struct.Pool.html
+/// [PoolBuilder]: struct.PoolBuilder.html
+/// [PoolSlice]: struct.PoolSlice.html
+pub struct SlicePool<A> {
+    inner: Rc<SlicePoolInner<A>>,
+}
+
+impl<A> SlicePool<A> {
+    /// Construct a pool for slices of `len` elements, holding at most
+    /// `max_size` slices' worth of memory.
+    ///
+    /// # Panics
+    ///
+    /// This panics if a slice of `len` elements of `A` would be too big to
+    /// allocate.
+    pub fn new(len: usize, max_size: usize) -> Self {
+        let (layout, offset) = Layout::new::<SliceHeader<A>>()
+            .extend(Layout::array::<A>(len).expect("SlicePool::new: slice too large"))
+            .expect("SlicePool::new: slice too large");
+        SlicePool {
+            inner: Rc::new(SlicePoolInner {
+                len,
+                max_size,
+                layout: layout.pad_to_align(),
+                offset,
+                stack: RefCell::new(Vec::with_capacity(max_size)),
+                _type: PhantomData,
+            }),
+        }
+    }
+
+    /// Get the length of the slices in this pool.
+    pub fn slice_len(&self) -> usize {
+        self.inner.len
+    }
+
+    /// Get the maximum size of the pool.
+    pub fn get_max_size(&self) -> usize {
+        self.inner.max_size
+    }
+
+    /// Get the current size of the pool.
+    pub fn get_pool_size(&self) -> usize {
+        self.inner.stack.borrow().len()
+    }
+
+    /// Fill the pool with empty allocations.
+    pub fn fill(&self) {
+        let mut stack = self.inner.stack.borrow_mut();
+        while stack.len() < self.inner.max_size {
+            stack.push(self.alloc_chunk());
+        }
+    }
+
+    fn alloc_chunk(&self) -> NonNull<u8> {
+        let layout = self.inner.layout;
+        NonNull::new(unsafe { alloc(layout) }).unwrap_or_else(|| handle_alloc_error(layout))
+    }
+
+    /// Take a chunk from the pool, or from the system allocator if the pool
+    /// is empty, and write a header to it.
+    fn pop(&self) -> NonNull<SliceHeader<A>> {
+        let chunk = self
+            .inner
+            .stack
+            .borrow_mut()
+            .pop()
+            .unwrap_or_else(|| self.alloc_chunk())
+            .cast::<SliceHeader<A>>();
+        unsafe {
+            chunk.as_ptr().write(SliceHeader {
+                pool: self.clone(),
+                len: self.inner.len,
+            })
+        };
+        chunk
+    }
+
+    /// Give a chunk back to the pool, or to the system allocator if the pool
+    /// is full.
+    fn push(&self, chunk: NonNull<u8>) {
+        let mut stack = self.inner.stack.borrow_mut();
+        if stack.len() < self.inner.max_size {
+            stack.push(chunk);
+        } else {
+            unsafe { dealloc(chunk.as_ptr(), self.inner.layout) };
+        }
+    }
+}
+
+impl<A> Clone for SlicePool<A> {
+    fn clone(&self) -> Self {
+        SlicePool {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<A> Debug for SlicePool<A> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        write!(
+            f,
+            "SlicePool[{}x{}/{}]:{:p}",
+            self.get_pool_size(),
+            self.inner.len,
+            self.inner.max_size,
+            Rc::as_ptr(&self.inner)
+        )
+    }
+}
+
+/// A pool allocated slice of `A`.
+///
+/// This is the slice equivalent of a [`PoolBox`][PoolBox], allocated from a
+/// [`SlicePool`][SlicePool], and its length is always the length of the pool's
+/// slices.
+///
+/// [PoolBox]: struct.PoolBox.html
+/// [SlicePool]: struct.SlicePool.html
+pub struct PoolSlice<A> {
+    header: NonNull<SliceHeader<A>>,
+    _type: PhantomData<A>,
+}
+
+impl<A> PoolSlice<A> {
+    /// Construct a slice with every element initialised by calling `f` with
+    /// its index.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use refpool::{PoolSlice, SlicePool};
+    /// let pool: SlicePool<usize> = SlicePool::new(4, 1);
+    /// let squares = PoolSlice::from_fn(&pool, |i| i * i);
+    /// assert_eq!(&[0, 1, 4, 9], &*squares);
+    /// ```
+    pub fn from_fn<F>(pool: &SlicePool<A>, mut f: F) -> Self
+    where
+        F: FnMut(usize) -> A,
+    {
+        let header = pool.pop();
+        let elements = unsafe {
+            header
+                .cast::<u8>()
+                .as_ptr()
+                .add(pool.inner.offset)
+                .cast::<A>()
+        };
+
+        // If `f` panics, drop the elements written so far and give the memory
+        // back to the pool.
+        struct Partial<A> {
+            header: NonNull<SliceHeader<A>>,
+            elements: *mut A,
+            written: usize,
+        }
+        impl<A> Drop for Partial<A> {
+            fn drop(&mut self) {
+                unsafe {
+                    std::ptr::slice_from_raw_parts_mut(self.elements, self.written).drop_in_place();
+                    let pool = self.header.as_ptr().read().pool;
+                    pool.push(self.header.cast());
+                }
+            }
+        }
+
+        let mut partial = Partial {
+            header,
+            elements,
+            written: 0,
+        };
+        while partial.written < pool.inner.len {
+            unsafe { elements.add(partial.written).write(f(partial.written)) };
+            partial.written += 1;
+        }
+        std::mem::forget(partial);
+        PoolSlice {
+            header,
+            _type: PhantomData,
+        }
+    }
+
+    /// Construct a slice without initialising its elements.
+    ///
+    /// This is for buffers which track how much of themselves they've
+    /// written, like [`PoolString`][PoolString]: the elements mustn't be read
+    /// through the slice, by dereferencing or cloning it, until they've all
+    /// been written. `A` has to be `Copy` so that dropping the slice doesn't
+    /// touch them either.
+    ///
+    /// [PoolString]: struct.PoolString.html
+    pub(crate) unsafe fn uninit(pool: &SlicePool<A>) -> Self
+    where
+        A: Copy,
+    {
+        PoolSlice {
+            header: pool.pop(),
+            _type: PhantomData,
+        }
+    }
+
+    /// Get a pointer to the first element of the slice.
+    pub(crate) fn as_ptr(this: &Self) -> *const A {
+        this.elements()
+    }
+
+    /// Get a mutable pointer to the first element of the slice.
+    pub(crate) fn as_mut_ptr(this: &mut Self) -> *mut A {
+        this.elements()
+    }
+
+    /// Construct a slice with every element set to `A::default()`.
+    pub fn default(pool: &SlicePool<A>) -> Self
+    where
+        A: Default,
+    {
+        Self::from_fn(pool, |_| A::default())
+    }
+
+    /// Construct a slice with every element set to a clone of `value`.
+    pub fn new(pool: &SlicePool<A>, value: A) -> Self
+    where
+        A: Clone,
+    {
+        Self::from_fn(pool, |_| value.clone())
+    }
+
+    /// Construct a slice by cloning the elements of `values`.
+    ///
+    /// # Panics
+    ///
+    /// This panics if the length of `values` isn't the length of the pool's
+    /// slices.
+    pub fn from_slice(pool: &SlicePool<A>, values: &[A]) -> Self
+    where
+        A: Clone,
+    {
+        assert_eq!(
+            pool.inner.len,
+            values.len(),
+            "PoolSlice::from_slice: slice length doesn't match the pool"
+        );
+        Self::from_fn(pool, |index| values[index].clone())
+    }
+
+    fn header(&self) -> &SliceHeader<A> {
+        unsafe { self.header.as_ref() }
+    }
+
+    fn elements(&self) -> *mut A {
+        unsafe {
+            self.header
+                .cast::<u8>()
+                .as_ptr()
+                .add(self.header().pool.inner.offset)
+                .cast::<A>()
+        }
+    }
+
+    /// Get a reference to the pool the slice was allocated from.
+    pub fn pool(this: &Self) -> &SlicePool<A> {
+        &this.header().pool
+    }
+}
+
+impl<A> Drop for PoolSlice<A> {
+    fn drop(&mut self) {
+        unsafe {
+            std::ptr::slice_from_raw_parts_mut(self.elements(), self.header().len).drop_in_place();
+            let pool = self.header.as_ptr().read().pool;
+            pool.push(self.header.cast());
+        }
+    }
+}
+
+impl<A> Clone for PoolSlice<A>
+where
+    A: Clone,
+{
+    fn clone(&self) -> Self {
+        Self::from_slice(&self.header().pool, self)
+    }
+}
+
+impl<A> Deref for PoolSlice<A> {
+    type Target = [A];
+    fn deref(&self) -> &Self::Target {
+        unsafe { std::slice::from_raw_parts(self.elements(), self.header().len) }
+    }
+}
+
+impl<A> DerefMut for PoolSlice<A> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        unsafe { std::slice::from_raw_parts_mut(self.elements(), self.header().len) }
+    }
+}
+
+impl<A> AsRef<[A]> for PoolSlice<A> {
+    fn as_ref(&self) -> &[A] {
+        self.deref()
+    }
+}
+
+impl<A> AsMut<[A]> for PoolSlice<A> {
+    fn as_mut(&mut self) -> &mut [A] {
+        self.deref_mut()
+    }
+}
+
+impl<A> Borrow<[A]> for PoolSlice<A> {
+    fn borrow(&self) -> &[A] {
+        self.deref()
+    }
+}
+
+impl<A> BorrowMut<[A]> for PoolSlice<A> {
+    fn borrow_mut(&mut self) -> &mut [A] {
+        self.deref_mut()
+    }
+}
+
+impl<A> PartialEq for PoolSlice<A>
+where
+    A: PartialEq,
+{
+    fn eq(&self, other: &Self) -> bool {
+        **self == **other
+    }
+}
+
+impl<A> Eq for PoolSlice<A> where A: Eq {}
+
+impl<A> Hash for PoolSlice<A>
+where
+    A: Hash,
+{
+    fn hash<H: Hasher>(&self, hasher: &mut H) {
+        (**self).hash(hasher)
+    }
+}
+
+impl<A> Debug for PoolSlice<A>
+where
+    A: Debug,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        (**self).fmt(f)
+    }
+}
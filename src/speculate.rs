@@ -0,0 +1,149 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::collections::HashSet;
+use std::fmt::{Debug, Error, Formatter};
+use std::ops::Deref;
+
+use crate::pool::Pool;
+use crate::stats::PoolStats;
+
+/// The state of a pool during a speculation.
+#[derive(Debug)]
+pub(crate) struct Speculation {
+    /// The addresses of chunks allocated during the speculation which haven't
+    /// been returned yet.
+    live: HashSet<usize>,
+    /// The pool's statistics when the speculation began.
+    stats: Option<PoolStats>,
+    /// The returns and discards of chunks allocated during the speculation.
+    returned: PoolStats,
+}
+
+impl Speculation {
+    pub(crate) fn new(stats: Option<PoolStats>) -> Self {
+        Speculation {
+            live: HashSet::new(),
+            stats,
+            returned: PoolStats::default(),
+        }
+    }
+
+    pub(crate) fn allocated(&mut self, chunk: *const ()) {
+        self.live.insert(chunk as usize);
+    }
+
+    pub(crate) fn freed(&mut self, chunk: *const (), discarded: bool) {
+        if self.live.remove(&(chunk as usize)) {
+            if discarded {
+                self.returned.discards += 1;
+            } else {
+                self.returned.returns += 1;
+            }
+        }
+    }
+
+    /// Check that everything allocated during the speculation has been
+    /// returned, and give back the pool's current statistics `stats` with
+    /// the speculation's share taken out.
+    pub(crate) fn rollback(self, stats: Option<PoolStats>) -> Option<PoolStats> {
+        assert!(
+            self.live.is_empty(),
+            "SpeculativePool::rollback: {} values allocated during the speculation are still alive",
+            self.live.len()
+        );
+        // Every allocation made during the speculation belongs to it, but
+        // values allocated before it may have come back during it, and those
+        // returns stay on the books.
+        match (self.stats, stats) {
+            (Some(before), Some(now)) => Some(PoolStats {
+                hits: before.hits,
+                misses: before.misses,
+                returns: now.returns - self.returned.returns,
+                discards: now.discards - self.returned.discards,
+            }),
+            _ => stats,
+        }
+    }
+}
+
+/// A view of a [`Pool`][Pool] which keeps track of the allocations made
+/// through it, so that they can be rolled back.
+///
+/// You obtain a `SpeculativePool` from [`Pool::speculate()`][speculate], and
+/// you allocate from it just as you would from the [`Pool`][Pool] itself,
+/// which it dereferences to. When you're done, either
+/// [`commit()`][commit] the speculation, which keeps everything allocated
+/// during it, or [`rollback()`][rollback], which checks that everything
+/// allocated during the speculation has been dropped and takes the
+/// allocations made during the speculation, and their returns, back out of the
+/// pool's statistics, so that an abandoned branch of work leaves no trace in
+/// the pool's accounting.
+///
+/// Dropping a `SpeculativePool` without calling either commits it.
+///
+/// A pool can only run one speculation at a time, and a pool with a maximum
+/// size of zero doesn't keep track of anything.
+///
+/// # Examples
+///
+/// ```rust
+/// # use refpool::{Pool, PoolRef};
+/// let pool: Pool<usize> = Pool::builder().max_size(16).stats(true).build();
+/// let kept = PoolRef::new(&pool, 1);
+/// let speculation = pool.speculate();
+/// let branch: Vec<_> = (0..8).map(|i| PoolRef::new(&speculation, i)).collect();
+/// // The branch turns out to be a dead end.
+/// drop(branch);
+/// speculation.rollback();
+/// assert_eq!(1, pool.stats().unwrap().misses);
+/// assert_eq!(8, pool.get_pool_size());
+/// ```
+///
+/// [Pool]: struct.Pool.html
+/// [speculate]: struct.Pool.html#method.speculate
+/// [commit]: #method.commit
+/// [rollback]: #method.rollback
+pub struct SpeculativePool<A> {
+    pool: Pool<A>,
+}
+
+impl<A> SpeculativePool<A> {
+    pub(crate) fn new(pool: &Pool<A>) -> Self {
+        pool.begin_speculation();
+        SpeculativePool { pool: pool.clone() }
+    }
+
+    /// End the speculation, keeping everything allocated during it.
+    pub fn commit(self) {}
+
+    /// End the speculation, undoing its effects on the pool's statistics.
+    ///
+    /// # Panics
+    ///
+    /// This panics if any value allocated during the speculation is still
+    /// alive.
+    pub fn rollback(self) {
+        self.pool.rollback_speculation();
+    }
+}
+
+impl<A> Deref for SpeculativePool<A> {
+    type Target = Pool<A>;
+    fn deref(&self) -> &Self::Target {
+        &self.pool
+    }
+}
+
+impl<A> Drop for SpeculativePool<A> {
+    fn drop(&mut self) {
+        self.pool.end_speculation();
+    }
+}
+
+impl<A> Debug for SpeculativePool<A> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        write!(f, "SpeculativePool[{:?}]", self.pool)
+    }
+}
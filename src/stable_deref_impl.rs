@@ -0,0 +1,19 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use stable_deref_trait::{CloneStableDeref, StableDeref};
+
+use crate::box_handle::PoolBox;
+use crate::ref_handle::PoolRef;
+
+// A pooled value lives in its own chunk of heap memory, which doesn't move
+// while any handle to it is alive, whichever pool it came from.
+
+unsafe impl<A> StableDeref for PoolRef<A> {}
+unsafe impl<A> CloneStableDeref for PoolRef<A> {}
+unsafe impl<A> StableDeref for PoolBox<A> {}
+
+unsafe impl<A> StableDeref for crate::fakepool::PoolRef<A> {}
+unsafe impl<A> CloneStableDeref for crate::fakepool::PoolRef<A> {}
+unsafe impl<A> StableDeref for crate::fakepool::PoolBox<A> {}
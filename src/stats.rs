@@ -0,0 +1,26 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+/// Usage statistics for a [`Pool`][Pool].
+///
+/// Statistics are only collected for pools constructed with
+/// [`PoolBuilder::stats(true)`][PoolBuilder::stats], and can be retrieved
+/// using [`Pool::stats()`][Pool::stats].
+///
+/// [Pool]: struct.Pool.html
+/// [Pool::stats]: struct.Pool.html#method.stats
+/// [PoolBuilder::stats]: struct.PoolBuilder.html#method.stats
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct PoolStats {
+    /// The number of allocations which were served from memory in the pool.
+    pub hits: usize,
+    /// The number of allocations which had to fall back to the system
+    /// allocator because the pool was empty.
+    pub misses: usize,
+    /// The number of allocations which were returned to the pool.
+    pub returns: usize,
+    /// The number of allocations which were handed back to the system
+    /// allocator because the pool was full.
+    pub discards: usize,
+}
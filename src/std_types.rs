@@ -2,11 +2,17 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
+use std::cell::{Cell, RefCell};
 use std::collections::{BTreeMap, BTreeSet, BinaryHeap, HashMap, HashSet, LinkedList, VecDeque};
+use std::ffi::{CString, OsString};
 use std::hash::{BuildHasher, Hash};
 use std::mem::MaybeUninit;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6};
 use std::path::PathBuf;
+use std::ptr::addr_of_mut;
+use std::sync::{Mutex, RwLock};
 
+use crate::macros::{clone_field_uninit, default_field_uninit};
 use crate::{PoolClone, PoolDefault};
 
 /// A marker trait for types which should be fully initialised.
@@ -69,10 +75,41 @@ impl PoolDefaultImpl for i64 {}
 impl PoolDefaultImpl for i128 {}
 impl PoolDefaultImpl for isize {}
 
-impl<A> PoolDefaultImpl for Option<A> {}
+impl<A> PoolClone for Option<A>
+where
+    A: Clone,
+{
+    unsafe fn clone_uninit(&self, target: &mut MaybeUninit<Self>) {
+        target.as_mut_ptr().write(self.clone());
+    }
+}
 
 impl PoolDefaultImpl for String {}
 impl PoolDefaultImpl for PathBuf {}
+impl PoolDefaultImpl for OsString {}
+impl PoolDefaultImpl for CString {}
+
+// Network addresses have no default, so they can only be cloned.
+macro_rules! impl_clone_only {
+    ($($type:ty),+) => {
+        $(
+            impl PoolClone for $type {
+                unsafe fn clone_uninit(&self, target: &mut MaybeUninit<Self>) {
+                    target.as_mut_ptr().write(*self);
+                }
+            }
+        )+
+    };
+}
+
+impl_clone_only!(
+    IpAddr,
+    Ipv4Addr,
+    Ipv6Addr,
+    SocketAddr,
+    SocketAddrV4,
+    SocketAddrV6
+);
 
 impl<A> PoolDefaultImpl for Vec<A> {}
 impl<A> PoolDefaultImpl for VecDeque<A> {}
@@ -83,72 +120,69 @@ impl<A: Ord> PoolDefaultImpl for BTreeSet<A> {}
 impl<A: Ord> PoolDefaultImpl for BinaryHeap<A> {}
 impl<A> PoolDefaultImpl for LinkedList<A> {}
 
-impl<A, B> PoolDefaultImpl for (A, B)
-where
-    A: PoolDefaultImpl,
-    B: PoolDefaultImpl,
-{
-}
-
-impl<A, B, C> PoolDefaultImpl for (A, B, C)
-where
-    A: PoolDefaultImpl,
-    B: PoolDefaultImpl,
-    C: PoolDefaultImpl,
-{
-}
-
-impl<A, B, C, D> PoolDefaultImpl for (A, B, C, D)
-where
-    A: PoolDefaultImpl,
-    B: PoolDefaultImpl,
-    C: PoolDefaultImpl,
-    D: PoolDefaultImpl,
-{
-}
+impl<A: Default> PoolDefaultImpl for RefCell<A> {}
+impl<A: Default> PoolDefaultImpl for Mutex<A> {}
+impl<A: Default> PoolDefaultImpl for RwLock<A> {}
 
-impl<A, B, C, D, E> PoolDefaultImpl for (A, B, C, D, E)
+// A `Cell<A>` has the same in-memory representation as an `A`, so it can
+// initialise itself however its contents would.
+impl<A> PoolDefault for Cell<A>
 where
-    A: PoolDefaultImpl,
-    B: PoolDefaultImpl,
-    C: PoolDefaultImpl,
-    D: PoolDefaultImpl,
-    E: PoolDefaultImpl,
+    A: PoolDefault,
 {
+    unsafe fn default_uninit(target: &mut MaybeUninit<Self>) {
+        A::default_uninit(&mut *target.as_mut_ptr().cast::<MaybeUninit<A>>());
+    }
 }
 
-impl<A, B, C, D, E, F> PoolDefaultImpl for (A, B, C, D, E, F)
+impl<A> PoolClone for Cell<A>
 where
-    A: PoolDefaultImpl,
-    B: PoolDefaultImpl,
-    C: PoolDefaultImpl,
-    D: PoolDefaultImpl,
-    E: PoolDefaultImpl,
-    F: PoolDefaultImpl,
+    A: Copy,
 {
+    unsafe fn clone_uninit(&self, target: &mut MaybeUninit<Self>) {
+        target.as_mut_ptr().write(self.clone());
+    }
 }
 
-impl<A, B, C, D, E, F, G> PoolDefaultImpl for (A, B, C, D, E, F, G)
-where
-    A: PoolDefaultImpl,
-    B: PoolDefaultImpl,
-    C: PoolDefaultImpl,
-    D: PoolDefaultImpl,
-    E: PoolDefaultImpl,
-    F: PoolDefaultImpl,
-    G: PoolDefaultImpl,
-{
+// Tuples initialise and clone each of their elements in place, using the
+// elements' own implementations, so an element which only needs part of its
+// memory initialised keeps that advantage inside a tuple.
+macro_rules! impl_tuple {
+    ($($type:ident $index:tt),+) => {
+        impl<$($type),+> PoolDefault for ($($type,)+)
+        where
+            $($type: PoolDefault),+
+        {
+            unsafe fn default_uninit(target: &mut MaybeUninit<Self>) {
+                let target = target.as_mut_ptr();
+                $(
+                    default_field_uninit(addr_of_mut!((*target).$index));
+                )+
+            }
+        }
+
+        impl<$($type),+> PoolClone for ($($type,)+)
+        where
+            $($type: PoolClone),+
+        {
+            unsafe fn clone_uninit(&self, target: &mut MaybeUninit<Self>) {
+                let target = target.as_mut_ptr();
+                $(
+                    clone_field_uninit(&self.$index, addr_of_mut!((*target).$index));
+                )+
+            }
+        }
+    };
 }
 
-impl<A, B, C, D, E, F, G, H> PoolDefaultImpl for (A, B, C, D, E, F, G, H)
-where
-    A: PoolDefaultImpl,
-    B: PoolDefaultImpl,
-    C: PoolDefaultImpl,
-    D: PoolDefaultImpl,
-    E: PoolDefaultImpl,
-    F: PoolDefaultImpl,
-    G: PoolDefaultImpl,
-    H: PoolDefaultImpl,
-{
-}
+impl_tuple!(A 0, B 1);
+impl_tuple!(A 0, B 1, C 2);
+impl_tuple!(A 0, B 1, C 2, D 3);
+impl_tuple!(A 0, B 1, C 2, D 3, E 4);
+impl_tuple!(A 0, B 1, C 2, D 3, E 4, F 5);
+impl_tuple!(A 0, B 1, C 2, D 3, E 4, F 5, G 6);
+impl_tuple!(A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7);
+impl_tuple!(A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8);
+impl_tuple!(A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8, J 9);
+impl_tuple!(A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8, J 9, K 10);
+impl_tuple!(A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8, J 9, K 10, L 11);
@@ -0,0 +1,559 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::collections::VecDeque;
+use std::fmt::{Debug, Error, Formatter};
+use std::mem::MaybeUninit;
+use std::ptr::NonNull;
+
+#[cfg(not(feature = "disable-pool"))]
+use crate::types::ElementPointer;
+
+/// A strategy for storing the free chunks of memory in a [`Pool`][Pool].
+///
+/// A pool keeps its free chunks in a [`VecStack`][VecStack] unless you choose
+/// something else with [`PoolBuilder::storage()`][PoolBuilder::storage]. You
+/// can implement this trait to provide your own strategy, which decides the
+/// order in which chunks are reused. The pool itself keeps track of its
+/// maximum size, so a storage only needs to hold on to the chunks it's given.
+///
+/// The storage is chosen when the pool is built, rather than being a type
+/// parameter of [`Pool`][Pool]. Every [`PoolRef`][PoolRef] and
+/// [`PoolBox`][PoolBox] holds on to its pool, so a storage type parameter
+/// would have to be carried by every handle type and everything built on top
+/// of them, and code which doesn't care how a pool stores its memory would
+/// have to be generic over it all the same. The default
+/// [`VecStack`][VecStack] is kept inline in the pool, so only other storages
+/// pay for a dynamic call when a chunk is stored or removed.
+///
+/// # Safety
+///
+/// The chunk pointers are opaque and must never be dereferenced. Every pointer
+/// returned from [`pop()`][pop] must have been given to [`push()`][push] and
+/// not popped since, and [`len()`][len] must always return the number of
+/// chunks currently stored. Any chunks left in the storage when it's dropped
+/// are freed by the pool, so the storage shouldn't try to free them itself.
+///
+/// # Examples
+///
+#[cfg_attr(feature = "disable-pool", doc = "```ignore")]
+#[cfg_attr(not(feature = "disable-pool"), doc = "```rust")]
+/// # use refpool::{Pool, PoolRef, PoolStorage};
+/// # use std::ptr::NonNull;
+/// /// A storage which holds on to at most one chunk.
+/// #[derive(Default)]
+/// struct OneChunk(Option<NonNull<u8>>);
+///
+/// unsafe impl Send for OneChunk {}
+///
+/// unsafe impl PoolStorage for OneChunk {
+///     fn with_capacity(_capacity: usize) -> Self {
+///         Self::default()
+///     }
+///     fn push(&mut self, chunk: NonNull<u8>) {
+///         assert!(self.0.replace(chunk).is_none());
+///     }
+///     fn pop(&mut self) -> Option<NonNull<u8>> {
+///         self.0.take()
+///     }
+///     fn len(&self) -> usize {
+///         self.0.iter().count()
+///     }
+///     fn is_full(&self) -> bool {
+///         self.0.is_some()
+///     }
+/// }
+///
+/// let pool: Pool<usize> = Pool::builder().max_size(16).storage::<OneChunk>().build();
+/// pool.fill();
+/// assert_eq!(1, pool.get_pool_size());
+/// ```
+///
+/// [Pool]: struct.Pool.html
+/// [PoolRef]: struct.PoolRef.html
+/// [PoolBox]: struct.PoolBox.html
+/// [VecStack]: struct.VecStack.html
+/// [PoolBuilder::storage]: struct.PoolBuilder.html#method.storage
+/// [push]: #tymethod.push
+/// [pop]: #tymethod.pop
+/// [len]: #tymethod.len
+pub unsafe trait PoolStorage: Send + 'static {
+    /// Construct an empty storage for a pool with the given maximum size.
+    fn with_capacity(capacity: usize) -> Self
+    where
+        Self: Sized;
+
+    /// Store a free chunk.
+    ///
+    /// This is never called while [`is_full()`][is_full] returns `true`.
+    ///
+    /// [is_full]: #method.is_full
+    fn push(&mut self, chunk: NonNull<u8>);
+
+    /// Remove a chunk from the storage, if there is one.
+    fn pop(&mut self) -> Option<NonNull<u8>>;
+
+    /// Get the number of chunks in the storage.
+    fn len(&self) -> usize;
+
+    /// Test whether the storage is empty.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Test whether the storage can't hold any more chunks, regardless of the
+    /// pool's maximum size.
+    ///
+    /// This defaults to `false`, for storages which can grow as needed.
+    fn is_full(&self) -> bool {
+        false
+    }
+
+    /// Call `f` with every chunk in the storage, in any order.
+    ///
+    /// This is only used for debugging, by
+    /// [`Pool::debug_dump()`][Pool::debug_dump]. The default implementation
+    /// doesn't visit any chunks, so a pool using a storage which doesn't
+    /// override it will look empty there.
+    ///
+    /// [Pool::debug_dump]: struct.Pool.html#method.debug_dump
+    fn for_each_chunk(&self, f: &mut dyn FnMut(NonNull<u8>)) {
+        let _ = f;
+    }
+}
+
+/// The default [`PoolStorage`][PoolStorage], which keeps free chunks on a
+/// stack, so that the most recently freed chunk is the first to be reused.
+///
+/// This is the best choice for most uses, because the most recently freed
+/// chunk is the most likely to still be in the CPU's cache.
+///
+/// [PoolStorage]: trait.PoolStorage.html
+#[derive(Default)]
+pub struct VecStack {
+    stack: Vec<NonNull<u8>>,
+}
+
+unsafe impl Send for VecStack {}
+
+unsafe impl PoolStorage for VecStack {
+    fn with_capacity(capacity: usize) -> Self {
+        VecStack {
+            stack: Vec::with_capacity(capacity),
+        }
+    }
+
+    #[inline(always)]
+    fn push(&mut self, chunk: NonNull<u8>) {
+        self.stack.push(chunk);
+    }
+
+    #[inline(always)]
+    fn pop(&mut self) -> Option<NonNull<u8>> {
+        self.stack.pop()
+    }
+
+    #[inline(always)]
+    fn len(&self) -> usize {
+        self.stack.len()
+    }
+
+    fn for_each_chunk(&self, f: &mut dyn FnMut(NonNull<u8>)) {
+        self.stack.iter().copied().for_each(f);
+    }
+}
+
+impl Debug for VecStack {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        write!(f, "VecStack[{}]", self.stack.len())
+    }
+}
+
+/// A [`PoolStorage`][PoolStorage] which keeps free chunks in a queue, so that
+/// the chunk which has been free the longest is the first to be reused.
+///
+/// This gives up the cache warmth of [`VecStack`][VecStack], but a chunk sits
+/// in the pool for as long as possible before it's reused, which makes a use
+/// after free more likely to show up in tests with memory checking tools, and
+/// spreads reuse evenly over all the pool's chunks.
+///
+/// # Examples
+///
+#[cfg_attr(feature = "disable-pool", doc = "```ignore")]
+#[cfg_attr(not(feature = "disable-pool"), doc = "```rust")]
+/// # use refpool::{Pool, PoolRef, VecQueue};
+/// let pool: Pool<usize> = Pool::builder().max_size(16).storage::<VecQueue>().build();
+/// let first = PoolRef::new(&pool, 1);
+/// let second = PoolRef::new(&pool, 2);
+/// let first_ptr = PoolRef::into_raw(first);
+/// drop(unsafe { PoolRef::from_raw(first_ptr) });
+/// drop(second);
+/// let third = PoolRef::new(&pool, 3);
+/// assert_eq!(first_ptr, &*third as *const usize);
+/// ```
+///
+/// [PoolStorage]: trait.PoolStorage.html
+/// [VecStack]: struct.VecStack.html
+#[derive(Default)]
+pub struct VecQueue {
+    queue: VecDeque<NonNull<u8>>,
+}
+
+unsafe impl Send for VecQueue {}
+
+unsafe impl PoolStorage for VecQueue {
+    fn with_capacity(capacity: usize) -> Self {
+        VecQueue {
+            queue: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    #[inline(always)]
+    fn push(&mut self, chunk: NonNull<u8>) {
+        self.queue.push_back(chunk);
+    }
+
+    #[inline(always)]
+    fn pop(&mut self) -> Option<NonNull<u8>> {
+        self.queue.pop_front()
+    }
+
+    #[inline(always)]
+    fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    fn for_each_chunk(&self, f: &mut dyn FnMut(NonNull<u8>)) {
+        self.queue.iter().copied().for_each(f);
+    }
+}
+
+impl Debug for VecQueue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        write!(f, "VecQueue[{}]", self.queue.len())
+    }
+}
+
+/// A [`PoolStorage`][PoolStorage] which keeps up to `N` free chunks on a stack
+/// in a fixed size array.
+///
+/// This works like [`VecStack`][VecStack], but it never reallocates, and a pool
+/// using it never holds more than `N` chunks, regardless of its maximum size.
+///
+/// Like any storage chosen through
+/// [`PoolBuilder::storage()`][PoolBuilder::storage], it's boxed separately
+/// from the pool. [`Pool::fixed()`][Pool::fixed] gives you a pool which works
+/// the same way, but keeps its array in the same allocation as the rest of
+/// the pool, so prefer that unless you need to combine it with other builder
+/// options.
+///
+/// [PoolStorage]: trait.PoolStorage.html
+/// [VecStack]: struct.VecStack.html
+/// [Pool::fixed]: struct.Pool.html#method.fixed
+/// [PoolBuilder::storage]: struct.PoolBuilder.html#method.storage
+pub struct FixedStack<const N: usize> {
+    len: usize,
+    stack: [MaybeUninit<NonNull<u8>>; N],
+}
+
+unsafe impl<const N: usize> Send for FixedStack<N> {}
+
+impl<const N: usize> Default for FixedStack<N> {
+    fn default() -> Self {
+        FixedStack {
+            len: 0,
+            stack: [MaybeUninit::uninit(); N],
+        }
+    }
+}
+
+unsafe impl<const N: usize> PoolStorage for FixedStack<N> {
+    fn with_capacity(_capacity: usize) -> Self {
+        Self::default()
+    }
+
+    #[inline(always)]
+    fn push(&mut self, chunk: NonNull<u8>) {
+        self.stack[self.len] = MaybeUninit::new(chunk);
+        self.len += 1;
+    }
+
+    #[inline(always)]
+    fn pop(&mut self) -> Option<NonNull<u8>> {
+        if self.len == 0 {
+            None
+        } else {
+            self.len -= 1;
+            Some(unsafe { self.stack[self.len].assume_init() })
+        }
+    }
+
+    #[inline(always)]
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    #[inline(always)]
+    fn is_full(&self) -> bool {
+        self.len == N
+    }
+
+    fn for_each_chunk(&self, f: &mut dyn FnMut(NonNull<u8>)) {
+        for chunk in &self.stack[..self.len] {
+            f(unsafe { chunk.assume_init() });
+        }
+    }
+}
+
+impl<const N: usize> Debug for FixedStack<N> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        write!(f, "FixedStack[{}/{}]", self.len, N)
+    }
+}
+
+/// A [`PoolStorage`][PoolStorage] which keeps free chunks in a ring buffer, so
+/// that the chunk which has been free the longest is the first to be reused.
+///
+/// This reuses chunks in the same order as [`VecQueue`][VecQueue], but its
+/// capacity is fixed at the pool's maximum size rounded up to a power of two,
+/// which lets it wrap its indices with a mask instead of a branch. This suits
+/// pools with a steady number of values in use, where chunks are constantly
+/// going in and out of the pool.
+///
+/// # Examples
+///
+#[cfg_attr(feature = "disable-pool", doc = "```ignore")]
+#[cfg_attr(not(feature = "disable-pool"), doc = "```rust")]
+/// # use refpool::{Pool, PoolRef, RingBuffer};
+/// let pool: Pool<usize> = Pool::builder()
+///     .max_size(64)
+///     .storage::<RingBuffer>()
+///     .build();
+/// for i in 0..1000 {
+///     let values: Vec<_> = (0..32).map(|j| PoolRef::new(&pool, i + j)).collect();
+///     assert_eq!(i + 31, *values[31]);
+/// }
+/// assert_eq!(32, pool.get_pool_size());
+/// ```
+///
+/// [PoolStorage]: trait.PoolStorage.html
+/// [VecQueue]: struct.VecQueue.html
+pub struct RingBuffer {
+    head: usize,
+    len: usize,
+    buffer: Box<[MaybeUninit<NonNull<u8>>]>,
+}
+
+unsafe impl Send for RingBuffer {}
+
+impl RingBuffer {
+    #[inline(always)]
+    fn mask(&self) -> usize {
+        self.buffer.len() - 1
+    }
+}
+
+unsafe impl PoolStorage for RingBuffer {
+    fn with_capacity(capacity: usize) -> Self {
+        RingBuffer {
+            head: 0,
+            len: 0,
+            buffer: vec![MaybeUninit::uninit(); capacity.max(1).next_power_of_two()]
+                .into_boxed_slice(),
+        }
+    }
+
+    #[inline(always)]
+    fn push(&mut self, chunk: NonNull<u8>) {
+        let index = (self.head + self.len) & self.mask();
+        self.buffer[index] = MaybeUninit::new(chunk);
+        self.len += 1;
+    }
+
+    #[inline(always)]
+    fn pop(&mut self) -> Option<NonNull<u8>> {
+        if self.len == 0 {
+            None
+        } else {
+            let chunk = unsafe { self.buffer[self.head].assume_init() };
+            self.head = (self.head + 1) & self.mask();
+            self.len -= 1;
+            Some(chunk)
+        }
+    }
+
+    #[inline(always)]
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    #[inline(always)]
+    fn is_full(&self) -> bool {
+        self.len == self.buffer.len()
+    }
+
+    fn for_each_chunk(&self, f: &mut dyn FnMut(NonNull<u8>)) {
+        for offset in 0..self.len {
+            let index = (self.head + offset) & self.mask();
+            f(unsafe { self.buffer[index].assume_init() });
+        }
+    }
+}
+
+impl Debug for RingBuffer {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        write!(f, "RingBuffer[{}/{}]", self.len, self.buffer.len())
+    }
+}
+
+/// A stack of up to `capacity` free chunks in an array which lives in the
+/// same allocation as the pool it belongs to, right after the pool itself.
+///
+/// This is what [`Pool::fixed()`][Pool::fixed] uses.
+///
+/// [Pool::fixed]: struct.Pool.html#method.fixed
+#[cfg(not(feature = "disable-pool"))]
+pub(crate) struct InlineStack {
+    slots: NonNull<MaybeUninit<NonNull<u8>>>,
+    len: usize,
+    capacity: usize,
+}
+
+#[cfg(not(feature = "disable-pool"))]
+impl InlineStack {
+    /// Construct a stack using the `capacity` slots starting at `slots`,
+    /// which must stay valid for as long as the stack does.
+    pub(crate) unsafe fn new(slots: NonNull<MaybeUninit<NonNull<u8>>>, capacity: usize) -> Self {
+        InlineStack {
+            slots,
+            len: 0,
+            capacity,
+        }
+    }
+
+    #[inline(always)]
+    fn push(&mut self, chunk: NonNull<u8>) {
+        debug_assert!(self.len < self.capacity);
+        unsafe {
+            self.slots
+                .as_ptr()
+                .add(self.len)
+                .write(MaybeUninit::new(chunk))
+        };
+        self.len += 1;
+    }
+
+    #[inline(always)]
+    fn pop(&mut self) -> Option<NonNull<u8>> {
+        if self.len == 0 {
+            None
+        } else {
+            self.len -= 1;
+            Some(unsafe { (*self.slots.as_ptr().add(self.len)).assume_init() })
+        }
+    }
+
+    #[cfg(feature = "debug-dump")]
+    fn for_each_chunk(&self, f: &mut dyn FnMut(NonNull<u8>)) {
+        for index in 0..self.len {
+            f(unsafe { (*self.slots.as_ptr().add(index)).assume_init() });
+        }
+    }
+}
+
+/// The storage in a pool, which avoids dynamic dispatch for the default
+/// storage and for inline storage.
+#[cfg(not(feature = "disable-pool"))]
+pub(crate) enum Storage {
+    Stack(VecStack),
+    Inline(InlineStack),
+    Custom(Box<dyn PoolStorage>),
+}
+
+#[cfg(not(feature = "disable-pool"))]
+impl Storage {
+    pub(crate) fn new(capacity: usize, custom: Option<StorageConstructor>) -> Self {
+        match custom {
+            Some(constructor) => Storage::Custom(constructor(capacity)),
+            None => Storage::Stack(VecStack::with_capacity(capacity)),
+        }
+    }
+
+    #[inline(always)]
+    pub(crate) fn push<A>(&mut self, chunk: ElementPointer<A>) {
+        #[cfg(feature = "sanitize")]
+        crate::sanitize::poison(chunk);
+        #[cfg(feature = "valgrind")]
+        crate::valgrind::make_noaccess(chunk);
+        match self {
+            Storage::Stack(stack) => stack.push(chunk.cast()),
+            Storage::Inline(stack) => stack.push(chunk.cast()),
+            Storage::Custom(storage) => storage.push(chunk.cast()),
+        }
+    }
+
+    #[inline(always)]
+    pub(crate) fn pop<A>(&mut self) -> Option<ElementPointer<A>> {
+        let chunk = match self {
+            Storage::Stack(stack) => stack.pop(),
+            Storage::Inline(stack) => stack.pop(),
+            Storage::Custom(storage) => storage.pop(),
+        }
+        .map(NonNull::cast);
+        #[cfg(feature = "sanitize")]
+        if let Some(chunk) = chunk {
+            crate::sanitize::unpoison(chunk);
+        }
+        #[cfg(feature = "valgrind")]
+        if let Some(chunk) = chunk {
+            crate::valgrind::make_defined(chunk);
+        }
+        chunk
+    }
+
+    #[inline(always)]
+    pub(crate) fn len(&self) -> usize {
+        match self {
+            Storage::Stack(stack) => stack.len(),
+            Storage::Inline(stack) => stack.len,
+            Storage::Custom(storage) => storage.len(),
+        }
+    }
+
+    #[inline(always)]
+    pub(crate) fn is_full(&self) -> bool {
+        match self {
+            Storage::Stack(_) => false,
+            Storage::Inline(stack) => stack.len == stack.capacity,
+            Storage::Custom(storage) => storage.is_full(),
+        }
+    }
+
+    /// Get the number of slots in the pool's allocation taken up by the
+    /// storage, if it's inline storage.
+    pub(crate) fn inline_capacity(&self) -> Option<usize> {
+        match self {
+            Storage::Inline(stack) => Some(stack.capacity),
+            _ => None,
+        }
+    }
+
+    #[cfg(feature = "debug-dump")]
+    pub(crate) fn for_each_chunk(&self, f: &mut dyn FnMut(NonNull<u8>)) {
+        match self {
+            Storage::Stack(stack) => stack.for_each_chunk(f),
+            Storage::Inline(stack) => stack.for_each_chunk(f),
+            Storage::Custom(storage) => storage.for_each_chunk(f),
+        }
+    }
+}
+
+/// A function constructing a boxed [`PoolStorage`][PoolStorage].
+#[cfg(not(feature = "disable-pool"))]
+pub(crate) type StorageConstructor = fn(usize) -> Box<dyn PoolStorage>;
+
+#[cfg(not(feature = "disable-pool"))]
+pub(crate) fn construct_storage<S: PoolStorage>(capacity: usize) -> Box<dyn PoolStorage> {
+    Box::new(S::with_capacity(capacity))
+}
@@ -0,0 +1,304 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+use std::fmt::{Debug, Display, Error, Formatter};
+use std::hash::{Hash, Hasher};
+use std::ops::{Deref, DerefMut};
+
+use crate::{CapacityError, PoolSlice, SlicePool};
+
+/// A pool allocated string with a fixed capacity.
+///
+/// The string lives in a [`PoolSlice`][PoolSlice] of bytes, so its capacity
+/// is the slice length of the [`SlicePool`][SlicePool] it was allocated from,
+/// and its memory goes back to that pool when it's dropped. Only the bytes
+/// the string's actually written are ever initialised, so constructing a
+/// string doesn't cost anything proportional to its capacity.
+///
+/// # Examples
+///
+/// ```rust
+/// # use refpool::{PoolString, SlicePool};
+/// let pool: SlicePool<u8> = SlicePool::new(64, 16);
+/// let mut string = PoolString::new(&pool);
+/// string.push_str("Hello");
+/// string.push(' ');
+/// string.push_str("Joe");
+/// assert_eq!("Hello Joe", string.as_str());
+/// assert_eq!(String::from("Hello Joe"), String::from(string));
+/// ```
+///
+/// [PoolSlice]: struct.PoolSlice.html
+/// [SlicePool]: struct.SlicePool.html
+pub struct PoolString {
+    // Only the first `len` bytes of the buffer are initialised, so it mustn't
+    // be dereferenced or cloned.
+    buffer: PoolSlice<u8>,
+    len: usize,
+}
+
+impl PoolString {
+    /// Construct an empty string.
+    pub fn new(pool: &SlicePool<u8>) -> Self {
+        PoolString {
+            buffer: unsafe { PoolSlice::uninit(pool) },
+            len: 0,
+        }
+    }
+
+    /// Construct a string containing a copy of `value`.
+    ///
+    /// If `value` doesn't fit in the pool's slices, you get a
+    /// [`CapacityError`][CapacityError].
+    ///
+    /// [CapacityError]: struct.CapacityError.html
+    pub fn try_from_str(pool: &SlicePool<u8>, value: &str) -> Result<Self, CapacityError> {
+        let mut string = Self::new(pool);
+        string.try_push_str(value)?;
+        Ok(string)
+    }
+
+    /// Get the number of bytes in the string.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Test whether the string is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Get the maximum number of bytes the string can hold.
+    pub fn capacity(&self) -> usize {
+        PoolString::pool(self).slice_len()
+    }
+
+    /// Get the number of bytes which can be added to the string before it's
+    /// full.
+    pub fn remaining_capacity(&self) -> usize {
+        self.capacity() - self.len
+    }
+
+    /// Get the string as a `&str`.
+    pub fn as_str(&self) -> &str {
+        unsafe {
+            std::str::from_utf8_unchecked(std::slice::from_raw_parts(
+                PoolSlice::as_ptr(&self.buffer),
+                self.len,
+            ))
+        }
+    }
+
+    /// Get the string as a `&mut str`.
+    pub fn as_mut_str(&mut self) -> &mut str {
+        unsafe {
+            std::str::from_utf8_unchecked_mut(std::slice::from_raw_parts_mut(
+                PoolSlice::as_mut_ptr(&mut self.buffer),
+                self.len,
+            ))
+        }
+    }
+
+    /// Append `value` to the end of the string.
+    ///
+    /// If there isn't room for all of `value`, the string is left unchanged
+    /// and you get a [`CapacityError`][CapacityError].
+    ///
+    /// [CapacityError]: struct.CapacityError.html
+    pub fn try_push_str(&mut self, value: &str) -> Result<(), CapacityError> {
+        let end = self.len + value.len();
+        if end > self.capacity() {
+            return Err(CapacityError);
+        }
+        unsafe {
+            std::ptr::copy_nonoverlapping(
+                value.as_ptr(),
+                PoolSlice::as_mut_ptr(&mut self.buffer).add(self.len),
+                value.len(),
+            )
+        };
+        self.len = end;
+        Ok(())
+    }
+
+    /// Append `value` to the end of the string.
+    ///
+    /// # Panics
+    ///
+    /// This panics if there isn't room for all of `value`.
+    pub fn push_str(&mut self, value: &str) {
+        self.try_push_str(value)
+            .expect("PoolString::push_str: capacity exceeded")
+    }
+
+    /// Append a character to the end of the string.
+    ///
+    /// If there isn't room for it, the string is left unchanged and you get a
+    /// [`CapacityError`][CapacityError].
+    ///
+    /// [CapacityError]: struct.CapacityError.html
+    pub fn try_push(&mut self, value: char) -> Result<(), CapacityError> {
+        self.try_push_str(value.encode_utf8(&mut [0; 4]))
+    }
+
+    /// Append a character to the end of the string.
+    ///
+    /// # Panics
+    ///
+    /// This panics if there isn't room for the character.
+    pub fn push(&mut self, value: char) {
+        self.try_push(value)
+            .expect("PoolString::push: capacity exceeded")
+    }
+
+    /// Remove the last character from the string and return it.
+    pub fn pop(&mut self) -> Option<char> {
+        let value = self.as_str().chars().next_back()?;
+        self.len -= value.len_utf8();
+        Some(value)
+    }
+
+    /// Shorten the string to `len` bytes.
+    ///
+    /// This does nothing if `len` is greater than the string's current
+    /// length.
+    ///
+    /// # Panics
+    ///
+    /// This panics if `len` doesn't lie on a `char` boundary.
+    pub fn truncate(&mut self, len: usize) {
+        if len < self.len {
+            assert!(
+                self.as_str().is_char_boundary(len),
+                "PoolString::truncate: not a char boundary"
+            );
+            self.len = len;
+        }
+    }
+
+    /// Remove the contents of the string.
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    /// Get a reference to the pool the string was allocated from.
+    pub fn pool(this: &Self) -> &SlicePool<u8> {
+        PoolSlice::pool(&this.buffer)
+    }
+}
+
+impl Clone for PoolString {
+    fn clone(&self) -> Self {
+        let mut string = PoolString::new(PoolString::pool(self));
+        string.push_str(self.as_str());
+        string
+    }
+}
+
+impl Deref for PoolString {
+    type Target = str;
+    fn deref(&self) -> &Self::Target {
+        self.as_str()
+    }
+}
+
+impl DerefMut for PoolString {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.as_mut_str()
+    }
+}
+
+impl AsRef<str> for PoolString {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl AsRef<[u8]> for PoolString {
+    fn as_ref(&self) -> &[u8] {
+        self.as_bytes()
+    }
+}
+
+impl Borrow<str> for PoolString {
+    fn borrow(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl From<PoolString> for String {
+    fn from(string: PoolString) -> Self {
+        string.as_str().to_owned()
+    }
+}
+
+impl From<&PoolString> for String {
+    fn from(string: &PoolString) -> Self {
+        string.as_str().to_owned()
+    }
+}
+
+impl std::fmt::Write for PoolString {
+    fn write_str(&mut self, value: &str) -> std::fmt::Result {
+        self.try_push_str(value).map_err(|_| Error)
+    }
+}
+
+impl PartialEq for PoolString {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl Eq for PoolString {}
+
+impl PartialEq<str> for PoolString {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<&str> for PoolString {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl PartialEq<String> for PoolString {
+    fn eq(&self, other: &String) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl PartialOrd for PoolString {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for PoolString {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.as_str().cmp(other.as_str())
+    }
+}
+
+impl Hash for PoolString {
+    fn hash<H: Hasher>(&self, hasher: &mut H) {
+        self.as_str().hash(hasher)
+    }
+}
+
+impl Display for PoolString {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        Display::fmt(self.as_str(), f)
+    }
+}
+
+impl Debug for PoolString {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        Debug::fmt(self.as_str(), f)
+    }
+}
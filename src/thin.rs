@@ -0,0 +1,308 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::alloc::{alloc, dealloc, handle_alloc_error, Layout};
+use std::borrow::{Borrow, BorrowMut};
+use std::cell::RefCell;
+use std::fmt::{Debug, Display, Error, Formatter};
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+use std::mem::{ManuallyDrop, MaybeUninit};
+use std::ops::{Deref, DerefMut};
+use std::ptr::NonNull;
+use std::rc::Rc;
+
+use crate::PoolDefault;
+
+fn alloc_chunk<A>() -> NonNull<A> {
+    let layout = Layout::new::<A>();
+    if layout.size() == 0 {
+        return NonNull::dangling();
+    }
+    NonNull::new(unsafe { alloc(layout) })
+        .unwrap_or_else(|| handle_alloc_error(layout))
+        .cast()
+}
+
+unsafe fn dealloc_chunk<A>(chunk: NonNull<A>) {
+    let layout = Layout::new::<A>();
+    if layout.size() != 0 {
+        dealloc(chunk.as_ptr().cast(), layout);
+    }
+}
+
+struct ThinPoolInner<A> {
+    max_size: usize,
+    stack: RefCell<Vec<NonNull<A>>>,
+}
+
+impl<A> Drop for ThinPoolInner<A> {
+    fn drop(&mut self) {
+        for chunk in self.stack.get_mut().drain(..) {
+            unsafe { dealloc_chunk(chunk) };
+        }
+    }
+}
+
+/// A pool of preallocated memory for [`PoolBoxThin`][PoolBoxThin]s.
+///
+/// Unlike a [`Pool`][Pool], whose values carry a reference to the pool they
+/// came from along with a reference count, the memory in a `ThinPool<A>` is
+/// sized exactly for an `A`, with no header at all. The price for this is
+/// that a [`PoolBoxThin`][PoolBoxThin] doesn't know where to return its
+/// memory, so you have to hand it back yourself with
+/// [`PoolBoxThin::drop_into()`][drop_into].
+///
+/// Like a [`SlicePool`][SlicePool], a `ThinPool` keeps its free memory on a
+/// plain stack and can't be configured with a [`PoolBuilder`][PoolBuilder],
+/// so it has no custom storage, statistics, overflow policies, groups, idle
+/// trimming, alignment or locked memory. It also isn't affected by the
+/// `disable-pool`, `sanitize`, `valgrind`, `debug-leaks` or `trace-alloc`
+/// feature flags.
+///
+/// # Examples
+///
+/// ```rust
+/// # use refpool::{PoolBoxThin, ThinPool};
+/// let pool: ThinPool<u64> = ThinPool::new(16);
+/// let number = PoolBoxThin::new(&pool, 1337);
+/// assert_eq!(1337, *number);
+/// PoolBoxThin::drop_into(number, &pool);
+/// assert_eq!(1, pool.get_pool_size());
+/// ```
+///
+/// [Pool]: struct.Pool.html
+/// [PoolBuilder]: struct.PoolBuilder.html
+/// [SlicePool]: struct.SlicePool.html
+/// [PoolBoxThin]: struct.PoolBoxThin.html
+/// [drop_into]: struct.PoolBoxThin.html#method.drop_into
+pub struct ThinPool<A> {
+    inner: Rc<ThinPoolInner<A>>,
+}
+
+impl<A> ThinPool<A> {
+    /// Construct a new pool with a given maximum size.
+    pub fn new(max_size: usize) -> Self {
+        ThinPool {
+            inner: Rc::new(ThinPoolInner {
+                max_size,
+                stack: RefCell::new(Vec::with_capacity(max_size)),
+            }),
+        }
+    }
+
+    /// Get the maximum size of the pool.
+    pub fn get_max_size(&self) -> usize {
+        self.inner.max_size
+    }
+
+    /// Get the current size of the pool.
+    pub fn get_pool_size(&self) -> usize {
+        self.inner.stack.borrow().len()
+    }
+
+    /// Fill the pool with empty allocations.
+    pub fn fill(&self) {
+        let mut stack = self.inner.stack.borrow_mut();
+        while stack.len() < self.inner.max_size {
+            stack.push(alloc_chunk());
+        }
+    }
+
+    /// Take a chunk from the pool, or from the system allocator if the pool
+    /// is empty.
+    fn pop(&self) -> NonNull<A> {
+        self.inner
+            .stack
+            .borrow_mut()
+            .pop()
+            .unwrap_or_else(alloc_chunk)
+    }
+
+    /// Give a chunk back to the pool, or to the system allocator if the pool
+    /// is full.
+    fn push(&self, chunk: NonNull<A>) {
+        let mut stack = self.inner.stack.borrow_mut();
+        if stack.len() < self.inner.max_size {
+            stack.push(chunk);
+        } else {
+            unsafe { dealloc_chunk(chunk) };
+        }
+    }
+}
+
+impl<A> Clone for ThinPool<A> {
+    fn clone(&self) -> Self {
+        ThinPool {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<A> Debug for ThinPool<A> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        write!(
+            f,
+            "ThinPool[{}/{}]:{:p}",
+            self.get_pool_size(),
+            self.inner.max_size,
+            Rc::as_ptr(&self.inner)
+        )
+    }
+}
+
+/// A unique pointer to a value of `A` allocated from a
+/// [`ThinPool`][ThinPool], with no per value overhead.
+///
+/// This works like a [`PoolBox`][PoolBox], except that it doesn't keep a
+/// reference to its pool, so the memory it points to is exactly the size of
+/// an `A`, and allocating and freeing values doesn't touch the pool's
+/// reference count. To give its memory back to the pool, pass it to
+/// [`PoolBoxThin::drop_into()`][drop_into] or
+/// [`PoolBoxThin::into_inner()`][into_inner]. If you just drop it, the value
+/// is dropped and its memory goes back to the system allocator instead.
+///
+/// Because it holds nothing but its value, a `PoolBoxThin` can be sent
+/// between threads when its value can, and returned to a pool on a different
+/// thread than the one it was allocated on.
+///
+/// [ThinPool]: struct.ThinPool.html
+/// [PoolBox]: struct.PoolBox.html
+/// [drop_into]: #method.drop_into
+/// [into_inner]: #method.into_inner
+pub struct PoolBoxThin<A> {
+    value: NonNull<A>,
+    _type: PhantomData<A>,
+}
+
+unsafe impl<A: Send> Send for PoolBoxThin<A> {}
+unsafe impl<A: Sync> Sync for PoolBoxThin<A> {}
+
+impl<A> PoolBoxThin<A> {
+    /// Construct a `PoolBoxThin` with a newly initialised value of `A`.
+    pub fn default(pool: &ThinPool<A>) -> Self
+    where
+        A: PoolDefault,
+    {
+        let value = pool.pop();
+        unsafe { A::default_uninit(&mut *value.as_ptr().cast::<MaybeUninit<A>>()) };
+        PoolBoxThin {
+            value,
+            _type: PhantomData,
+        }
+    }
+
+    /// Wrap a value in a `PoolBoxThin`.
+    pub fn new(pool: &ThinPool<A>, value: A) -> Self {
+        let chunk = pool.pop();
+        unsafe { chunk.as_ptr().write(value) };
+        PoolBoxThin {
+            value: chunk,
+            _type: PhantomData,
+        }
+    }
+
+    /// Drop the value and give its memory back to `pool`.
+    ///
+    /// The memory doesn't have to go back to the pool it came from, so long
+    /// as that's a pool for the same type.
+    pub fn drop_into(this: Self, pool: &ThinPool<A>) {
+        let this = ManuallyDrop::new(this);
+        unsafe { this.value.as_ptr().drop_in_place() };
+        pool.push(this.value);
+    }
+
+    /// Move the value out of the `PoolBoxThin` and give its memory back to
+    /// `pool`.
+    pub fn into_inner(this: Self, pool: &ThinPool<A>) -> A {
+        let this = ManuallyDrop::new(this);
+        let value = unsafe { this.value.as_ptr().read() };
+        pool.push(this.value);
+        value
+    }
+}
+
+impl<A> Drop for PoolBoxThin<A> {
+    fn drop(&mut self) {
+        unsafe {
+            self.value.as_ptr().drop_in_place();
+            dealloc_chunk(self.value);
+        }
+    }
+}
+
+impl<A> Deref for PoolBoxThin<A> {
+    type Target = A;
+    fn deref(&self) -> &Self::Target {
+        unsafe { self.value.as_ref() }
+    }
+}
+
+impl<A> DerefMut for PoolBoxThin<A> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        unsafe { self.value.as_mut() }
+    }
+}
+
+impl<A> AsRef<A> for PoolBoxThin<A> {
+    fn as_ref(&self) -> &A {
+        self.deref()
+    }
+}
+
+impl<A> AsMut<A> for PoolBoxThin<A> {
+    fn as_mut(&mut self) -> &mut A {
+        self.deref_mut()
+    }
+}
+
+impl<A> Borrow<A> for PoolBoxThin<A> {
+    fn borrow(&self) -> &A {
+        self.deref()
+    }
+}
+
+impl<A> BorrowMut<A> for PoolBoxThin<A> {
+    fn borrow_mut(&mut self) -> &mut A {
+        self.deref_mut()
+    }
+}
+
+impl<A> PartialEq for PoolBoxThin<A>
+where
+    A: PartialEq,
+{
+    fn eq(&self, other: &Self) -> bool {
+        **self == **other
+    }
+}
+
+impl<A> Eq for PoolBoxThin<A> where A: Eq {}
+
+impl<A> Hash for PoolBoxThin<A>
+where
+    A: Hash,
+{
+    fn hash<H: Hasher>(&self, hasher: &mut H) {
+        (**self).hash(hasher)
+    }
+}
+
+impl<A> Display for PoolBoxThin<A>
+where
+    A: Display,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        (**self).fmt(f)
+    }
+}
+
+impl<A> Debug for PoolBoxThin<A>
+where
+    A: Debug,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        (**self).fmt(f)
+    }
+}
@@ -0,0 +1,73 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::any::{Any, TypeId};
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use crate::pool::Pool;
+
+thread_local! {
+    static REGISTRY: RefCell<HashMap<TypeId, Box<dyn Any>>> = RefCell::new(HashMap::new());
+}
+
+/// Get the current thread's pool for `A`, installing a new one with the
+/// given maximum size if there isn't one.
+pub(crate) fn install<A: 'static>(max_size: usize) -> Pool<A> {
+    REGISTRY
+        .try_with(|registry| {
+            registry
+                .borrow_mut()
+                .entry(TypeId::of::<A>())
+                .or_insert_with(|| Box::new(Pool::<A>::new(max_size)))
+                .downcast_ref::<Pool<A>>()
+                .unwrap()
+                .clone()
+        })
+        .unwrap_or_else(|_| Pool::new(0))
+}
+
+/// Get the current thread's pool for `A`, or a null pool if there isn't one.
+#[cfg(feature = "thread-local")]
+pub(crate) fn current<A: 'static>() -> Pool<A> {
+    REGISTRY
+        .try_with(|registry| {
+            registry
+                .borrow()
+                .get(&TypeId::of::<A>())
+                .and_then(|pool| pool.downcast_ref::<Pool<A>>())
+                .cloned()
+        })
+        .ok()
+        .flatten()
+        .unwrap_or_else(|| Pool::new(0))
+}
+
+/// Allocate a value in a [`PoolRef`][PoolRef] from the current thread's pool
+/// for its type.
+///
+/// This is shorthand for [`PoolRef::new_tl(value)`][PoolRef::new_tl]. If
+/// there's no pool installed for the type using
+/// [`Pool::thread_local()`][Pool::thread_local], the value is allocated on
+/// the heap as usual.
+///
+/// # Examples
+///
+/// ```rust
+/// # use refpool::{pooled, Pool, PoolRef};
+/// Pool::<usize>::thread_local(1024);
+/// let number: PoolRef<usize> = pooled!(1337);
+/// assert_eq!(1337, *number);
+/// ```
+///
+/// [PoolRef]: struct.PoolRef.html
+/// [PoolRef::new_tl]: struct.PoolRef.html#method.new_tl
+/// [Pool::thread_local]: struct.Pool.html#method.thread_local
+#[cfg(feature = "thread-local")]
+#[macro_export]
+macro_rules! pooled {
+    ($value:expr) => {
+        $crate::PoolRef::new_tl($value)
+    };
+}
@@ -0,0 +1,199 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::cell::UnsafeCell;
+use std::fmt::{Debug, Error, Formatter};
+use std::sync::Mutex;
+use std::thread::{self, ThreadId};
+
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+
+use crate::pool::Pool;
+use crate::ref_handle::PoolRef;
+
+struct Slot<A> {
+    owner: Mutex<Option<ThreadId>>,
+    pool: UnsafeCell<Option<Pool<A>>>,
+}
+
+/// A set of pools, one for each worker thread of a [`rayon`][rayon] thread
+/// pool.
+///
+/// [`Pool`][Pool] is strictly thread local, so it can't be shared between
+/// the workers of a data-parallel computation. `ThreadLocalPools` hands each
+/// worker its own pool instead, creating it the first time the worker asks for
+/// it.
+///
+/// Pool handles are not [`Send`][Send], so values allocated from a worker's
+/// pool, and clones of the pool itself, must not outlive the call to
+/// [`with_pool()`][with_pool] which allocated them. This is checked at
+/// runtime, and leaking a handle out of the closure will abort the process.
+///
+/// # Examples
+///
+/// ```rust
+/// # use refpool::{PoolRef, ThreadLocalPools};
+/// let pools: ThreadLocalPools<usize> = ThreadLocalPools::new(1024);
+/// let sums: Vec<usize> = pools.par_alloc_batch(
+///     1000,
+///     |index| index * 2,
+///     |value| *value + 1,
+/// );
+/// assert_eq!(1, sums[0]);
+/// assert_eq!(1999, sums[999]);
+/// ```
+///
+/// [rayon]: https://docs.rs/rayon
+/// [Pool]: struct.Pool.html
+/// [Send]: https://doc.rust-lang.org/std/marker/trait.Send.html
+/// [with_pool]: #method.with_pool
+pub struct ThreadLocalPools<A> {
+    make_pool: Box<dyn Fn() -> Pool<A> + Send + Sync>,
+    slots: Vec<Slot<A>>,
+}
+
+// Each slot's pool is only ever touched by the thread which first claimed the
+// slot, and `with_pool` makes sure no pool handles survive beyond the closure
+// it's been given, aborting if they do, so the pools are never shared between
+// threads.
+unsafe impl<A: Send> Send for ThreadLocalPools<A> {}
+unsafe impl<A: Send> Sync for ThreadLocalPools<A> {}
+
+impl<A> ThreadLocalPools<A> {
+    /// Construct a set of pools with the given max size for the current rayon
+    /// thread pool.
+    pub fn new(max_size: usize) -> Self {
+        Self::from_fn(move || Pool::new(max_size))
+    }
+
+    /// Construct a set of pools for the current rayon thread pool, using a
+    /// function to construct each worker's pool.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use refpool::{Pool, ThreadLocalPools};
+    /// let pools: ThreadLocalPools<usize> =
+    ///     ThreadLocalPools::from_fn(|| Pool::builder().max_size(256).prefill(true).build());
+    /// # pools.with_pool(|pool| assert!(pool.get_max_size() == 256 || pool.get_max_size() == 0));
+    /// ```
+    pub fn from_fn<F>(make_pool: F) -> Self
+    where
+        F: Fn() -> Pool<A> + Send + Sync + 'static,
+    {
+        let slots = (0..rayon::current_num_threads())
+            .map(|_| Slot {
+                owner: Mutex::new(None),
+                pool: UnsafeCell::new(None),
+            })
+            .collect();
+        ThreadLocalPools {
+            make_pool: Box::new(make_pool),
+            slots,
+        }
+    }
+
+    /// Call a function with the current rayon worker's pool.
+    ///
+    /// The pool is created the first time a worker asks for it. If called from
+    /// outside the thread pool the `ThreadLocalPools` was created for, the
+    /// function is given an empty pool, which allocates from the heap.
+    ///
+    /// # Aborts
+    ///
+    /// This will abort the process if any values allocated from the pool, or
+    /// any clones of the pool, are still alive when `f` returns or unwinds.
+    /// A leaked handle could end up being dropped on another thread, which
+    /// would race with the worker on the pool's reference count, so it isn't
+    /// safe to unwind past it.
+    pub fn with_pool<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce(&Pool<A>) -> R,
+        R: Send,
+    {
+        let slot = match self.claim_slot() {
+            Some(slot) => slot.pool.get(),
+            None => return f(&Pool::new(0)),
+        };
+        // Only write to the slot if it's empty, so that we never create a
+        // mutable reference while a reentrant call might be holding on to the
+        // pool.
+        let pool = unsafe {
+            if (*slot).is_none() {
+                *slot = Some((self.make_pool)());
+            }
+            (*slot).as_ref().unwrap()
+        };
+        let _check = LeakCheck {
+            pool,
+            handles: pool.handle_count(),
+        };
+        f(pool)
+    }
+
+    /// Allocate `count` values in parallel, each from the pool of the rayon
+    /// worker which constructs it.
+    ///
+    /// The value at each index is constructed by `init`, and its handle is
+    /// passed to `finish` on the same worker thread, whose results are
+    /// returned in index order.
+    ///
+    /// This can't return the handles themselves, because a
+    /// [`PoolRef`][PoolRef] isn't [`Send`][Send] and has to be dropped on
+    /// the worker which allocated it, so `finish` has to turn each one into
+    /// something which can leave the worker, such as the result of a
+    /// computation on the value.
+    ///
+    /// [PoolRef]: struct.PoolRef.html
+    /// [Send]: https://doc.rust-lang.org/std/marker/trait.Send.html
+    pub fn par_alloc_batch<I, F, R>(&self, count: usize, init: I, finish: F) -> Vec<R>
+    where
+        A: Send,
+        I: Fn(usize) -> A + Send + Sync,
+        F: Fn(PoolRef<A>) -> R + Send + Sync,
+        R: Send,
+    {
+        (0..count)
+            .into_par_iter()
+            .map(|index| self.with_pool(|pool| finish(PoolRef::new(pool, init(index)))))
+            .collect()
+    }
+
+    fn claim_slot(&self) -> Option<&Slot<A>> {
+        let slot = self.slots.get(rayon::current_thread_index()?)?;
+        let current = thread::current().id();
+        let mut owner = slot.owner.lock().unwrap_or_else(|e| e.into_inner());
+        match *owner {
+            Some(id) if id != current => None,
+            _ => {
+                *owner = Some(current);
+                Some(slot)
+            }
+        }
+    }
+}
+
+/// Aborts if a pool has more handles than it had when the check was created.
+///
+/// This runs on unwinding as well as on return, because a panicking closure
+/// could just as well have leaked a handle before it panicked.
+struct LeakCheck<'a, A> {
+    pool: &'a Pool<A>,
+    handles: usize,
+}
+
+impl<'a, A> Drop for LeakCheck<'a, A> {
+    fn drop(&mut self) {
+        if self.pool.handle_count() != self.handles {
+            eprintln!("ThreadLocalPools::with_pool: pool handles must not outlive the closure");
+            std::process::abort();
+        }
+    }
+}
+
+impl<A> Debug for ThreadLocalPools<A> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        write!(f, "ThreadLocalPools[{}]", self.slots.len())
+    }
+}
@@ -0,0 +1,73 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+// With the `disable-pool` feature flag, there's no real pool to make use of
+// the crate internals here, only the fake pool which ignores them.
+#![cfg_attr(feature = "disable-pool", allow(dead_code))]
+
+use std::collections::HashMap;
+use std::panic::Location;
+
+/// Allocation counts for a single call site.
+///
+/// You get these from [`Pool::alloc_sites()`][alloc_sites].
+///
+/// [alloc_sites]: struct.Pool.html#method.alloc_sites
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct AllocSite {
+    /// The location in the source code which allocated from the pool.
+    pub location: &'static Location<'static>,
+    /// The number of values allocated from this location.
+    pub allocations: usize,
+    /// The number of those allocations which couldn't be served from the
+    /// pool, and had to fall back to the system allocator.
+    pub heap_fallbacks: usize,
+}
+
+/// Per call site allocation counts kept by a pool.
+#[derive(Debug, Default)]
+pub(crate) struct AllocSites {
+    sites: HashMap<&'static Location<'static>, (usize, usize)>,
+}
+
+impl AllocSites {
+    pub(crate) fn record(&mut self, location: &'static Location<'static>, heap_fallback: bool) {
+        let counts = self.sites.entry(location).or_default();
+        counts.0 += 1;
+        if heap_fallback {
+            counts.1 += 1;
+        }
+    }
+
+    pub(crate) fn to_vec(&self) -> Vec<AllocSite> {
+        let mut sites: Vec<AllocSite> = self
+            .sites
+            .iter()
+            .map(|(location, (allocations, heap_fallbacks))| AllocSite {
+                location,
+                allocations: *allocations,
+                heap_fallbacks: *heap_fallbacks,
+            })
+            .collect();
+        sites.sort_by(|left, right| {
+            right
+                .heap_fallbacks
+                .cmp(&left.heap_fallbacks)
+                .then(right.allocations.cmp(&left.allocations))
+                .then_with(|| {
+                    (
+                        left.location.file(),
+                        left.location.line(),
+                        left.location.column(),
+                    )
+                        .cmp(&(
+                            right.location.file(),
+                            right.location.line(),
+                            right.location.column(),
+                        ))
+                })
+        });
+        sites
+    }
+}
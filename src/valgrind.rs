@@ -0,0 +1,74 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Memcheck annotations for chunks of memory sitting in a pool.
+//!
+//! These are Valgrind client requests, which are a special sequence of no-op
+//! instructions that Valgrind recognises when it's running the program, and
+//! which do nothing at all when it isn't. They're only implemented for
+//! `x86_64`, and do nothing on other platforms.
+
+use std::mem::size_of;
+
+use crate::refbox::RefBox;
+use crate::types::ElementPointer;
+
+// Memcheck's client requests start at `('M' << 24) | ('C' << 16)`.
+const MAKE_MEM_NOACCESS: usize = 0x4d43_0000;
+const MAKE_MEM_DEFINED: usize = 0x4d43_0002;
+
+/// Make a client request, the same way `valgrind.h` does, returning
+/// `default` if we're not running under Valgrind.
+#[cfg(all(target_arch = "x86_64", unix))]
+#[inline(always)]
+unsafe fn client_request(default: usize, request: usize, addr: usize, size: usize) -> usize {
+    let args: [usize; 6] = [request, addr, size, 0, 0, 0];
+    let mut result = default;
+    std::arch::asm!(
+        "rol rdi, 3",
+        "rol rdi, 13",
+        "rol rdi, 61",
+        "rol rdi, 51",
+        "xchg rbx, rbx",
+        inout("rdx") result,
+        in("rax") args.as_ptr(),
+        inout("rdi") 0usize => _,
+    );
+    result
+}
+
+#[cfg(not(all(target_arch = "x86_64", unix)))]
+#[inline(always)]
+unsafe fn client_request(default: usize, _request: usize, _addr: usize, _size: usize) -> usize {
+    default
+}
+
+/// Mark a chunk as off limits while it's in the pool.
+#[inline(always)]
+pub(crate) fn make_noaccess<A>(chunk: ElementPointer<A>) {
+    unsafe {
+        client_request(
+            0,
+            MAKE_MEM_NOACCESS,
+            chunk.as_ptr() as usize,
+            size_of::<RefBox<A>>(),
+        )
+    };
+}
+
+/// Mark a chunk as usable again as it leaves the pool.
+///
+/// Its memory is marked as defined rather than undefined, because a
+/// recycling pool leaves live values in the chunks it holds.
+#[inline(always)]
+pub(crate) fn make_defined<A>(chunk: ElementPointer<A>) {
+    unsafe {
+        client_request(
+            0,
+            MAKE_MEM_DEFINED,
+            chunk.as_ptr() as usize,
+            size_of::<RefBox<A>>(),
+        )
+    };
+}